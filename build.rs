@@ -1,15 +1,49 @@
 use cc::Build;
-use std::env::consts::FAMILY;
+use std::env::{self, consts::FAMILY};
 
-/// Select the platform specific source file
-fn select_impl() -> &'static str {
+/// Select the platform specific source files
+fn select_impl() -> &'static [&'static str] {
     match FAMILY {
-        "unix" => "src/serial/unix.c",
+        "unix" => &[
+            "src/serial/unix.c",
+            "src/serial/linux.c",
+            "src/signal/unix.c",
+            "src/batch/unix.c",
+            "src/transport/unix.c",
+            "src/clock/unix.c",
+            "src/affinity/unix.c",
+            "src/sched/unix.c",
+            "src/multicast/unix.c",
+        ],
+        "windows" => &[
+            "src/serial/windows.c",
+            "src/signal/windows.c",
+            "src/batch/windows.c",
+            "src/transport/windows.c",
+            "src/clock/windows.c",
+            "src/affinity/windows.c",
+            "src/sched/windows.c",
+            "src/multicast/windows.c",
+        ],
         family => panic!("Unsupported target OS family: {family}"),
     }
 }
+/// Select the platform specific source files of the optional features
+fn select_features() -> &'static [&'static str] {
+    match (FAMILY, env::var_os("CARGO_FEATURE_SANDBOX").is_some()) {
+        ("unix", true) => &["src/sandbox/unix.c"],
+        ("windows", true) => &["src/sandbox/windows.c"],
+        _ => &[],
+    }
+}
 
 fn main() {
     // Build and link the helper shim
-    Build::new().file(select_impl()).warnings_into_errors(true).compile("serial");
+    Build::new().files(select_impl()).files(select_features()).warnings_into_errors(true).compile("serial");
+    if FAMILY == "windows" {
+        // Link the socket library for the transport shim
+        println!("cargo:rustc-link-lib=ws2_32");
+        // Link the registry library for the port enumeration
+        println!("cargo:rustc-link-lib=advapi32");
+    }
 }
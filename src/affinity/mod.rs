@@ -0,0 +1,36 @@
+//! Provides OS-specific CPU affinity
+
+use std::io;
+
+extern "C" {
+    // int64_t affinity_cpu_count(void)
+    fn affinity_cpu_count() -> i64;
+    // int32_t affinity_set(const uint64_t* cpus, uint64_t len)
+    fn affinity_set(cpus: *const u64, len: u64) -> i32;
+}
+
+/// The amount of CPUs configured in the system
+pub fn cpu_count() -> io::Result<usize> {
+    let count = unsafe { affinity_cpu_count() };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(count as usize)
+}
+
+/// Pins the calling thread and all threads spawned by it afterwards to the given CPUs
+///
+/// # Note
+/// This must be called before spawning any threads so that the affinity applies to the whole process. This fails with
+/// [`io::ErrorKind::Unsupported`] if CPU affinity is not supported on this platform (Linux and Windows only).
+pub fn apply(cpus: &[usize]) -> io::Result<()> {
+    let cpus: Vec<u64> = cpus.iter().map(|&cpu| cpu as u64).collect();
+    let result = unsafe { affinity_set(cpus.as_ptr(), cpus.len() as u64) };
+    if result == -2 {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "CPU affinity is not supported on this platform"));
+    }
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
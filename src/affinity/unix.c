@@ -0,0 +1,55 @@
+#ifdef __linux__
+#define _GNU_SOURCE
+#endif
+#include <stdint.h>
+#include <errno.h>
+#include <unistd.h>
+#ifdef __linux__
+#include <sched.h>
+#endif
+
+/**
+ * @brief Gets the amount of configured CPUs
+ *
+ * @return The amount of CPUs or `-1` on error
+ */
+int64_t affinity_cpu_count(void) {
+    long count = sysconf(_SC_NPROCESSORS_CONF);
+    if (count < 0) {
+        return -1;
+    }
+    return (int64_t)count;
+}
+
+/**
+ * @brief Pins the calling thread to the given CPUs; threads spawned afterwards inherit the affinity
+ *
+ * @param cpus The indices of the CPUs
+ * @param len The amount of CPU indices
+ * @return `0`, `-1` on error or `-2` if CPU affinity is not supported on this platform
+ */
+int32_t affinity_set(const uint64_t* cpus, uint64_t len) {
+#ifdef __linux__
+    // Assemble the CPU set
+    cpu_set_t set;
+    CPU_ZERO(&set);
+    for (uint64_t index = 0; index < len; index++) {
+        if (cpus[index] >= CPU_SETSIZE) {
+            errno = EINVAL;
+            return -1;
+        }
+        CPU_SET(cpus[index], &set);
+    }
+
+    // Apply the CPU set
+    if (sched_setaffinity(0, sizeof(set), &set) != 0) {
+        return -1;
+    }
+    return 0;
+#else
+    // CPU affinity is not supported on this platform
+    (void)cpus;
+    (void)len;
+    return -2;
+#endif
+}
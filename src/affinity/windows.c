@@ -0,0 +1,38 @@
+#include <stdint.h>
+#include <windows.h>
+
+/**
+ * @brief Gets the amount of configured CPUs
+ *
+ * @return The amount of CPUs or `-1` on error
+ */
+int64_t affinity_cpu_count(void) {
+    SYSTEM_INFO info;
+    GetSystemInfo(&info);
+    return (int64_t)info.dwNumberOfProcessors;
+}
+
+/**
+ * @brief Pins the process to the given CPUs
+ *
+ * @param cpus The indices of the CPUs
+ * @param len The amount of CPU indices
+ * @return `0`, `-1` on error or `-2` if CPU affinity is not supported on this platform
+ */
+int32_t affinity_set(const uint64_t* cpus, uint64_t len) {
+    // Assemble the affinity mask
+    DWORD_PTR mask = 0;
+    for (uint64_t index = 0; index < len; index++) {
+        if (cpus[index] >= sizeof(DWORD_PTR) * 8) {
+            SetLastError(ERROR_INVALID_PARAMETER);
+            return -1;
+        }
+        mask |= (DWORD_PTR)1 << cpus[index];
+    }
+
+    // Apply the affinity mask
+    if (!SetProcessAffinityMask(GetCurrentProcess(), mask)) {
+        return -1;
+    }
+    return 0;
+}
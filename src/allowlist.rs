@@ -0,0 +1,76 @@
+//! Implements the source address allowlist for UDP->serial traffic
+
+use crate::error::Error;
+use std::net::IpAddr;
+
+/// An IP network given as address and prefix length
+#[derive(Debug, Clone, Copy)]
+struct Network {
+    /// The network address
+    address: IpAddr,
+    /// The amount of leading bits that must match
+    prefix_len: u32,
+}
+impl Network {
+    /// Parses a network from a CIDR notation like `192.168.1.0/24` or a plain IP address like `10.0.0.1`
+    fn parse(network: &str) -> Result<Self, Error> {
+        let (address, prefix_len) = match network.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (network, None),
+        };
+
+        // Parse the address and prefix length
+        let address: IpAddr = (address.parse())
+            .map_err(|_| eio!("Invalid source address {network} (expected an IP address or network)"))?;
+        let prefix_max = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len.map(str::parse::<u32>) {
+            None => prefix_max,
+            Some(Ok(prefix_len)) if prefix_len <= prefix_max => prefix_len,
+            Some(_) => {
+                return Err(eio!("Invalid prefix length in source network {network} (expected 0 to {prefix_max})"))
+            }
+        };
+        Ok(Self { address, prefix_len })
+    }
+
+    /// Whether the network contains the address
+    fn contains(&self, address: IpAddr) -> bool {
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len).unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len).unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The source addresses that may send datagrams to the serial device
+#[derive(Debug)]
+pub struct SourceAllowlist {
+    /// The allowed networks
+    networks: Vec<Network>,
+}
+impl SourceAllowlist {
+    /// Creates a new allowlist from CIDR networks or plain IP addresses
+    pub fn new<T>(networks: &[T]) -> Result<Self, Error>
+    where
+        T: AsRef<str>,
+    {
+        let networks = networks.iter().map(|network| Network::parse(network.as_ref())).collect::<Result<_, _>>()?;
+        Ok(Self { networks })
+    }
+
+    /// Whether the address is allowed
+    ///
+    /// # Note
+    /// IPv4-mapped IPv6 addresses as seen on dual-stack sockets are matched as IPv4 addresses.
+    pub fn allows(&self, address: IpAddr) -> bool {
+        let address = address.to_canonical();
+        self.networks.iter().any(|network| network.contains(address))
+    }
+}
@@ -0,0 +1,52 @@
+//! Implements a best-effort automatic baudrate detection
+
+use crate::{
+    config::{self, AutobaudScoring, BytePattern},
+    error::Error,
+    serial::SerialDevice,
+};
+use std::time::Duration;
+
+/// The maximum amount of bytes to sample per candidate
+const SAMPLE_SIZE_MAX: usize = 4096;
+
+/// Detects the baudrate by sampling the input at each candidate and returns the most plausible candidate, or `None` if
+/// no candidate yielded plausible data
+///
+/// # Note
+/// This is a heuristic; it requires the device to send data during the sampling windows, and rates that are integer
+/// multiples of each other may yield similar scores for some data.
+pub fn detect(serial: &SerialDevice, config: &config::Autobaud) -> Result<Option<u64>, Error> {
+    let (mut best, mut buf) = (None, vec![0; SAMPLE_SIZE_MAX]);
+    for &candidate in &config.candidates {
+        // Sample the input at the candidate baudrate
+        serial.set_baudrate(candidate)?;
+        let sampled = serial.read_window(&mut buf, Duration::from_millis(config.window_ms))?;
+
+        // Score the sample and keep the best candidate
+        let score = score(&buf[..sampled], config);
+        debug!("Autobaud: candidate {candidate} scored {score:.3} ({sampled} bytes)");
+        if score > 0.0 && best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((candidate, score));
+        }
+    }
+    Ok(best.map(|(candidate, _)| candidate))
+}
+
+/// Scores a sample
+fn score(sample: &[u8], config: &config::Autobaud) -> f64 {
+    match (config.scoring, config.pattern.as_ref().map(BytePattern::as_bytes)) {
+        (AutobaudScoring::Printable, _) if sample.is_empty() => 0.0,
+        (AutobaudScoring::Printable, _) => {
+            // The ratio of printable ASCII characters
+            let is_printable = |byte: &&u8| byte.is_ascii_graphic() || b" \t\r\n".contains(byte);
+            let printable = sample.iter().filter(is_printable).count();
+            printable as f64 / sample.len() as f64
+        }
+        (AutobaudScoring::Pattern, Some(pattern)) if !pattern.is_empty() => {
+            // The amount of pattern occurrences
+            sample.windows(pattern.len()).filter(|candidate| *candidate == pattern).count() as f64
+        }
+        (AutobaudScoring::Pattern, _) => 0.0,
+    }
+}
@@ -0,0 +1,103 @@
+//! Provides OS-specific batch receiving of UDP datagrams
+
+#[cfg(unix)]
+use std::os::fd::AsRawFd;
+#[cfg(windows)]
+use std::os::windows::io::AsRawSocket;
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, UdpSocket},
+};
+
+/// A sender address
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct BatchAddr {
+    /// The IP version (`4` or `6`)
+    version: u32,
+    /// The port in host byte order
+    port: u16,
+    /// The IP address in network byte order (IPv4 addresses use the first four bytes)
+    ip: [u8; 16],
+    /// The IPv6 flow info
+    flowinfo: u32,
+    /// The IPv6 scope ID
+    scope_id: u32,
+}
+impl BatchAddr {
+    /// Converts the sender address into a socket address
+    fn to_socket_addr(self) -> SocketAddr {
+        match self.version {
+            6 => SocketAddrV6::new(Ipv6Addr::from(self.ip), self.port, self.flowinfo, self.scope_id).into(),
+            _ => {
+                let [a, b, c, d, ..] = self.ip;
+                SocketAddrV4::new(Ipv4Addr::new(a, b, c, d), self.port).into()
+            }
+        }
+    }
+}
+
+extern "C" {
+    // int64_t batch_recv(int64_t fd, uint8_t* buf, uint64_t slot_size, uint64_t slots, uint64_t* lengths,
+    //     batch_addr_t* addrs)
+    fn batch_recv(fd: i64, buf: *mut u8, slot_size: u64, slots: u64, lengths: *mut u64, addrs: *mut BatchAddr) -> i64;
+}
+
+/// Receives batches of datagrams with a single syscall where supported
+#[derive(Debug)]
+pub struct BatchReceiver {
+    /// The size of each slot
+    slot_size: usize,
+    /// The datagram buffer
+    buf: Vec<u8>,
+    /// The datagram lengths
+    lengths: Vec<u64>,
+    /// The sender addresses
+    addrs: Vec<BatchAddr>,
+}
+impl BatchReceiver {
+    /// The maximum amount of datagrams per batch
+    pub const SLOTS_MAX: usize = 64;
+
+    /// Creates a new batch receiver for up to `slots` datagrams of up to `slot_size` bytes each
+    pub fn new(slots: usize, slot_size: usize) -> Self {
+        let slots = slots.clamp(1, Self::SLOTS_MAX);
+        Self {
+            slot_size,
+            buf: vec![0; slots * slot_size],
+            lengths: vec![0; slots],
+            addrs: vec![BatchAddr::default(); slots],
+        }
+    }
+
+    /// Receives a batch of datagrams, blocking until at least one datagram is available, and returns the amount of
+    /// datagrams received
+    ///
+    /// # Note
+    /// This fails with [`io::ErrorKind::Unsupported`] if batch receiving is not supported on this platform.
+    pub fn recv(&mut self, socket: &UdpSocket) -> io::Result<usize> {
+        #[cfg(unix)]
+        let fd = socket.as_raw_fd() as i64;
+        #[cfg(windows)]
+        let fd = socket.as_raw_socket() as i64;
+
+        let slots = self.lengths.len() as u64;
+        let (buf, lengths, addrs) = (self.buf.as_mut_ptr(), self.lengths.as_mut_ptr(), self.addrs.as_mut_ptr());
+        let received = unsafe { batch_recv(fd, buf, self.slot_size as u64, slots, lengths, addrs) };
+        if received == -2 {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "Batch receiving is not supported"));
+        }
+        if received < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno);
+        }
+        Ok(received as usize)
+    }
+
+    /// Gets the datagram and sender address in the given slot of the most recent batch
+    pub fn get_mut(&mut self, slot: usize) -> (&mut [u8], SocketAddr) {
+        let start = slot * self.slot_size;
+        let len = (self.lengths[slot] as usize).min(self.slot_size);
+        (&mut self.buf[start..start + len], self.addrs[slot].to_socket_addr())
+    }
+}
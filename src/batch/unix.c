@@ -0,0 +1,110 @@
+#ifdef __linux__
+#define _GNU_SOURCE
+#endif
+#include <stdint.h>
+#include <string.h>
+#include <errno.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+
+/**
+ * @brief A sender address
+ */
+typedef struct {
+    /// The IP version (`4` or `6`)
+    uint32_t version;
+    /// The port in host byte order
+    uint16_t port;
+    /// The IP address in network byte order (IPv4 addresses use the first four bytes)
+    uint8_t ip[16];
+    /// The IPv6 flow info
+    uint32_t flowinfo;
+    /// The IPv6 scope ID
+    uint32_t scope_id;
+} batch_addr_t;
+
+#ifdef __linux__
+/// The maximum amount of datagrams per batch
+#define BATCH_SLOTS_MAX 64
+
+/**
+ * @brief Converts a socket address into a sender address
+ * 
+ * @param storage The socket address
+ * @param addr The target sender address
+ */
+static void batch_convert_addr(const struct sockaddr_storage* storage, batch_addr_t* addr) {
+    memset(addr, 0, sizeof(batch_addr_t));
+    if (storage->ss_family == AF_INET) {
+        const struct sockaddr_in* in = (const struct sockaddr_in*)storage;
+        addr->version = 4;
+        addr->port = ntohs(in->sin_port);
+        memcpy(addr->ip, &in->sin_addr, 4);
+    } else if (storage->ss_family == AF_INET6) {
+        const struct sockaddr_in6* in6 = (const struct sockaddr_in6*)storage;
+        addr->version = 6;
+        addr->port = ntohs(in6->sin6_port);
+        memcpy(addr->ip, &in6->sin6_addr, 16);
+        addr->flowinfo = in6->sin6_flowinfo;
+        addr->scope_id = in6->sin6_scope_id;
+    }
+}
+#endif
+
+/**
+ * @brief Receives a batch of datagrams with a single syscall, blocking until at least one datagram is available
+ * 
+ * @param fd The socket file descriptor
+ * @param buf The target buffer which is split into `slots` slots of `slot_size` bytes each
+ * @param slot_size The size of each slot
+ * @param slots The amount of slots (at most 64)
+ * @param lengths The target buffer for the datagram lengths
+ * @param addrs The target buffer for the sender addresses
+ * @return The amount of datagrams received, `-1` on error or `-2` if batch receiving is not supported
+ */
+int64_t batch_recv(int64_t fd, uint8_t* buf, uint64_t slot_size, uint64_t slots, uint64_t* lengths,
+    batch_addr_t* addrs) {
+#ifdef __linux__
+    // Validate the amount of slots
+    if (slots == 0 || slots > BATCH_SLOTS_MAX) {
+        errno = EINVAL;
+        return -1;
+    }
+
+    // Prepare the message headers
+    struct mmsghdr headers[BATCH_SLOTS_MAX];
+    struct iovec iovecs[BATCH_SLOTS_MAX];
+    struct sockaddr_storage storages[BATCH_SLOTS_MAX];
+    memset(headers, 0, sizeof(headers));
+    for (uint64_t slot = 0; slot < slots; slot++) {
+        iovecs[slot].iov_base = buf + slot * slot_size;
+        iovecs[slot].iov_len = slot_size;
+        headers[slot].msg_hdr.msg_iov = &iovecs[slot];
+        headers[slot].msg_hdr.msg_iovlen = 1;
+        headers[slot].msg_hdr.msg_name = &storages[slot];
+        headers[slot].msg_hdr.msg_namelen = sizeof(struct sockaddr_storage);
+    }
+
+    // Receive the datagrams
+    int received = recvmmsg((int)fd, headers, (unsigned int)slots, MSG_WAITFORONE, NULL);
+    if (received < 0) {
+        return -1;
+    }
+
+    // Copy the lengths and sender addresses
+    for (int slot = 0; slot < received; slot++) {
+        lengths[slot] = headers[slot].msg_len;
+        batch_convert_addr(&storages[slot], &addrs[slot]);
+    }
+    return received;
+#else
+    // Batch receiving is not supported on this platform
+    (void)fd;
+    (void)buf;
+    (void)slot_size;
+    (void)slots;
+    (void)lengths;
+    (void)addrs;
+    return -2;
+#endif
+}
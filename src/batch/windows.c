@@ -0,0 +1,39 @@
+#include <stdint.h>
+
+/**
+ * @brief A sender address
+ */
+typedef struct {
+    /// The IP version (`4` or `6`)
+    uint32_t version;
+    /// The port in host byte order
+    uint16_t port;
+    /// The IP address in network byte order (IPv4 addresses use the first four bytes)
+    uint8_t ip[16];
+    /// The IPv6 flow info
+    uint32_t flowinfo;
+    /// The IPv6 scope ID
+    uint32_t scope_id;
+} batch_addr_t;
+
+/**
+ * @brief Receives a batch of datagrams with a single syscall, blocking until at least one datagram is available
+ *
+ * @param fd The socket handle
+ * @param buf The target buffer which is split into `slots` slots of `slot_size` bytes each
+ * @param slot_size The size of each slot
+ * @param slots The amount of slots (at most 64)
+ * @param lengths The target buffer for the datagram lengths
+ * @param addrs The target buffer for the sender addresses
+ * @return `-2` since batch receiving is not supported on this platform
+ */
+int64_t batch_recv(int64_t fd, uint8_t* buf, uint64_t slot_size, uint64_t slots, uint64_t* lengths,
+    batch_addr_t* addrs) {
+    (void)fd;
+    (void)buf;
+    (void)slot_size;
+    (void)slots;
+    (void)lengths;
+    (void)addrs;
+    return -2;
+}
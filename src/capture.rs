@@ -0,0 +1,77 @@
+//! Implements a capture file for the serial output
+
+use crate::{
+    config::{self, CaptureFormat},
+    error::Error,
+};
+use std::{fs::File, io::Write, sync::Mutex, time::Instant};
+
+/// The capture file state
+#[derive(Debug)]
+struct State {
+    /// The capture file, or `None` if capturing has been disabled after an error
+    file: Option<File>,
+    /// The time of the previous chunk
+    previous: Instant,
+    /// The buffer to assemble a record
+    record: Vec<u8>,
+}
+
+/// Writes the chunks read from the serial device to a capture file
+///
+/// # Timed format
+/// The timed format starts with the magic bytes `SSCAPT01`, followed by one record per chunk. Each record consists of
+/// the time since the previous chunk (or since the capture start for the first chunk) in microseconds as 64 bit
+/// big-endian integer, the chunk length in bytes as 32 bit big-endian integer, and the chunk itself.
+#[derive(Debug)]
+pub struct Capture {
+    /// The capture format
+    format: CaptureFormat,
+    /// The capture file state
+    state: Mutex<State>,
+}
+impl Capture {
+    /// The magic bytes at the start of a timed capture file
+    pub const TIMED_MAGIC: &'static [u8; 8] = b"SSCAPT01";
+
+    /// Creates a new capture file, truncating any existing file
+    pub fn new(path: &str, config: &config::Capture) -> Result<Self, Error> {
+        let mut file = File::create(path).map_err(|e| eio!("Failed to create the capture file {path} ({e})"))?;
+        if config.format == CaptureFormat::Timed {
+            file.write_all(Self::TIMED_MAGIC)?;
+        }
+
+        // Setup the state
+        let state = State { file: Some(file), previous: Instant::now(), record: Vec::new() };
+        Ok(Self { format: config.format, state: Mutex::new(state) })
+    }
+
+    /// Appends a chunk to the capture file
+    ///
+    /// # Note
+    /// Capturing is disabled with a warning if the capture file cannot be written so that the forwarding is unaffected.
+    pub fn record(&self, chunk: &[u8]) {
+        let mut state = self.state.lock().expect("Capture state is poisoned");
+        let State { file, previous, record } = &mut *state;
+        let Some(file_) = file.as_mut() else {
+            return;
+        };
+
+        // Assemble the record
+        let now = Instant::now();
+        record.clear();
+        if self.format == CaptureFormat::Timed {
+            let delta_us = now.duration_since(*previous).as_micros() as u64;
+            record.extend_from_slice(&delta_us.to_be_bytes());
+            record.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+        }
+        record.extend_from_slice(chunk);
+        *previous = now;
+
+        // Write the record
+        if let Err(e) = file_.write_all(record) {
+            warn!("failed to write the capture file ({e}); disabling the capture");
+            *file = None;
+        }
+    }
+}
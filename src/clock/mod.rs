@@ -0,0 +1,26 @@
+//! Provides OS-specific clocks for timestamps
+
+use crate::config::TimestampClock;
+use std::{io, time::Duration};
+
+extern "C" {
+    // int32_t clock_now(uint32_t clock, uint64_t* secs, uint32_t* nanos)
+    fn clock_now(clock: u32, secs: *mut u64, nanos: *mut u32) -> i32;
+}
+
+/// Reads the given clock and returns the time since the clock's epoch
+pub fn now(clock: TimestampClock) -> io::Result<Duration> {
+    let clock_id = match clock {
+        TimestampClock::Realtime => 0,
+        TimestampClock::Monotonic => 1,
+        TimestampClock::Boottime => 2,
+    };
+
+    // Read the clock
+    let (mut secs, mut nanos) = (0, 0);
+    let result = unsafe { clock_now(clock_id, &mut secs, &mut nanos) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Duration::new(secs, nanos))
+}
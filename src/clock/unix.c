@@ -0,0 +1,41 @@
+#include <stdint.h>
+#include <errno.h>
+#include <time.h>
+
+/**
+ * @brief Reads the given clock
+ * 
+ * @param clock The clock to read (`0` for realtime, `1` for monotonic, `2` for boottime)
+ * @param secs The target for the seconds since the clock's epoch
+ * @param nanos The target for the nanoseconds within the current second
+ * @return `0` or `-1` on error (`EINVAL` if the clock is not supported on this platform)
+ */
+int32_t clock_now(uint32_t clock, uint64_t* secs, uint32_t* nanos) {
+    // Select the clock
+    clockid_t id;
+    switch (clock) {
+        case 0:
+            id = CLOCK_REALTIME;
+            break;
+        case 1:
+            id = CLOCK_MONOTONIC;
+            break;
+#ifdef CLOCK_BOOTTIME
+        case 2:
+            id = CLOCK_BOOTTIME;
+            break;
+#endif
+        default:
+            errno = EINVAL;
+            return -1;
+    }
+
+    // Read the clock
+    struct timespec now;
+    if (clock_gettime(id, &now) != 0) {
+        return -1;
+    }
+    *secs = (uint64_t)now.tv_sec;
+    *nanos = (uint32_t)now.tv_nsec;
+    return 0;
+}
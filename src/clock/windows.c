@@ -0,0 +1,41 @@
+#include <stdint.h>
+#include <windows.h>
+
+/// The offset between the Windows epoch (1601-01-01) and the UNIX epoch in 100ns intervals
+#define CLOCK_EPOCH_OFFSET 116444736000000000ULL
+
+/**
+ * @brief Reads the given clock
+ *
+ * @param clock The clock to read (`0` for realtime, `1` for monotonic, `2` for boottime)
+ * @param secs The target for the seconds since the clock's epoch
+ * @param nanos The target for the nanoseconds within the current second
+ * @return `0` or `-1` on error (`ERROR_NOT_SUPPORTED` if the clock is not supported on this platform)
+ */
+int32_t clock_now(uint32_t clock, uint64_t* secs, uint32_t* nanos) {
+    switch (clock) {
+        case 0: {
+            // Read the system time in 100ns intervals since the Windows epoch
+            FILETIME now;
+            GetSystemTimePreciseAsFileTime(&now);
+            uint64_t intervals = ((uint64_t)now.dwHighDateTime << 32 | now.dwLowDateTime) - CLOCK_EPOCH_OFFSET;
+            *secs = intervals / 10000000;
+            *nanos = (uint32_t)(intervals % 10000000) * 100;
+            return 0;
+        }
+        case 1: {
+            // Read the performance counter
+            LARGE_INTEGER counter, frequency;
+            if (!QueryPerformanceCounter(&counter) || !QueryPerformanceFrequency(&frequency)) {
+                return -1;
+            }
+            *secs = (uint64_t)counter.QuadPart / (uint64_t)frequency.QuadPart;
+            uint64_t remainder = (uint64_t)counter.QuadPart % (uint64_t)frequency.QuadPart;
+            *nanos = (uint32_t)(remainder * 1000000000 / (uint64_t)frequency.QuadPart);
+            return 0;
+        }
+        default:
+            SetLastError(ERROR_NOT_SUPPORTED);
+            return -1;
+    }
+}
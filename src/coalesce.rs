@@ -0,0 +1,71 @@
+//! Implements write coalescing for the UDP->serial direction
+
+use std::{
+    mem,
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The coalesced frames and the arrival time of the oldest one
+#[derive(Debug, Default)]
+struct State {
+    /// The coalesced frames
+    buf: Vec<u8>,
+    /// The arrival time of the oldest frame
+    started: Option<Instant>,
+}
+
+/// Accumulates many small frames so that they are written to the serial device in a single write and flush
+#[derive(Debug)]
+pub struct WriteCoalescer {
+    /// The coalesced frames
+    state: Mutex<State>,
+    /// Signals that frames have been added or taken
+    changed: Condvar,
+    /// The maximum time to hold the oldest frame
+    window: Duration,
+    /// The size at which the coalesced frames are released early
+    max_bytes: usize,
+}
+impl WriteCoalescer {
+    /// Creates a new write coalescer
+    pub fn new(window: Duration, max_bytes: usize) -> Self {
+        Self { state: Mutex::default(), changed: Condvar::new(), window, max_bytes }
+    }
+
+    /// Appends a frame, waiting until the previous frames have been taken if the coalescer is full
+    pub fn push(&self, frame: &[u8]) {
+        let state = self.state.lock().expect("Write coalescer is poisoned");
+        let mut state = (self.changed.wait_while(state, |state| state.buf.len() >= self.max_bytes))
+            .expect("Write coalescer is poisoned");
+
+        // Append the frame
+        state.started.get_or_insert_with(Instant::now);
+        state.buf.extend_from_slice(frame);
+        self.changed.notify_all();
+    }
+
+    /// Waits until the window of the oldest frame has elapsed or the coalescer is full, swaps the coalesced frames into
+    /// `batch` and returns the arrival time of the oldest frame
+    pub fn pop(&self, batch: &mut Vec<u8>) -> Instant {
+        let mut state = self.state.lock().expect("Write coalescer is poisoned");
+        loop {
+            // Wait for a frame
+            let Some(started) = state.started else {
+                state = self.changed.wait(state).expect("Write coalescer is poisoned");
+                continue;
+            };
+
+            // Wait until the window has elapsed or the coalescer is full
+            let (release, now) = (started + self.window, Instant::now());
+            if now >= release || state.buf.len() >= self.max_bytes {
+                batch.clear();
+                mem::swap(&mut state.buf, batch);
+                state.started = None;
+                self.changed.notify_all();
+                return started;
+            }
+            (state, _) = self.changed.wait_timeout(state, release - now).expect("Write coalescer is poisoned");
+        }
+    }
+}
@@ -1,70 +1,1318 @@
 //! Implements a config object
 
-use crate::error::Error;
-use serde::Deserialize;
-use std::{env, fs, path::Path};
+use crate::{
+    allowlist::SourceAllowlist,
+    batch::BatchReceiver,
+    destination::Destination,
+    error::{Error, ErrorCategory},
+    json,
+    pcap::PcapWriter,
+    yaml,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs},
+    path::Path,
+    str,
+};
+
+/// The serial framing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Framing {
+    /// No framing; chunks are forwarded as-is
+    #[default]
+    Raw,
+    /// Frames are terminated by a delimiter
+    Line,
+    /// Frames are preceded by a fixed-size header giving the payload length
+    LengthPrefixed,
+}
+
+/// How datagrams are mapped onto serial frames
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UdpToSerialFraming {
+    /// Each datagram is written as-is and thus forms exactly one frame
+    #[default]
+    Preserve,
+    /// Each datagram is re-encoded with the configured serial framing
+    Reframe,
+}
+
+/// A datagram header field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeaderFieldKind {
+    /// The payload length in bytes
+    Length,
+    /// A sequence number that is incremented for each outgoing datagram
+    Sequence,
+    /// The time of sending in milliseconds since the Unix epoch
+    Timestamp,
+    /// The configured source ID
+    SourceId,
+}
+
+/// The byte order of a header field or length prefix
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Endianness {
+    /// Most significant byte first
+    #[default]
+    Big,
+    /// Least significant byte first
+    Little,
+}
+
+/// The primary transport
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    /// UDP with an optional additional TCP transport
+    #[default]
+    Udp,
+    /// TCP only; UDP is disabled
+    Tcp,
+}
+
+/// The serial flow control
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlowControl {
+    /// No flow control
+    #[default]
+    None,
+    /// Hardware flow control via the RTS/CTS lines
+    RtsCts,
+    /// Software flow control via XON/XOFF characters
+    XonXoff,
+}
+
+/// The serial parity
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Parity {
+    /// No parity bit
+    #[default]
+    None,
+    /// An even parity bit
+    Even,
+    /// An odd parity bit
+    Odd,
+}
+
+/// How flushing the serial device behaves
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlushMode {
+    /// Wait until all output has been physically transmitted
+    #[default]
+    Drain,
+    /// Return as soon as the output has been passed to the kernel without waiting for the transmission
+    None,
+}
+
+/// How data is handled if a bounded buffer is full
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overflow {
+    /// Wait until there is space, applying backpressure to the producer (e.g. via flow control for serial output)
+    Block,
+    /// Drop the new output
+    DropNew,
+    /// Drop the oldest buffered output
+    #[default]
+    DropOld,
+}
+
+/// The log line timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Timestamp {
+    /// The RFC 3339 wall-clock time in UTC
+    Wallclock,
+    /// The microseconds since server start from a monotonic clock
+    MonotonicUs,
+    /// Both the wall-clock time and the monotonic microseconds
+    Both,
+}
+
+/// The clock used for timestamps
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampClock {
+    /// The wall-clock time since the Unix epoch
+    #[default]
+    Realtime,
+    /// A monotonic clock that is immune to clock adjustments but does not advance during suspend
+    Monotonic,
+    /// A monotonic clock that also advances during suspend (Linux only)
+    Boottime,
+}
+
+/// The stream the I/O log is written to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogSink {
+    /// The standard output
+    #[default]
+    Stdout,
+    /// The standard error
+    Stderr,
+}
+
+/// The minimum severity of the diagnostic messages that are written to stderr
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+    /// No diagnostic messages at all
+    Off,
+    /// Fatal errors only
+    Error,
+    /// Errors and recoverable problems
+    Warn,
+    /// Errors, warnings and lifecycle events like opening the serial device
+    #[default]
+    Info,
+    /// Everything including details like the started worker threads
+    Debug,
+}
+
+/// The format of the logged data
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// The data as text with the non-printable bytes escaped as `\xNN`
+    #[default]
+    Ascii,
+    /// An `xxd`-style hex dump with an offset column, 16 bytes per line and an ASCII gutter
+    Hexdump,
+}
+
+/// What happens to serial frames that match no routing rule
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Unrouted {
+    /// Send the frame to the default destination `udp.send`
+    #[default]
+    Default,
+    /// Drop the frame
+    Drop,
+}
+
+/// How new TCP clients are handled if the client limit is reached
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TcpLimit {
+    /// Close the new connection immediately
+    #[default]
+    Refuse,
+    /// Stop accepting connections until a client disconnects, so that new connections wait in the listen backlog
+    Queue,
+}
+
+/// Which TCP clients may write to the serial device
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TcpWritePolicy {
+    /// All clients may write; the messages are written in the order they arrive without being interleaved
+    #[default]
+    Shared,
+    /// The first client that writes becomes the only writer until it disconnects; the input of other clients is dropped
+    Exclusive,
+}
+
+/// The capture file format
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureFormat {
+    /// The bytes as-is
+    #[default]
+    Raw,
+    /// Each chunk with a preceding time delta and length
+    Timed,
+}
+
+/// The heuristic to score the samples during baudrate detection
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutobaudScoring {
+    /// The ratio of printable ASCII characters
+    #[default]
+    Printable,
+    /// The amount of occurrences of the configured pattern
+    Pattern,
+}
 
 /// The serial config
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Serial {
     /// The path to the serial device
     pub device: String,
     /// The baudrate to use with the serial port
     #[serde(default = "Serial::baudrate_default")]
     pub baudrate: u64,
+    /// The amount of data bits per character
+    #[serde(default = "Serial::data_bits_default")]
+    pub data_bits: u8,
+    /// The parity
+    #[serde(default)]
+    pub parity: Parity,
+    /// The amount of stop bits per character
+    #[serde(default = "Serial::stop_bits_default")]
+    pub stop_bits: u8,
+    /// The flow control
+    #[serde(default)]
+    pub flow_control: FlowControl,
+    /// How flushing the serial device behaves
+    #[serde(default)]
+    pub flush_mode: FlushMode,
+    /// The maximum time in milliseconds a read waits for the first byte before it returns without data (optional)
+    #[serde(default)]
+    pub read_timeout_ms: Option<u64>,
+    /// The maximum time in milliseconds a write or flush may take before it fails and the device is reconnected
+    /// (optional)
+    #[serde(default)]
+    pub write_timeout_ms: Option<u64>,
+    /// The interval in milliseconds to flush the serial device independently of the writes (optional)
+    #[serde(default)]
+    pub flush_interval_ms: Option<u64>,
+    /// How serial output is handled if the pending buffer is full
+    #[serde(default)]
+    pub overflow: Overflow,
+    /// The baudrates that may be configured (optional; if omitted, any baudrate is allowed)
+    #[serde(default)]
+    pub allowed_baudrates: Option<Vec<u64>>,
+    /// The mode to apply to the device node before opening it
+    #[serde(default)]
+    pub chmod: Option<u32>,
+    /// The owner to apply to the device node before opening it as `user`, `user:group` or `:group`
+    #[serde(default)]
+    pub chown: Option<String>,
+    /// The state to set the DTR line to right after opening the device (optional)
+    #[serde(default)]
+    pub initial_dtr: Option<bool>,
+    /// The state to set the RTS line to right after opening the device (optional)
+    #[serde(default)]
+    pub initial_rts: Option<bool>,
+    /// The duration in milliseconds of a break condition transmitted on request
+    #[serde(default = "Serial::break_duration_ms_default")]
+    pub break_duration_ms: u64,
+    /// The serial framing
+    #[serde(default)]
+    pub framing: Framing,
+    /// The frame delimiter for line framing
+    #[serde(default = "Serial::delimiter_default")]
+    pub delimiter: String,
+    /// The size in bytes of the length prefix for length-prefixed framing
+    #[serde(default = "Serial::length_bytes_default")]
+    pub length_bytes: usize,
+    /// The byte order of the length prefix for length-prefixed framing
+    #[serde(default)]
+    pub endianness: Endianness,
+    /// How datagrams are mapped onto serial frames
+    #[serde(default)]
+    pub udp_to_serial_framing: UdpToSerialFraming,
+    /// The time in milliseconds to accumulate datagrams before they are written to the serial device at once (optional)
+    #[serde(default)]
+    pub udp_to_serial_coalesce_ms: Option<u64>,
+    /// The maximum time in milliseconds buffered input may wait before it is dropped instead of written (optional)
+    #[serde(default)]
+    pub udp_to_serial_max_delay_ms: Option<u64>,
+    /// The maximum amount of datagrams to queue between receiving and writing them to the serial device (optional)
+    #[serde(default)]
+    pub udp_to_serial_queue_depth: Option<usize>,
+    /// How datagrams are handled if the UDP->serial queue is full
+    #[serde(default)]
+    pub udp_to_serial_queue_overflow: Overflow,
+    /// The size in bytes at which accumulated datagrams are written before the coalescing window has elapsed
+    #[serde(default = "Serial::udp_to_serial_coalesce_max_bytes_default")]
+    pub udp_to_serial_coalesce_max_bytes: usize,
+    /// The expected frame size in bytes to size the serial reads to (optional)
+    #[serde(default)]
+    pub read_hint_bytes: Option<usize>,
+    /// The time in milliseconds without a new byte after which buffered raw output is forwarded as one chunk (optional;
+    /// `0` disables coalescing)
+    #[serde(default)]
+    pub coalesce_ms: Option<u64>,
+    /// The time in milliseconds after which a partial frame is forwarded if no further data arrives (optional)
+    #[serde(default)]
+    pub final_idle_ms: Option<u64>,
+    /// The marker to append to partial frames that are forwarded due to the idle timeout or on shutdown (optional)
+    #[serde(default)]
+    pub incomplete_marker: Option<String>,
+    /// The commands to write to the serial device on shutdown
+    #[serde(default)]
+    pub shutdown_commands: Vec<BytePattern>,
+    /// The maximum time in milliseconds to wait for the shutdown commands and the pending output to be transmitted
+    #[serde(default = "Serial::shutdown_grace_ms_default")]
+    pub shutdown_grace_ms: u64,
+    /// Whether to check that the device is writable when it is opened
+    #[serde(default)]
+    pub probe_write: bool,
+    /// The latency timer in milliseconds to set via sysfs (Linux USB serial devices like FTDI only)
+    #[serde(default)]
+    pub latency_timer: Option<u64>,
+    /// Whether to read and report the effective latency timer at startup
+    #[serde(default)]
+    pub report_latency_timer: bool,
+    /// The interval in milliseconds to poll the driver's error counts (Linux only; optional)
+    #[serde(default)]
+    pub error_counts_interval_ms: Option<u64>,
+    /// The warm-up pattern to write at startup
+    #[serde(default)]
+    pub warmup: Option<Warmup>,
+    /// The pattern that must be seen in the serial output before it is forwarded (optional)
+    #[serde(default)]
+    pub start_trigger: Option<StartTrigger>,
+    /// The pattern in the serial output that stops the forwarding (optional)
+    #[serde(default)]
+    pub stop_trigger: Option<StopTrigger>,
+    /// The automatic baudrate detection
+    #[serde(default)]
+    pub autobaud: Option<Autobaud>,
 }
 impl Serial {
     /// The default baudrate
     const fn baudrate_default() -> u64 {
         115200
     }
+    /// The default amount of data bits
+    const fn data_bits_default() -> u8 {
+        8
+    }
+    /// The default amount of stop bits
+    const fn stop_bits_default() -> u8 {
+        1
+    }
+    /// The default frame delimiter
+    fn delimiter_default() -> String {
+        "\n".to_string()
+    }
+    /// The default length prefix size
+    const fn length_bytes_default() -> usize {
+        2
+    }
+    /// The default coalescing size limit
+    const fn udp_to_serial_coalesce_max_bytes_default() -> usize {
+        4096
+    }
+    /// The default break duration
+    const fn break_duration_ms_default() -> u64 {
+        250
+    }
+    /// The default shutdown grace period
+    const fn shutdown_grace_ms_default() -> u64 {
+        1000
+    }
+}
+
+/// A part of a byte pattern given as mixed array
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum BytePart {
+    /// A single byte
+    Byte(u8),
+    /// A string
+    Text(String),
+    /// A hex-encoded byte sequence
+    Hex {
+        /// The hex digits, e.g. `0x41 0x54` or `4154`
+        hex: String,
+    },
+}
+
+/// A byte pattern as written in the config
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum BytePatternRepr {
+    /// A pattern given as string
+    Text(String),
+    /// A pattern given as array of bytes, strings and hex-encoded byte sequences
+    Parts(Vec<BytePart>),
+    /// A pattern given as hex-encoded byte sequence
+    Hex {
+        /// The hex digits, e.g. `0x41 0x54` or `4154`
+        hex: String,
+    },
+}
+
+/// A byte pattern given as string (with the TOML escapes like `\r\n`), as hex-encoded byte sequence like
+/// `{ hex = "0x41 0x54 0d0a" }`, or as array that mixes bytes, strings and hex-encoded byte sequences like
+/// `["AT", 0x0d, { hex = "0a" }]`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "BytePatternRepr", into = "BytePatternRepr")]
+pub struct BytePattern {
+    /// The pattern as written in the config
+    repr: BytePatternRepr,
+    /// The decoded pattern bytes
+    bytes: Vec<u8>,
+}
+impl BytePattern {
+    /// The maximum length of trigger patterns
+    const TRIGGER_LEN_MAX: usize = 64;
+
+    /// The pattern bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Decodes a hex-encoded byte sequence of whitespace-separated groups with an optional `0x` prefix each
+    fn decode_hex(hex: &str, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        for group in hex.split_whitespace() {
+            // Strip the prefix and validate the group
+            let digits = group.strip_prefix("0x").or_else(|| group.strip_prefix("0X")).unwrap_or(group);
+            if let Some(invalid) = digits.chars().find(|digit| !digit.is_ascii_hexdigit()) {
+                return Err(eio!("Invalid hex digit `{invalid}` in `{group}` (expected 0-9, a-f or A-F)"));
+            }
+            if digits.is_empty() || digits.len() % 2 != 0 {
+                return Err(eio!("Invalid hex group `{group}` (expected an even amount of hex digits per group)"));
+            }
+
+            // Decode the digit pairs
+            for index in (0..digits.len()).step_by(2) {
+                bytes.push(u8::from_str_radix(&digits[index..index + 2], 16).expect("Hex digits are invalid"));
+            }
+        }
+        Ok(())
+    }
+    /// Validates the pattern as trigger pattern of the given trigger
+    fn validate_trigger(&self, trigger: &str) -> Result<(), Error> {
+        let len = self.as_bytes().len();
+        if !(1..=Self::TRIGGER_LEN_MAX).contains(&len) {
+            let max = Self::TRIGGER_LEN_MAX;
+            return Err(eio!("Invalid {trigger} trigger length of {len} bytes (expected 1 to {max} bytes)"));
+        }
+        Ok(())
+    }
+}
+impl TryFrom<BytePatternRepr> for BytePattern {
+    type Error = Error;
+
+    fn try_from(repr: BytePatternRepr) -> Result<Self, Self::Error> {
+        let mut bytes = Vec::new();
+        match &repr {
+            BytePatternRepr::Text(text) => bytes.extend_from_slice(text.as_bytes()),
+            BytePatternRepr::Hex { hex } => Self::decode_hex(hex, &mut bytes)?,
+            BytePatternRepr::Parts(parts) => {
+                for part in parts {
+                    match part {
+                        BytePart::Byte(byte) => bytes.push(*byte),
+                        BytePart::Text(text) => bytes.extend_from_slice(text.as_bytes()),
+                        BytePart::Hex { hex } => Self::decode_hex(hex, &mut bytes)?,
+                    }
+                }
+            }
+        }
+        Ok(Self { repr, bytes })
+    }
+}
+impl From<BytePattern> for BytePatternRepr {
+    fn from(pattern: BytePattern) -> Self {
+        pattern.repr
+    }
+}
+
+/// The start trigger configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StartTrigger {
+    /// The pattern that starts the forwarding
+    pub pattern: BytePattern,
+    /// Whether to forward the trigger itself
+    #[serde(default)]
+    pub include_trigger: bool,
+}
+
+/// What happens if the stop trigger is seen
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StopAction {
+    /// Pause the forwarding in both directions like the `pause` control command
+    #[default]
+    Pause,
+    /// Shut the server down gracefully
+    Shutdown,
+}
+
+/// The stop trigger configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StopTrigger {
+    /// The pattern that stops the forwarding
+    pub pattern: BytePattern,
+    /// What happens if the pattern is seen
+    #[serde(default)]
+    pub action: StopAction,
+}
+
+/// The warm-up configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Warmup {
+    /// The byte pattern to write repeatedly
+    pub pattern: BytePattern,
+    /// The amount of repetitions
+    #[serde(default)]
+    pub count: Option<u64>,
+    /// The time in milliseconds to repeat the pattern
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+}
+impl Warmup {
+    /// The maximum amount of bytes to write
+    const BYTES_MAX: u64 = 1024 * 1024;
+    /// The maximum warm-up duration in milliseconds
+    const DURATION_MS_MAX: u64 = 60_000;
+
+    /// Validates the warm-up configuration
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.pattern.as_bytes().is_empty() {
+            return Err(eio!("Warm-up pattern must not be empty"));
+        }
+        match (self.count, self.duration_ms) {
+            (Some(count), None) if count.saturating_mul(self.pattern.as_bytes().len() as u64) > Self::BYTES_MAX => {
+                Err(eio!("Warm-up exceeds the maximum of {} bytes", Self::BYTES_MAX))
+            }
+            (None, Some(duration_ms)) if duration_ms > Self::DURATION_MS_MAX => {
+                Err(eio!("Warm-up exceeds the maximum duration of {}ms", Self::DURATION_MS_MAX))
+            }
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            _ => Err(eio!("Warm-up requires either a count or a duration")),
+        }
+    }
+}
+
+/// The automatic baudrate detection configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Autobaud {
+    /// The candidate baudrates in the order they are tried
+    pub candidates: Vec<u64>,
+    /// The sampling window per candidate in milliseconds
+    #[serde(default = "Autobaud::window_ms_default")]
+    pub window_ms: u64,
+    /// The scoring heuristic
+    #[serde(default)]
+    pub scoring: AutobaudScoring,
+    /// The pattern for the pattern scoring
+    #[serde(default)]
+    pub pattern: Option<BytePattern>,
+}
+impl Autobaud {
+    /// The default sampling window
+    const fn window_ms_default() -> u64 {
+        1000
+    }
+
+    /// Validates the autobaud configuration
+    pub fn validate(&self, allowed_baudrates: Option<&[u64]>) -> Result<(), Error> {
+        if self.candidates.is_empty() {
+            return Err(eio!("Autobaud requires at least one candidate baudrate"));
+        }
+        if self.scoring == AutobaudScoring::Pattern
+            && self.pattern.as_ref().is_none_or(|pattern| pattern.as_bytes().is_empty())
+        {
+            return Err(eio!("Autobaud pattern scoring requires a non-empty pattern"));
+        }
+
+        // Validate the candidates against the policy
+        let disallowed = allowed_baudrates.and_then(|allowed| {
+            // Find the first candidate that is not allowed
+            self.candidates.iter().find(|candidate| !allowed.contains(candidate))
+        });
+        if let Some(candidate) = disallowed {
+            return Err(eio!("Autobaud candidate {candidate} is not allowed"));
+        }
+        Ok(())
+    }
 }
 
 /// The UDP configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Udp {
-    /// The UDP address to listen on
+    /// The UDP address to listen on (required in UDP mode)
+    #[serde(default)]
     pub listen: String,
-    /// The UDP address to send to
+    /// The UDP address to send to, or `auto` to send to the sender of the most recent inbound packet
     #[serde(default)]
     pub send: Option<String>,
+    /// The local address to send from (optional; defaults to `0.0.0.0:0`)
+    #[serde(default)]
+    pub bind: Option<String>,
+    /// The amount of serial output bytes to buffer until the send destination is known
+    #[serde(default)]
+    pub pending_buffer: usize,
+    /// The interval in milliseconds to re-resolve the send address (optional; `0` resolves it only once)
+    #[serde(default)]
+    pub resolve_interval_ms: Option<u64>,
+    /// The maximum time in milliseconds to buffer serial output until it is dropped (optional)
+    #[serde(default)]
+    pub max_buffer_age_ms: Option<u64>,
+    /// The amount of consecutive packets after which a new sender replaces the adopted destination in auto mode
+    #[serde(default)]
+    pub adopt_after_packets: Option<u64>,
+    /// The time in milliseconds after which a new sender that keeps sending replaces the adopted destination in auto
+    /// mode
+    #[serde(default)]
+    pub adopt_after_ms: Option<u64>,
     /// The TTL for outgoing UDP packets
     #[serde(default)]
     pub ttl: u32,
+    /// Whether multicast datagrams are looped back to the local host
+    #[serde(default = "Udp::multicast_loop_default")]
+    pub multicast_loop: bool,
+    /// The maximum size in bytes of inbound datagrams including the header; larger datagrams are dropped (optional)
+    #[serde(default)]
+    pub max_packet_size: Option<usize>,
+    /// The maximum amount of datagrams to receive with a single syscall (Linux only; defaults to 1)
+    #[serde(default = "Udp::recv_batch_default")]
+    pub recv_batch: usize,
+    /// The datagram header (optional; if omitted, datagrams are sent and received without header)
+    #[serde(default)]
+    pub header: Option<Header>,
+    /// The source networks in CIDR notation or IP addresses that may send datagrams to the serial device (optional; if
+    /// omitted or empty, all sources are allowed)
+    #[serde(default)]
+    pub allowed_sources: Option<Vec<String>>,
+    /// The datagram payload that requests a break condition instead of being written to the serial device (optional)
+    #[serde(default)]
+    pub break_datagram: Option<BytePattern>,
+    /// The heartbeat that is sent while the serial device is idle (optional)
+    #[serde(default)]
+    pub heartbeat: Option<Heartbeat>,
+    /// The IPv4 multicast configuration (optional)
+    #[serde(default)]
+    pub multicast: Option<Multicast>,
+}
+
+impl Udp {
+    /// The default receive batch size
+    const fn recv_batch_default() -> usize {
+        1
+    }
+    /// The default multicast loopback
+    const fn multicast_loop_default() -> bool {
+        true
+    }
+
+    /// Whether the send address is a multicast address
+    pub fn sends_multicast(&self) -> bool {
+        let send = self.send.as_deref().filter(|send| *send != Destination::AUTO);
+        send.and_then(Self::resolve).is_some_and(|address| address.ip().is_multicast())
+    }
+    /// The local address to send from
+    ///
+    /// # Note
+    /// If `bind` is omitted, the address family follows the send address, or the listen address in `auto` mode since
+    /// the destination is adopted from the listen socket.
+    pub fn bind_address(&self) -> &str {
+        if let Some(bind) = self.bind.as_deref() {
+            return bind;
+        }
+        let send = self.send.as_deref().filter(|send| *send != Destination::AUTO);
+        match Self::resolve(send.unwrap_or(&self.listen)) {
+            Some(SocketAddr::V6(_)) => "[::]:0",
+            _ => "0.0.0.0:0",
+        }
+    }
+    /// Resolves an address to its first socket address if possible
+    fn resolve(address: &str) -> Option<SocketAddr> {
+        address.to_socket_addrs().ok().and_then(|mut addresses| addresses.next())
+    }
+}
+impl Default for Udp {
+    fn default() -> Self {
+        Self {
+            listen: String::new(),
+            send: None,
+            bind: None,
+            pending_buffer: 0,
+            resolve_interval_ms: None,
+            max_buffer_age_ms: None,
+            adopt_after_packets: None,
+            adopt_after_ms: None,
+            ttl: 0,
+            multicast_loop: Self::multicast_loop_default(),
+            max_packet_size: None,
+            recv_batch: Self::recv_batch_default(),
+            header: None,
+            allowed_sources: None,
+            break_datagram: None,
+            heartbeat: None,
+            multicast: None,
+        }
+    }
+}
+
+/// A header field specification
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct HeaderField {
+    /// The field
+    pub field: HeaderFieldKind,
+    /// The field size in bytes
+    pub size: usize,
+    /// The byte order of the field
+    #[serde(default)]
+    pub endian: Endianness,
+}
+
+/// The datagram header configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Header {
+    /// The ordered header fields
+    pub fields: Vec<HeaderField>,
+    /// The value of the source ID field
+    #[serde(default)]
+    pub source_id: u64,
+    /// The maximum datagram size including the header
+    #[serde(default = "Header::mtu_default")]
+    pub mtu: usize,
+}
+impl Header {
+    /// The maximum size of a header field
+    pub const FIELD_SIZE_MAX: usize = 8;
+
+    /// The default MTU (an Ethernet frame without the IPv4 and UDP headers)
+    const fn mtu_default() -> usize {
+        1472
+    }
+
+    /// The total header size
+    pub fn size(&self) -> usize {
+        self.fields.iter().map(|field| field.size).sum()
+    }
+
+    /// Validates the header specification
+    pub fn validate(&self) -> Result<(), Error> {
+        for (index, field) in self.fields.iter().enumerate() {
+            // Validate the field size
+            if !(1..=Self::FIELD_SIZE_MAX).contains(&field.size) {
+                return Err(eio!("Invalid size of header field {:?} ({} bytes)", field.field, field.size));
+            }
+
+            // Reject duplicate fields
+            if self.fields[..index].iter().any(|other| other.field == field.field) {
+                return Err(eio!("Duplicate header field {:?}", field.field));
+            }
+        }
+
+        // Validate the total size against the MTU
+        let size = self.size();
+        if size >= self.mtu {
+            return Err(eio!("Header size exceeds the MTU ({size} >= {} bytes)", self.mtu));
+        }
+
+        // Validate that the length field can represent the maximum payload length
+        let payload_max = (self.mtu - size) as u128;
+        let length = self.fields.iter().find(|field| field.field == HeaderFieldKind::Length);
+        if let Some(length) = length.filter(|length| payload_max >= 1 << (length.size * 8)) {
+            return Err(eio!("Header length field is too small for the MTU ({} bytes)", length.size));
+        }
+        Ok(())
+    }
+}
+
+/// The heartbeat configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Heartbeat {
+    /// The time in milliseconds without any sent datagram after which the heartbeat is sent
+    pub interval_ms: u64,
+    /// The heartbeat datagram payload
+    pub payload: BytePattern,
+}
+
+/// The IPv4 multicast configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Multicast {
+    /// The address of the local interface to send multicast datagrams from and to join the groups on (optional; if
+    /// omitted, the OS selects the interface)
+    #[serde(default)]
+    pub interface: Option<Ipv4Addr>,
+    /// The IPv4 or IPv6 multicast groups to join on the listen socket
+    #[serde(default)]
+    pub groups: Vec<IpAddr>,
 }
 
 /// The logger configuration
-#[derive(Debug, Default, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Log {
+    /// The minimum severity of the diagnostic messages
+    #[serde(default)]
+    pub level: LogLevel,
     /// Whether to enable logging or not
     #[serde(default)]
     pub enabled: bool,
+    /// The stream to write the log to
+    #[serde(default)]
+    pub sink: LogSink,
+    /// The format of the logged data
+    #[serde(default)]
+    pub format: LogFormat,
+    /// The file to write the log to instead of `sink` (optional)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The size in bytes at which the log file is rotated
+    #[serde(default = "Log::max_size_bytes_default")]
+    pub max_size_bytes: u64,
+    /// The amount of rotated log files to keep
+    #[serde(default = "Log::max_files_default")]
+    pub max_files: usize,
+    /// Whether to gzip-compress the rotated log files
+    #[serde(default)]
+    pub compress: bool,
+    /// The interval in milliseconds to log the current data rates (optional)
+    #[serde(default)]
+    pub rate_interval_ms: Option<u64>,
+    /// Whether to log the data that is dropped by a policy together with the reason
+    #[serde(default)]
+    pub log_drops: bool,
+    /// Whether to collapse consecutive identical chunks into one chunk and a repetition count
+    #[serde(default)]
+    pub dedup_consecutive: bool,
+    /// Whether to prefix each log line with the serial device path
+    #[serde(default)]
+    pub include_device: bool,
+    /// Whether to tag each log line with its direction as `RX` (serial->UDP) or `TX` (UDP->serial)
+    #[serde(default)]
+    pub include_direction: bool,
+    /// The maximum time in milliseconds to suppress repetitions before the count is logged
+    #[serde(default = "Log::dedup_timeout_ms_default")]
+    pub dedup_timeout_ms: u64,
+    /// The timestamp to prefix each log line with (optional)
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>,
+}
+impl Log {
+    /// The default deduplication timeout
+    const fn dedup_timeout_ms_default() -> u64 {
+        10_000
+    }
+    /// The default log file rotation size
+    const fn max_size_bytes_default() -> u64 {
+        10 * 1024 * 1024
+    }
+    /// The default amount of rotated log files
+    const fn max_files_default() -> usize {
+        5
+    }
+}
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::default(),
+            enabled: false,
+            sink: LogSink::default(),
+            format: LogFormat::default(),
+            path: None,
+            max_size_bytes: Self::max_size_bytes_default(),
+            max_files: Self::max_files_default(),
+            compress: false,
+            rate_interval_ms: None,
+            log_drops: false,
+            dedup_consecutive: false,
+            include_device: false,
+            include_direction: false,
+            dedup_timeout_ms: Self::dedup_timeout_ms_default(),
+            timestamp: None,
+        }
+    }
+}
+
+/// The byte translation configuration
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Translate {
+    /// The byte translations applied to data read from the serial device
+    #[serde(default)]
+    pub serial_to_udp: BTreeMap<String, u8>,
+    /// The byte translations applied to data written to the serial device
+    #[serde(default)]
+    pub udp_to_serial: BTreeMap<String, u8>,
+}
+
+/// The jitter buffer configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JitterBuffer {
+    /// The time in milliseconds to hold each datagram before it is written to the serial device
+    pub delay_ms: u64,
+}
+
+/// The write verification configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VerifyWrites {
+    /// The expected response, or `None` to expect an echo of the written data
+    #[serde(default)]
+    pub response: Option<String>,
+    /// The time to wait for the response per attempt in milliseconds
+    #[serde(default = "VerifyWrites::timeout_ms_default")]
+    pub timeout_ms: u64,
+    /// The amount of retries if the response is not seen in time
+    #[serde(default)]
+    pub retries: u64,
+}
+impl VerifyWrites {
+    /// The default response timeout
+    const fn timeout_ms_default() -> u64 {
+        1000
+    }
+}
+
+/// The TCP transport configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Tcp {
+    /// The TCP address to listen on for clients (either `listen` or `connect` is required)
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// The TCP address to connect to as client, reconnecting after a disconnect
+    #[serde(default)]
+    pub connect: Option<String>,
+    /// The maximum amount of concurrently connected clients
+    #[serde(default = "Tcp::max_clients_default")]
+    pub max_clients: usize,
+    /// The maximum amount of pending connections in the listen backlog
+    #[serde(default = "Tcp::backlog_default")]
+    pub backlog: u32,
+    /// How new clients are handled if the client limit is reached
+    #[serde(default)]
+    pub on_limit: TcpLimit,
+    /// Which clients may write to the serial device
+    #[serde(default)]
+    pub write_policy: TcpWritePolicy,
+}
+impl Tcp {
+    /// The default client limit
+    const fn max_clients_default() -> usize {
+        1
+    }
+    /// The default listen backlog
+    const fn backlog_default() -> u32 {
+        128
+    }
+}
+
+/// The capture configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Capture {
+    /// The path to the serial output capture file (optional)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The serial output capture file format
+    #[serde(default)]
+    pub format: CaptureFormat,
+    /// The path to the pcap capture file of the bridged UDP datagrams (optional)
+    #[serde(default)]
+    pub pcap: Option<String>,
+    /// The maximum size of the pcap capture file in bytes before it is rotated
+    #[serde(default = "Capture::pcap_max_bytes_default")]
+    pub pcap_max_bytes: u64,
+}
+impl Capture {
+    /// The default maximum pcap capture file size
+    const fn pcap_max_bytes_default() -> u64 {
+        100 * 1024 * 1024
+    }
+}
+
+/// The control socket configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Control {
+    /// The TCP address to listen on for control connections
+    pub listen: String,
+    /// The amount of recent bytes per direction to replay to new tail subscribers
+    #[serde(default = "Control::tail_history_default")]
+    pub tail_history: usize,
+    /// The amount of chunks to buffer per tail subscriber before dropping data
+    #[serde(default = "Control::tail_backlog_default")]
+    pub tail_backlog: usize,
+}
+impl Control {
+    /// The default tail history size
+    const fn tail_history_default() -> usize {
+        4096
+    }
+    /// The default tail backlog
+    const fn tail_backlog_default() -> usize {
+        64
+    }
+}
+
+/// The status file configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Status {
+    /// The path of the status file
+    pub file: String,
+    /// The interval in milliseconds to rewrite the status file
+    #[serde(default = "Status::interval_ms_default")]
+    pub interval_ms: u64,
+}
+impl Status {
+    /// The default status file interval
+    const fn interval_ms_default() -> u64 {
+        1000
+    }
+}
+
+/// A routing rule
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Route {
+    /// The prefix that identifies the frames
+    pub prefix: BytePattern,
+    /// The UDP address to send the matching frames to
+    pub send: String,
+}
+
+/// The content-based routing configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Routing {
+    /// What happens to frames that match no rule
+    #[serde(default)]
+    pub unmatched: Unrouted,
+    /// The routing rules; the first matching rule wins
+    pub rules: Vec<Route>,
+}
+
+/// The reconnect configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Reconnect {
+    /// Whether to reopen the serial device after an I/O error instead of exiting
+    #[serde(default)]
+    pub enabled: bool,
+    /// The delay in milliseconds before the first reopen attempt
+    #[serde(default = "Reconnect::initial_delay_ms_default")]
+    pub initial_delay_ms: u64,
+    /// The maximum delay in milliseconds between reopen attempts
+    #[serde(default = "Reconnect::max_delay_ms_default")]
+    pub max_delay_ms: u64,
+    /// The maximum amount of reopen attempts per reconnect cycle (`0` means infinite)
+    #[serde(default)]
+    pub max_retries: u64,
+    /// The maximum amount of reconnect cycles before the server shuts down (`0` means infinite)
+    #[serde(default)]
+    pub max_cycles: u64,
+}
+impl Reconnect {
+    /// The exit code if the maximum amount of reconnect cycles has been reached
+    pub const EXIT_CYCLES_EXHAUSTED: i32 = 3;
+
+    /// The default initial delay
+    const fn initial_delay_ms_default() -> u64 {
+        100
+    }
+    /// The default maximum delay
+    const fn max_delay_ms_default() -> u64 {
+        10_000
+    }
+}
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            initial_delay_ms: Self::initial_delay_ms_default(),
+            max_delay_ms: Self::max_delay_ms_default(),
+            max_retries: 0,
+            max_cycles: 0,
+        }
+    }
+}
+
+/// The diagnostic metrics configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Metrics {
+    /// Whether to periodically log a histogram of the chunk sizes per direction
+    #[serde(default)]
+    pub chunk_histogram: bool,
+    /// The interval in milliseconds to log the metrics
+    #[serde(default = "Metrics::interval_ms_default")]
+    pub interval_ms: u64,
+    /// The inclusive upper bounds of the latency histogram buckets in milliseconds
+    #[serde(default = "Metrics::latency_buckets_ms_default")]
+    pub latency_buckets_ms: Vec<f64>,
+    /// The TCP address to serve the statistics as JSON on (optional)
+    #[serde(default)]
+    pub listen: Option<String>,
+}
+impl Metrics {
+    /// The default interval
+    const fn interval_ms_default() -> u64 {
+        1000
+    }
+    /// The default latency histogram buckets
+    fn latency_buckets_ms_default() -> Vec<f64> {
+        vec![1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0]
+    }
+
+    /// Validates the latency histogram buckets
+    fn validate(&self) -> Result<(), Error> {
+        let buckets = &self.latency_buckets_ms;
+        if buckets.iter().any(|bound| !bound.is_finite() || *bound <= 0.0) {
+            return Err(eio!("Invalid latency histogram buckets {buckets:?} (expected positive finite bounds)"));
+        }
+        if buckets.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(eio!("Invalid latency histogram buckets {buckets:?} (expected strictly increasing bounds)"));
+        }
+        Ok(())
+    }
+}
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            chunk_histogram: false,
+            interval_ms: Self::interval_ms_default(),
+            latency_buckets_ms: Self::latency_buckets_ms_default(),
+            listen: None,
+        }
+    }
+}
+
+/// The buffer size configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Buffers {
+    /// The size of the serial read buffer and thus the maximum chunk size
+    #[serde(default = "Buffers::serial_buffer_default")]
+    pub serial_buffer: usize,
+    /// The size of the UDP receive buffer and thus the maximum datagram size towards the serial device
+    #[serde(default = "Buffers::udp_buffer_default")]
+    pub udp_buffer: usize,
+}
+impl Buffers {
+    /// The default serial read buffer size
+    const fn serial_buffer_default() -> usize {
+        400
+    }
+    /// The default UDP receive buffer size, which holds the largest possible UDP datagram
+    const fn udp_buffer_default() -> usize {
+        65536
+    }
+}
+impl Default for Buffers {
+    fn default() -> Self {
+        Self { serial_buffer: Self::serial_buffer_default(), udp_buffer: Self::udp_buffer_default() }
+    }
+}
+
+/// A real-time scheduling policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchedulingPolicy {
+    /// First-in first-out without time slices (`SCHED_FIFO`)
+    Fifo,
+    /// Round-robin with time slices among threads of the same priority (`SCHED_RR`)
+    Rr,
+}
+
+/// The real-time scheduling configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Scheduling {
+    /// The scheduling policy for the serial threads
+    pub policy: SchedulingPolicy,
+    /// The static priority of the serial threads
+    pub priority: u32,
+}
+
+/// The syscall sandbox configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Sandbox {
+    /// Whether to allow opening files and changing file attributes after setup
+    #[serde(default)]
+    pub allow_open: bool,
 }
 
 /// The config
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
+    /// The clock used for the log, header and pcap timestamps
+    #[serde(default)]
+    pub timestamp_clock: TimestampClock,
+    /// The indices of the CPUs to pin the server threads to (optional)
+    #[serde(default)]
+    pub cpu_affinity: Option<Vec<usize>>,
     /// The serial device config
     pub serial: Serial,
+    /// The primary transport
+    #[serde(default)]
+    pub mode: Mode,
+    /// Whether the serial device's output is forwarded to the network
+    #[serde(default = "Config::direction_default")]
+    pub serial_to_udp: bool,
+    /// Whether the input from the network is written to the serial device
+    #[serde(default = "Config::direction_default")]
+    pub udp_to_serial: bool,
     /// The UDP config
+    #[serde(default)]
     pub udp: Udp,
     /// The logger configuration
     #[serde(default)]
     pub log: Log,
+    /// The diagnostic metrics configuration
+    #[serde(default)]
+    pub metrics: Metrics,
+    /// The buffer size configuration
+    #[serde(default)]
+    pub buffers: Buffers,
+    /// The byte translation configuration
+    #[serde(default)]
+    pub translate: Translate,
+    /// The jitter buffer configuration
+    #[serde(default)]
+    pub jitter_buffer: Option<JitterBuffer>,
+    /// The write verification configuration
+    #[serde(default)]
+    pub verify_writes: Option<VerifyWrites>,
+    /// The capture configuration
+    #[serde(default)]
+    pub capture: Option<Capture>,
+    /// The control socket configuration
+    #[serde(default)]
+    pub control: Option<Control>,
+    /// The TCP transport configuration
+    #[serde(default)]
+    pub tcp: Option<Tcp>,
+    /// The status file configuration
+    #[serde(default)]
+    pub status: Option<Status>,
+    /// The content-based routing configuration
+    #[serde(default)]
+    pub routing: Option<Routing>,
+    /// The reconnect configuration
+    #[serde(default)]
+    pub reconnect: Reconnect,
+    /// The real-time scheduling configuration (Unix only)
+    #[serde(default)]
+    pub scheduling: Option<Scheduling>,
+    /// The syscall sandbox configuration (Linux only; requires the `sandbox` feature)
+    #[serde(default)]
+    pub sandbox: Option<Sandbox>,
 }
 impl Config {
     /// The default config path
     const PATH: &'static str = "config.toml";
 
-    /// Loads the config
-    pub fn load() -> Result<Self, Error> {
+    /// The default for the per-direction forwarding flags
+    const fn direction_default() -> bool {
+        true
+    }
+
+    /// Loads the config and returns one config per bridge
+    ///
+    /// # Note
+    /// A config without `[[bridge]]` tables is a single bridge.
+    pub fn load() -> Result<Vec<Self>, Error> {
+        // Note: options like `--list-ports` and `--check` are handled before the config is loaded
+        Self::load_from(env::args().nth(1))
+    }
+    /// Loads the config from the file defined by the environment, the given path or the local config, and returns one
+    /// config per bridge
+    pub fn load_from(path: Option<String>) -> Result<Vec<Self>, Error> {
         // Load the config file defined by the environment
         if let Ok(path) = env::var("SERIALSERVER_CONFIG") {
             return Self::load_file(&path);
         }
 
-        // Load the config file from first argv
-        if let Some(path) = env::args().nth(1) {
+        // Load the config file from the given path
+        if let Some(path) = path {
+            if path.starts_with("--") {
+                let error = eio!("Unknown option {path} (expected a config file path, `--check` or `--list-ports`)");
+                return Err(error.in_category(ErrorCategory::Config));
+            }
             return Self::load_file(&path);
         }
 
@@ -74,17 +1322,738 @@ impl Config {
         }
 
         // Raise an error if no config could be found
-        Err(eio!("Config file not found"))
+        Err(eio!("Config file not found").in_category(ErrorCategory::Config))
+    }
+
+    /// Validates the config
+    pub fn validate(&self) -> Result<(), Error> {
+        // Validate the baudrate against the policy
+        if let Some(allowed_baudrates) = self.serial.allowed_baudrates.as_ref() {
+            if !allowed_baudrates.contains(&self.serial.baudrate) {
+                let baudrate = self.serial.baudrate;
+                return Err(eio!("Baudrate {baudrate} is not allowed (allowed baudrates: {allowed_baudrates:?})"));
+            }
+        }
+
+        // Validate the character format
+        if !(5..=8).contains(&self.serial.data_bits) {
+            return Err(eio!("Invalid amount of {} data bits (expected 5 to 8)", self.serial.data_bits));
+        }
+        if !(1..=2).contains(&self.serial.stop_bits) {
+            return Err(eio!("Invalid amount of {} stop bits (expected 1 or 2)", self.serial.stop_bits));
+        }
+
+        // Validate the autobaud configuration
+        if let Some(autobaud) = self.serial.autobaud.as_ref() {
+            autobaud.validate(self.serial.allowed_baudrates.as_deref())?;
+        }
+
+        // Validate the warm-up configuration
+        if let Some(warmup) = self.serial.warmup.as_ref() {
+            warmup.validate()?;
+        }
+
+        // Validate the start and stop trigger
+        if let Some(start_trigger) = self.serial.start_trigger.as_ref() {
+            start_trigger.pattern.validate_trigger("start")?;
+        }
+        if let Some(stop_trigger) = self.serial.stop_trigger.as_ref() {
+            stop_trigger.pattern.validate_trigger("stop")?;
+        }
+
+        // Validate the read hint
+        if self.serial.read_hint_bytes == Some(0) {
+            return Err(eio!("Invalid read hint of 0 bytes (expected at least 1 byte)"));
+        }
+
+        // Validate the buffer sizes
+        if self.buffers.serial_buffer == 0 {
+            return Err(eio!("Invalid serial buffer size of 0 bytes (expected at least 1 byte)"));
+        }
+        if self.buffers.udp_buffer == 0 {
+            return Err(eio!("Invalid UDP buffer size of 0 bytes (expected at least 1 byte)"));
+        }
+        if self.udp.max_packet_size == Some(0) {
+            return Err(eio!("Invalid maximum packet size of 0 bytes (expected at least 1 byte)"));
+        }
+
+        // Validate the final idle timeout
+        if self.serial.final_idle_ms == Some(0) {
+            return Err(eio!("Invalid final idle timeout of 0ms (expected at least 1ms)"));
+        }
+
+        // Validate the length prefix
+        if !(1..=8).contains(&self.serial.length_bytes) {
+            return Err(eio!(
+                "Invalid length prefix size of {} bytes (expected 1 to 8 bytes)",
+                self.serial.length_bytes
+            ));
+        }
+
+        // Validate the break condition
+        if self.serial.break_duration_ms == 0 {
+            return Err(eio!("Invalid break duration of 0ms (expected at least 1ms)"));
+        }
+        if self.udp.break_datagram.as_ref().is_some_and(|pattern| pattern.as_bytes().is_empty()) {
+            return Err(eio!("Invalid empty break datagram (expected at least 1 byte)"));
+        }
+
+        // Validate the heartbeat
+        if let Some(heartbeat) = self.udp.heartbeat.as_ref() {
+            if heartbeat.interval_ms == 0 {
+                return Err(eio!("Invalid heartbeat interval of 0ms (expected at least 1ms)"));
+            }
+            if heartbeat.payload.as_bytes().is_empty() {
+                return Err(eio!("Invalid empty heartbeat payload (expected at least 1 byte)"));
+            }
+        }
+
+        // Validate the read timeout
+        if self.serial.read_timeout_ms == Some(0) {
+            return Err(eio!("Invalid read timeout of 0ms (expected at least 1ms)"));
+        }
+
+        // Validate the write timeout
+        if self.serial.write_timeout_ms == Some(0) {
+            return Err(eio!("Invalid write timeout of 0ms (expected at least 1ms)"));
+        }
+
+        // Validate the periodic flush
+        if self.serial.flush_interval_ms == Some(0) {
+            return Err(eio!("Invalid flush interval of 0ms (expected at least 1ms)"));
+        }
+
+        // Validate the maximum UDP->serial delay
+        if self.serial.udp_to_serial_max_delay_ms == Some(0) {
+            return Err(eio!("Invalid maximum UDP->serial delay of 0ms (expected at least 1ms)"));
+        }
+
+        // Validate the write coalescing
+        if self.serial.udp_to_serial_coalesce_ms == Some(0) {
+            return Err(eio!("Invalid coalescing window of 0ms (expected at least 1ms)"));
+        }
+        if self.serial.udp_to_serial_coalesce_max_bytes == 0 {
+            return Err(eio!("Invalid coalescing size limit of 0 bytes (expected at least 1 byte)"));
+        }
+
+        // Validate the write queue
+        if self.serial.udp_to_serial_queue_depth == Some(0) {
+            return Err(eio!("Invalid UDP->serial queue depth of 0 datagrams (expected at least 1 datagram)"));
+        }
+
+        // Validate the metrics interval
+        if self.metrics.chunk_histogram && self.metrics.interval_ms == 0 {
+            return Err(eio!("Invalid metrics interval of 0ms (expected at least 1ms)"));
+        }
+        self.metrics.validate()?;
+
+        // Validate the status file interval
+        if self.status.as_ref().is_some_and(|status| status.interval_ms == 0) {
+            return Err(eio!("Invalid status file interval of 0ms (expected at least 1ms)"));
+        }
+
+        // Validate the pcap capture file size
+        if let Some(capture) =
+            self.capture.as_ref().filter(|capture| capture.pcap_max_bytes < PcapWriter::MAX_BYTES_MIN)
+        {
+            let (pcap_max_bytes, min) = (capture.pcap_max_bytes, PcapWriter::MAX_BYTES_MIN);
+            return Err(eio!(
+                "Invalid maximum pcap file size of {pcap_max_bytes} bytes (expected at least {min} bytes)"
+            ));
+        }
+
+        // Validate the transports
+        match self.mode {
+            Mode::Udp if self.udp.listen.is_empty() && self.udp_to_serial => {
+                return Err(eio!("Missing `udp.listen` (required unless `mode = \"tcp\"` or `udp_to_serial = false`)"));
+            }
+            Mode::Tcp if self.tcp.is_none() => return Err(eio!("Missing `[tcp]` (required for `mode = \"tcp\"`)")),
+            Mode::Tcp if !self.udp.listen.is_empty() || self.udp.send.is_some() => {
+                return Err(eio!("Unexpected `udp.listen` or `udp.send` (UDP is disabled for `mode = \"tcp\"`)"));
+            }
+            _ => (),
+        }
+        if self.tcp.as_ref().is_some_and(|tcp| tcp.listen.is_some() == tcp.connect.is_some()) {
+            return Err(eio!("`[tcp]` requires exactly one of `tcp.listen` and `tcp.connect`"));
+        }
+
+        // Validate that the UDP addresses resolve so that a typo fails at startup instead of silently sending nothing
+        if self.mode == Mode::Udp && self.udp_to_serial {
+            Self::validate_address("udp.listen", &self.udp.listen)?;
+        }
+        if let Some(send) = self.udp.send.as_deref().filter(|send| *send != Destination::AUTO) {
+            Self::validate_address("udp.send", send)?;
+        }
+        if let Some(bind) = self.udp.bind.as_deref() {
+            Self::validate_address("udp.bind", bind)?;
+        }
+
+        // Validate the multicast groups
+        let mut groups = self.udp.multicast.iter().flat_map(|multicast| multicast.groups.iter());
+        if let Some(group) = groups.find(|group| !group.is_multicast()) {
+            return Err(eio!("Invalid multicast group {group} (expected an address from 224.0.0.0/4 or ff00::/8)"));
+        }
+
+        // Validate the TCP client limit
+        if self.tcp.as_ref().is_some_and(|tcp| tcp.max_clients == 0) {
+            return Err(eio!("Invalid TCP client limit of 0 (expected at least 1)"));
+        }
+
+        // Validate the maximum buffer age
+        if self.udp.max_buffer_age_ms == Some(0) {
+            return Err(eio!("Invalid maximum buffer age of 0ms (expected at least 1ms)"));
+        }
+
+        // Validate the adoption grace
+        if self.udp.adopt_after_packets == Some(0) {
+            return Err(eio!("Invalid adoption grace of 0 packets (expected at least 1 packet)"));
+        }
+
+        // Validate the receive batch size
+        if !(1..=BatchReceiver::SLOTS_MAX).contains(&self.udp.recv_batch) {
+            let (recv_batch, max) = (self.udp.recv_batch, BatchReceiver::SLOTS_MAX);
+            return Err(eio!("Invalid receive batch size {recv_batch} (expected 1 to {max})"));
+        }
+
+        // Validate the header specification
+        if let Some(header) = self.udp.header.as_ref() {
+            header.validate()?;
+        }
+
+        // Validate that the incomplete marker leaves space for the payload
+        let marker_len = self.serial.incomplete_marker.as_ref().map(String::len).unwrap_or_default();
+        if let Some(header) = self.udp.header.as_ref().filter(|header| marker_len >= header.mtu - header.size()) {
+            return Err(eio!(
+                "Incomplete marker exceeds the maximum payload size ({} bytes)",
+                header.mtu - header.size()
+            ));
+        }
+
+        // Validate the source allowlist
+        if let Some(allowed_sources) = self.udp.allowed_sources.as_ref() {
+            SourceAllowlist::new(allowed_sources)?;
+        }
+
+        // Validate the log file rotation
+        if self.log.max_size_bytes == 0 {
+            return Err(eio!("Invalid log file size limit of 0 bytes (expected at least 1 byte)"));
+        }
+        if self.log.max_files == 0 {
+            return Err(eio!("Invalid amount of 0 rotated log files (expected at least 1)"));
+        }
+
+        // Validate the routing rules
+        if let Some(routing) = self.routing.as_ref() {
+            if routing.rules.is_empty() {
+                return Err(eio!("Missing routing rules (expected at least one `[[routing.rules]]`)"));
+            }
+            if routing.rules.iter().any(|rule| rule.prefix.as_bytes().is_empty()) {
+                return Err(eio!("Invalid empty routing prefix (expected at least 1 byte)"));
+            }
+            if routing.rules.iter().any(|rule| rule.send == Destination::AUTO) {
+                return Err(eio!("Invalid routing destination `auto` (expected an address)"));
+            }
+            for rule in &routing.rules {
+                Self::validate_address("routing.rules.send", &rule.send)?;
+            }
+        }
+
+        // Validate the CPU affinity
+        if let Some(cpus) = self.cpu_affinity.as_ref() {
+            if cpus.is_empty() {
+                return Err(eio!("Invalid empty CPU affinity (expected at least one CPU index)"));
+            }
+            if (1..cpus.len()).any(|index| cpus[..index].contains(&cpus[index])) {
+                return Err(eio!("Invalid CPU affinity {cpus:?} (expected each CPU index at most once)"));
+            }
+        }
+
+        // Validate the real-time scheduling
+        if let Some(scheduling) = self.scheduling.as_ref() {
+            if !(1..=99).contains(&scheduling.priority) {
+                return Err(eio!("Invalid real-time priority {} (expected 1 to 99)", scheduling.priority));
+            }
+        }
+
+        // Validate that the sandbox is available
+        if self.sandbox.is_some() && !cfg!(feature = "sandbox") {
+            return Err(eio!("The syscall sandbox requires a build with the `sandbox` feature"));
+        }
+
+        // Reject logically inconsistent combinations
+        self.validate_consistency()
+    }
+    /// Validates that an address resolves to at least one socket address and returns the first one
+    pub fn validate_address(field: &str, address: &str) -> Result<SocketAddr, Error> {
+        let mut resolved = (address.to_socket_addrs())
+            .map_err(|e| eio!("Invalid `{field}` address {address} ({e}; expected a resolvable `host:port`)"))?;
+        resolved
+            .next()
+            .ok_or_else(|| eio!("Invalid `{field}` address {address} (the host does not resolve to any address)"))
+    }
+    /// Validates that the config does not contain contradictory or silently ineffective combinations
+    fn validate_consistency(&self) -> Result<(), Error> {
+        // Validate the forwarding directions
+        if !self.serial_to_udp && !self.udp_to_serial {
+            return Err(eio!(
+                "`serial_to_udp = false` and `udp_to_serial = false` disable all forwarding; enable at least one \
+                direction"
+            ));
+        }
+        let serial_to_udp_options = [
+            (self.udp.send.is_some(), "`udp.send`"),
+            (self.routing.is_some(), "`[routing]`"),
+            (self.capture.as_ref().is_some_and(|capture| capture.path.is_some()), "`capture.path`"),
+            (self.verify_writes.is_some(), "`[verify_writes]`"),
+            (self.serial.start_trigger.is_some(), "`serial.start_trigger`"),
+            (self.serial.stop_trigger.is_some(), "`serial.stop_trigger`"),
+        ];
+        if let Some((_, option)) = serial_to_udp_options.iter().find(|(set, _)| *set && !self.serial_to_udp) {
+            return Err(eio!(
+                "{option} has no effect with `serial_to_udp = false` since the serial device's output is not read; \
+                enable the direction or remove the option"
+            ));
+        }
+        let groups = self.udp.multicast.as_ref().is_some_and(|multicast| !multicast.groups.is_empty());
+        let udp_to_serial_options = [
+            (self.udp.send.as_deref() == Some(Destination::AUTO), "`udp.send = \"auto\"`"),
+            (self.udp.allowed_sources.is_some(), "`udp.allowed_sources`"),
+            (self.udp.break_datagram.is_some(), "`udp.break_datagram`"),
+            (groups, "`udp.multicast.groups`"),
+            (self.jitter_buffer.is_some(), "`[jitter_buffer]`"),
+            (self.serial.udp_to_serial_coalesce_ms.is_some(), "`serial.udp_to_serial_coalesce_ms`"),
+            (self.serial.udp_to_serial_queue_depth.is_some(), "`serial.udp_to_serial_queue_depth`"),
+            (self.verify_writes.is_some(), "`[verify_writes]`"),
+        ];
+        if let Some((_, option)) = udp_to_serial_options.iter().find(|(set, _)| *set && !self.udp_to_serial) {
+            return Err(eio!(
+                "{option} has no effect with `udp_to_serial = false` since the input from the network is not written \
+                to the serial device; enable the direction or remove the option"
+            ));
+        }
+
+        // Validate the capture
+        if self.capture.as_ref().is_some_and(|capture| capture.path.is_none() && capture.pcap.is_none()) {
+            return Err(eio!(
+                "`[capture]` has no effect without `capture.path` or `capture.pcap`; set a capture file or remove the \
+                section"
+            ));
+        }
+
+        // Validate the serial framing
+        if self.serial.framing == Framing::Line && self.serial.delimiter.is_empty() {
+            return Err(eio!(
+                "`serial.framing = \"line\"` requires a non-empty `serial.delimiter` to split frames; set a \
+                delimiter or use `serial.framing = \"raw\"`"
+            ));
+        }
+        if self.serial.udp_to_serial_framing == UdpToSerialFraming::Reframe && self.serial.framing == Framing::Raw {
+            return Err(eio!(
+                "`serial.udp_to_serial_framing = \"reframe\"` has no effect with `serial.framing = \"raw\"` \
+                since raw framing has no frame boundaries; set `serial.framing = \"line\"` or remove the option"
+            ));
+        }
+        let coalesce = self.serial.coalesce_ms.is_some_and(|coalesce_ms| coalesce_ms > 0);
+        if coalesce && self.serial.framing != Framing::Raw {
+            return Err(eio!(
+                "`serial.coalesce_ms` requires `serial.framing = \"raw\"` since framed output is already split at the \
+                frame boundaries; remove the option or use raw framing"
+            ));
+        }
+        if coalesce && self.serial.final_idle_ms.is_some() {
+            return Err(eio!(
+                "`serial.coalesce_ms` and `serial.final_idle_ms` are mutually exclusive since coalesced output is \
+                always forwarded once the line is idle; remove one of the options"
+            ));
+        }
+        if self.serial.framing == Framing::LengthPrefixed && self.serial.final_idle_ms.is_some() {
+            return Err(eio!(
+                "`serial.final_idle_ms` has no effect with `serial.framing = \"length-prefixed\"` since partial frames \
+                are never forwarded; remove the option"
+            ));
+        }
+        if self.serial.framing == Framing::LengthPrefixed && self.serial.incomplete_marker.is_some() {
+            return Err(eio!(
+                "`serial.incomplete_marker` has no effect with `serial.framing = \"length-prefixed\"` since partial \
+                frames are never forwarded; remove the option"
+            ));
+        }
+
+        // Validate the modem control lines
+        if self.serial.initial_rts.is_some() && self.serial.flow_control == FlowControl::RtsCts {
+            return Err(eio!(
+                "`serial.initial_rts` conflicts with `serial.flow_control = \"rts-cts\"` since the driver controls the \
+                RTS line for hardware flow control; remove the option or disable hardware flow control"
+            ));
+        }
+
+        // Validate the overflow strategy
+        if self.serial.overflow == Overflow::Block && self.serial.flow_control == FlowControl::None {
+            return Err(eio!(
+                "`serial.overflow = \"block\"` requires `serial.flow_control` since the device would \
+                otherwise keep sending and the kernel would drop the input; enable flow control or use `drop-new` or \
+                `drop-old`"
+            ));
+        }
+
+        // Validate the periodic flush
+        if self.serial.flush_interval_ms.is_some() && self.serial.flush_mode == FlushMode::None {
+            return Err(eio!(
+                "`serial.flush_interval_ms` has no effect with `serial.flush_mode = \"none\"` since flushing does \
+                not wait for the transmission; set `flush_mode = \"drain\"` or remove the option"
+            ));
+        }
+
+        // Validate the maximum UDP->serial delay
+        let max_delay_ms = self.serial.udp_to_serial_max_delay_ms;
+        let buffered = self.jitter_buffer.is_some()
+            || self.serial.udp_to_serial_coalesce_ms.is_some()
+            || self.serial.udp_to_serial_queue_depth.is_some();
+        if max_delay_ms.is_some() && !buffered {
+            return Err(eio!(
+                "`serial.udp_to_serial_max_delay_ms` has no effect without `[jitter_buffer]`, \
+                `serial.udp_to_serial_coalesce_ms` or `serial.udp_to_serial_queue_depth` since input is only dropped \
+                when it leaves a buffer; enable a buffer or remove the option"
+            ));
+        }
+        let buffer_delay_ms =
+            (self.jitter_buffer.as_ref().map(|jitter| jitter.delay_ms)).or(self.serial.udp_to_serial_coalesce_ms);
+        if let (Some(max_delay_ms), Some(buffer_delay_ms)) = (max_delay_ms, buffer_delay_ms) {
+            if max_delay_ms <= buffer_delay_ms {
+                return Err(eio!(
+                    "`serial.udp_to_serial_max_delay_ms` ({max_delay_ms}ms) does not exceed the buffering delay \
+                    ({buffer_delay_ms}ms), so all input would be dropped; raise the maximum delay"
+                ));
+            }
+        }
+
+        // Validate the write coalescing
+        if self.serial.udp_to_serial_coalesce_ms.is_some() && self.jitter_buffer.is_some() {
+            return Err(eio!(
+                "`serial.udp_to_serial_coalesce_ms` contradicts `[jitter_buffer]` which paces each datagram \
+                individually; remove one of them"
+            ));
+        }
+        if self.serial.udp_to_serial_queue_depth.is_some()
+            && (self.jitter_buffer.is_some() || self.serial.udp_to_serial_coalesce_ms.is_some())
+        {
+            return Err(eio!(
+                "`serial.udp_to_serial_queue_depth` contradicts `[jitter_buffer]` and \
+                `serial.udp_to_serial_coalesce_ms` which already buffer the datagrams; remove one of them"
+            ));
+        }
+        if self.serial.udp_to_serial_coalesce_ms.is_some() && self.verify_writes.is_some() {
+            return Err(eio!(
+                "`serial.udp_to_serial_coalesce_ms` contradicts `[verify_writes]` which verifies each datagram \
+                individually; remove one of them"
+            ));
+        }
+
+        // Validate the break datagram
+        let buffered = self.jitter_buffer.is_some()
+            || self.serial.udp_to_serial_coalesce_ms.is_some()
+            || self.serial.udp_to_serial_queue_depth.is_some();
+        if self.udp.break_datagram.is_some() && buffered {
+            return Err(eio!(
+                "`udp.break_datagram` contradicts `[jitter_buffer]`, `serial.udp_to_serial_coalesce_ms` and \
+                `serial.udp_to_serial_queue_depth` since the break would overtake the buffered datagrams; remove the \
+                buffering or the break datagram"
+            ));
+        }
+
+        // Validate the multicast configuration
+        if let Some(multicast) = self.udp.multicast.as_ref() {
+            if multicast.interface.is_none() && multicast.groups.is_empty() {
+                return Err(eio!(
+                    "`[udp.multicast]` has no effect without `udp.multicast.interface` or `udp.multicast.groups`; \
+                    set one of them or remove the section"
+                ));
+            }
+            if !multicast.groups.is_empty() && self.mode != Mode::Udp {
+                return Err(eio!(
+                    "`udp.multicast.groups` has no effect outside UDP mode since the groups are joined on the \
+                    listen socket; set `mode = \"udp\"` or remove the groups"
+                ));
+            }
+        }
+
+        // Validate the UDP address families
+        let send = self.udp.send.as_deref().filter(|send| *send != Destination::AUTO).and_then(Udp::resolve);
+        if let (Some(send), Some(bind)) = (send, Udp::resolve(self.udp.bind_address())) {
+            if send.is_ipv6() != bind.is_ipv6() {
+                return Err(eio!(
+                    "`udp.bind` {bind} cannot send to `udp.send` {send} since the address families differ; use \
+                    either IPv4 or IPv6 addresses for both"
+                ));
+            }
+        }
+        if !self.udp.multicast_loop && !self.udp.sends_multicast() {
+            return Err(eio!(
+                "`udp.multicast_loop = false` has no effect unless `udp.send` is a multicast address; set a multicast \
+                send address or remove the option"
+            ));
+        }
+
+        // Validate the UDP destination
+        if self.udp.heartbeat.is_some() && self.udp.send.is_none() {
+            return Err(eio!(
+                "`[udp.heartbeat]` has no effect without `udp.send` since the heartbeat is sent to the send \
+                destination; set `udp.send` or remove the heartbeat"
+            ));
+        }
+        if self.udp.pending_buffer > 0 && self.udp.send.is_none() {
+            return Err(eio!(
+                "`udp.pending_buffer` has no effect without `udp.send` since the buffered output would \
+                never be sent; set `udp.send` or remove the option"
+            ));
+        }
+        let send_auto = self.udp.send.as_deref().is_none_or(|send| send == Destination::AUTO);
+        if self.udp.resolve_interval_ms.is_some_and(|ms| ms > 0) && send_auto {
+            return Err(eio!(
+                "`udp.resolve_interval_ms` has no effect without a configured `udp.send` address since there is \
+                nothing to resolve; set a send address or remove the option"
+            ));
+        }
+        let adopt_grace = self.udp.adopt_after_packets.is_some() || self.udp.adopt_after_ms.is_some();
+        if adopt_grace && self.udp.send.as_deref() != Some(Destination::AUTO) {
+            return Err(eio!(
+                "`udp.adopt_after_packets` and `udp.adopt_after_ms` have no effect without `udp.send = \"auto\"` \
+                since no sender is adopted; set `udp.send = \"auto\"` or remove the options"
+            ));
+        }
+        if self.udp.max_buffer_age_ms.is_some() && self.udp.pending_buffer == 0 {
+            return Err(eio!(
+                "`udp.max_buffer_age_ms` has no effect without `udp.pending_buffer` since no output is \
+                buffered; set a pending buffer size or remove the option"
+            ));
+        }
+
+        // Validate the logger
+        let log = &self.log;
+        if !log.enabled
+            && (log.format != LogFormat::Ascii
+                || log.dedup_consecutive
+                || log.include_device
+                || log.include_direction
+                || log.timestamp.is_some()
+                || log.path.is_some())
+        {
+            return Err(eio!(
+                "`log.format`, `log.dedup_consecutive`, `log.include_device`, `log.include_direction`, \
+                `log.timestamp` and `log.path` only affect the I/O log; set `log.enabled = true` or remove the options"
+            ));
+        }
+        if log.compress && log.path.is_none() {
+            return Err(eio!(
+                "`log.compress` only affects the rotated log files; set `log.path` or remove `log.compress = true`"
+            ));
+        }
+
+        // Validate the routing
+        if self.routing.is_some() && self.serial.framing != Framing::Line {
+            return Err(eio!(
+                "`[routing]` requires `serial.framing = \"line\"` since frames are routed by their prefix; set the \
+                framing or remove the section"
+            ));
+        }
+        if self.routing.is_some() && self.mode == Mode::Tcp {
+            return Err(eio!(
+                "`[routing]` has no effect with `mode = \"tcp\"` since UDP is disabled; use `mode = \"udp\"` or \
+                remove the section"
+            ));
+        }
+
+        // Validate the maximum packet size
+        if let Some(max_packet_size) = self.udp.max_packet_size.filter(|&size| size > self.buffers.udp_buffer) {
+            return Err(eio!(
+                "`udp.max_packet_size` of {max_packet_size} bytes exceeds `buffers.udp_buffer` of {} bytes, so larger \
+                datagrams would be dropped as truncated anyway; raise the buffer size or lower the limit",
+                self.buffers.udp_buffer
+            ));
+        }
+
+        // Validate the source allowlist
+        if self.udp.allowed_sources.is_some() && self.mode == Mode::Tcp {
+            return Err(eio!(
+                "`udp.allowed_sources` has no effect with `mode = \"tcp\"` since UDP is disabled; use `mode = \"udp\"` \
+                or remove the option"
+            ));
+        }
+
+        // Validate the reconnect configuration
+        let reconnect = &self.reconnect;
+        if !reconnect.enabled && (reconnect.max_retries != 0 || reconnect.max_cycles != 0) {
+            return Err(eio!(
+                "`reconnect.max_retries` and `reconnect.max_cycles` have no effect while reconnecting is \
+                disabled; set `reconnect.enabled = true` or remove the options"
+            ));
+        }
+        if reconnect.initial_delay_ms > reconnect.max_delay_ms {
+            return Err(eio!(
+                "`reconnect.initial_delay_ms` exceeds `reconnect.max_delay_ms`, so the backoff could \
+                never grow; lower the initial delay or raise the maximum delay"
+            ));
+        }
+        if reconnect.enabled && reconnect.initial_delay_ms == 0 {
+            return Err(eio!(
+                "Invalid `reconnect.initial_delay_ms` of 0 (expected at least 1) since the doubling backoff would \
+                never grow and the reopen attempts would spin"
+            ));
+        }
+
+        // Validate the sandbox
+        if reconnect.enabled && self.sandbox.as_ref().is_some_and(|sandbox| !sandbox.allow_open) {
+            return Err(eio!(
+                "`reconnect.enabled = true` requires `sandbox.allow_open = true` since the sandbox would \
+                otherwise prevent the serial device from being reopened; allow opening files or disable reconnecting"
+            ));
+        }
+        if self.log.path.is_some() && self.sandbox.as_ref().is_some_and(|sandbox| !sandbox.allow_open) {
+            return Err(eio!(
+                "`log.path` requires `sandbox.allow_open = true` since the sandbox would otherwise prevent the log \
+                file from being rotated or reopened on reload; allow opening files or log to `log.sink`"
+            ));
+        }
+        if self.status.is_some() && self.sandbox.as_ref().is_some_and(|sandbox| !sandbox.allow_open) {
+            return Err(eio!(
+                "`[status]` requires `sandbox.allow_open = true` since the sandbox would otherwise prevent the \
+                status file from being written; allow opening files or remove the section"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Converts the config into a generic value with all secrets redacted
+    pub fn to_redacted_value(&self) -> Result<toml::Value, Error> {
+        let mut value = toml::Value::try_from(self)?;
+        json::redact(&mut value);
+        Ok(value)
+    }
+    /// Lists the settings that differ in the other config as `section.key`, or as `key` for top-level settings and
+    /// sections that are only present in one config
+    pub fn changed_settings(&self, other: &Self) -> Result<Vec<String>, Error> {
+        let (toml::Value::Table(old), toml::Value::Table(new)) =
+            (toml::Value::try_from(self)?, toml::Value::try_from(other)?)
+        else {
+            return Err(eio!("Failed to compare the configs (expected tables)"));
+        };
+        let mut changed = Vec::new();
+        for key in old.keys().chain(new.keys()).collect::<BTreeSet<_>>() {
+            match (old.get(key), new.get(key)) {
+                (Some(toml::Value::Table(old)), Some(toml::Value::Table(new))) => {
+                    let subkeys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+                    let subkeys = subkeys.into_iter().filter(|subkey| old.get(*subkey) != new.get(*subkey));
+                    changed.extend(subkeys.map(|subkey| format!("{key}.{subkey}")));
+                }
+                (old, new) if old != new => changed.push(key.clone()),
+                _ => (),
+            }
+        }
+        Ok(changed)
     }
 
     /// Checks if a file exists
     fn file_exists(path: &str) -> Result<bool, Error> {
         Ok(Path::new(path).is_file())
     }
-    /// Loads the config from a file
-    fn load_file(path: &str) -> Result<Self, Error> {
+    /// Loads the config from a file and expands the bridges into one config each
+    fn load_file(path: &str) -> Result<Vec<Self>, Error> {
+        Self::load_bridges(path).map_err(|e| e.in_category(ErrorCategory::Config))
+    }
+    /// Loads the config from a file and expands the bridges into one config each without classifying the errors
+    fn load_bridges(path: &str) -> Result<Vec<Self>, Error> {
         let config_bin = fs::read(path)?;
-        let config: Self = toml::from_slice(&config_bin)?;
-        Ok(config)
+        let mut config = Self::parse_file(path, &config_bin)?;
+
+        // Load a single bridge config as-is
+        let Some(bridges) = config.remove("bridge") else {
+            let config: Self = toml::Value::Table(config).try_into()?;
+            config.validate()?;
+            return Ok(vec![config]);
+        };
+
+        // Expand the bridges into one config each
+        if config.contains_key("serial") || config.contains_key("udp") {
+            return Err(eio!(
+                "`[serial]` and `[udp]` cannot be combined with `[[bridge]]` since each bridge has its own serial \
+                device and UDP sockets; move the sections into the bridges"
+            ));
+        }
+        let toml::Value::Array(bridges) = bridges else {
+            return Err(eio!("Invalid bridges (expected `[[bridge]]` tables)"));
+        };
+        if bridges.is_empty() {
+            return Err(eio!("Invalid bridges (expected at least one `[[bridge]]` table)"));
+        }
+        let mut configs = Vec::with_capacity(bridges.len());
+        for (index, bridge) in bridges.into_iter().enumerate() {
+            let toml::Value::Table(bridge) = bridge else {
+                return Err(eio!("Invalid bridge {index} (expected a `[[bridge]]` table)"));
+            };
+            if let Some(key) = bridge.keys().find(|key| !matches!(key.as_str(), "serial" | "udp")) {
+                return Err(eio!("Invalid bridge {index} (unexpected key `{key}`; expected `serial` and `udp`)"));
+            }
+
+            // Replace the serial and UDP sections
+            let mut bridge_config = config.clone();
+            bridge_config.extend(bridge);
+            let bridge_config: Self =
+                (toml::Value::Table(bridge_config).try_into()).map_err(|e| eio!("Invalid bridge {index} ({e})"))?;
+            bridge_config.validate().map_err(|e| eio!("Invalid bridge {index} ({})", e.to_string().trim_end()))?;
+            configs.push(bridge_config);
+        }
+        Self::validate_bridges(&configs)?;
+        Ok(configs)
+    }
+
+    /// Parses a config file as JSON or YAML depending on its extension, or as TOML otherwise
+    fn parse_file(path: &str, config_bin: &[u8]) -> Result<toml::value::Table, Error> {
+        let config_str = || str::from_utf8(config_bin).map_err(|e| eio!("Invalid config file encoding ({e})"));
+        let config = match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("json") => json::from_json(config_str()?)?,
+            Some("yaml" | "yml") => yaml::from_yaml(config_str()?)?,
+            _ => return Ok(toml::from_slice(config_bin)?),
+        };
+        match config {
+            toml::Value::Table(config) => Ok(config),
+            _ => Err(eio!("Invalid config file (expected a table/object at the top level)")),
+        }
+    }
+
+    /// Validates that the bridges do not share a device or address
+    fn validate_bridges(configs: &[Self]) -> Result<(), Error> {
+        // Note: the sections outside of the bridges are the same for all bridges
+        let (Some(config), true) = (configs.first(), configs.len() > 1) else {
+            return Ok(());
+        };
+
+        // Validate that the process-wide resources are not claimed by each bridge
+        let shared = [
+            (config.mode == Mode::Tcp, "`mode = \"tcp\"`"),
+            (config.tcp.is_some(), "`[tcp]`"),
+            (config.control.is_some(), "`[control]`"),
+            (config.metrics.listen.is_some(), "`metrics.listen`"),
+            (config.status.is_some(), "`[status]`"),
+            (config.capture.is_some(), "`[capture]`"),
+            (config.log.path.is_some(), "`log.path`"),
+        ];
+        if let Some((_, option)) = shared.iter().find(|(set, _)| *set) {
+            return Err(eio!(
+                "{option} is not supported with multiple bridges since every bridge would claim the same socket or \
+                file; remove the option or run one process per bridge"
+            ));
+        }
+
+        // Validate that the bridges have distinct devices and listen addresses
+        for (index, config) in configs.iter().enumerate() {
+            let previous = &configs[..index];
+            if previous.iter().any(|previous| previous.serial.device == config.serial.device) {
+                return Err(eio!("Invalid bridge {index} (device {} is used by another bridge)", config.serial.device));
+            }
+            if config.udp_to_serial && previous.iter().any(|previous| previous.udp.listen == config.udp.listen) {
+                return Err(eio!("Invalid bridge {index} (address {} is used by another bridge)", config.udp.listen));
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,277 @@
+//! A line-based control socket
+
+use crate::{
+    config,
+    error::Error,
+    server::{Direction, Server},
+};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Mutex,
+    },
+    thread::Scope,
+};
+
+/// A tail event
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A chunk of forwarded data
+    Data(Vec<u8>),
+    /// The given amount of chunks has been dropped because the subscriber could not keep up
+    Lagged(usize),
+}
+
+/// A tail subscriber
+#[derive(Debug)]
+struct Subscriber {
+    /// The subscription ID
+    id: u64,
+    /// The subscribed direction
+    direction: Direction,
+    /// The bounded channel to the subscriber
+    sender: SyncSender<Event>,
+    /// The amount of chunks dropped since the last successful send
+    dropped: usize,
+}
+impl Subscriber {
+    /// Sends a chunk to the subscriber and returns whether the subscriber is still connected
+    fn send(&mut self, data: &[u8]) -> bool {
+        // Report previously dropped chunks first
+        if self.dropped > 0 {
+            match self.sender.try_send(Event::Lagged(self.dropped)) {
+                Ok(_) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    return true;
+                }
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+
+        // Send the chunk itself
+        match self.sender.try_send(Event::Data(data.to_vec())) {
+            Ok(_) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+/// The shared tail state
+#[derive(Debug, Default)]
+struct TailState {
+    /// The next subscription ID
+    next_id: u64,
+    /// The subscribers
+    subscribers: Vec<Subscriber>,
+    /// The most recent serial->UDP bytes
+    history_rx: VecDeque<u8>,
+    /// The most recent UDP->serial bytes
+    history_tx: VecDeque<u8>,
+}
+impl TailState {
+    /// Gets the history for the given direction
+    fn history_mut(&mut self, direction: Direction) -> &mut VecDeque<u8> {
+        match direction {
+            Direction::SerialToUdp => &mut self.history_rx,
+            Direction::UdpToSerial => &mut self.history_tx,
+        }
+    }
+}
+
+/// Distributes copies of the forwarded data to tail subscribers
+#[derive(Debug)]
+pub struct Tail {
+    /// The shared state
+    state: Mutex<TailState>,
+    /// The amount of recent bytes to keep per direction
+    history: usize,
+    /// The amount of chunks to buffer per subscriber
+    backlog: usize,
+}
+impl Tail {
+    /// Creates a new tail
+    pub fn new(history: usize, backlog: usize) -> Self {
+        // Note: a zero-sized sync channel is a rendezvous channel where `try_send` would always fail
+        let backlog = backlog.max(1);
+        Self { state: Mutex::default(), history, backlog }
+    }
+
+    /// Pushes a copy of a forwarded chunk to the history and all subscribers of the given direction
+    pub fn push(&self, direction: Direction, data: &[u8]) {
+        let mut state = self.state.lock().expect("Tail state is poisoned");
+
+        // Update the history
+        let history = state.history_mut(direction);
+        history.extend(data);
+        let excess = history.len().saturating_sub(self.history);
+        history.drain(..excess);
+
+        // Distribute the chunk and remove disconnected subscribers
+        state.subscribers.retain_mut(|subscriber| match subscriber.direction == direction {
+            true => subscriber.send(data),
+            false => true,
+        });
+    }
+
+    /// Subscribes to the given direction, starting with the most recent bytes
+    pub fn subscribe(&self, direction: Direction) -> (u64, Receiver<Event>) {
+        let mut state = self.state.lock().expect("Tail state is poisoned");
+        let (sender, receiver) = mpsc::sync_channel(self.backlog);
+
+        // Replay the history
+        let history: Vec<u8> = state.history_mut(direction).iter().copied().collect();
+        if !history.is_empty() {
+            // Note: this cannot fail since the channel is empty and has a capacity of at least one
+            let _ = sender.try_send(Event::Data(history));
+        }
+
+        // Register the subscriber
+        let id = state.next_id;
+        state.next_id += 1;
+        state.subscribers.push(Subscriber { id, direction, sender, dropped: 0 });
+        (id, receiver)
+    }
+    /// Removes the subscription with the given ID
+    pub fn unsubscribe(&self, id: u64) {
+        let mut state = self.state.lock().expect("Tail state is poisoned");
+        state.subscribers.retain(|subscriber| subscriber.id != id);
+    }
+}
+
+/// The control socket
+#[derive(Debug)]
+pub struct Control {
+    /// The TCP listener
+    listener: TcpListener,
+    /// The tail subscriptions
+    tail: Tail,
+}
+impl Control {
+    /// Creates a new control socket
+    pub fn new(config: &config::Control) -> Result<Self, Error> {
+        let listener = TcpListener::bind(&config.listen)?;
+        let tail = Tail::new(config.tail_history, config.tail_backlog);
+        Ok(Self { listener, tail })
+    }
+
+    /// The tail subscriptions
+    pub fn tail(&self) -> &Tail {
+        &self.tail
+    }
+
+    /// Starts the control socket runloop
+    pub fn runloop<'scope>(
+        &'scope self,
+        scope: &'scope Scope<'scope, '_>,
+        server: &'scope Server,
+    ) -> Result<(), Error> {
+        for stream in self.listener.incoming() {
+            // Ignore failed connection attempts
+            let Ok(stream) = stream else {
+                continue;
+            };
+
+            // Only accept local connections
+            if !stream.peer_addr().is_ok_and(|address| address.ip().is_loopback()) {
+                let _ = writeln!(&stream, "error: only local connections are allowed");
+                continue;
+            }
+
+            // Handle the connection
+            scope.spawn(move || self.handle(scope, server, stream));
+        }
+        Ok(())
+    }
+    /// Handles a control connection
+    fn handle<'scope>(&'scope self, scope: &'scope Scope<'scope, '_>, server: &Server, stream: TcpStream) {
+        // Serve the connection and unsubscribe on disconnect
+        let mut subscriptions = Vec::new();
+        let _ = self.serve(scope, server, stream, &mut subscriptions);
+        for id in subscriptions {
+            self.tail.unsubscribe(id);
+        }
+    }
+    /// Reads and executes the commands from a control connection
+    fn serve<'scope>(
+        &'scope self,
+        scope: &'scope Scope<'scope, '_>,
+        server: &Server,
+        stream: TcpStream,
+        subscriptions: &mut Vec<u64>,
+    ) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            // Parse the command
+            let line = line?;
+            let command: Vec<&str> = line.split_whitespace().collect();
+            match command.as_slice() {
+                [] => continue,
+                ["status"] => write!(writer, "{}", server.status())?,
+                ["status", "--json"] => match server.status_json() {
+                    Ok(json) => writeln!(writer, "{json}")?,
+                    Err(e) => write!(writer, "error: {e}")?,
+                },
+                ["stats"] => write!(writer, "{}", server.stats())?,
+                ["metrics"] => write!(writer, "{}", server.metrics())?,
+                ["pause"] => {
+                    server.set_paused(true);
+                    writeln!(writer, "ok")?;
+                }
+                ["resume"] => {
+                    server.set_paused(false);
+                    writeln!(writer, "ok")?;
+                }
+                ["flush"] => Self::respond(&mut writer, server.flush_pending())?,
+                ["reload"] => Self::respond(&mut writer, server.reload())?,
+                ["reopen"] => Self::respond(&mut writer, server.reopen_serial())?,
+                ["break"] => Self::respond(&mut writer, server.send_break())?,
+                ["set", "send", address] => Self::respond(&mut writer, server.set_send(address))?,
+                ["tail", direction] => {
+                    // Parse the direction
+                    let direction = match *direction {
+                        "rx" => Direction::SerialToUdp,
+                        "tx" => Direction::UdpToSerial,
+                        _ => {
+                            writeln!(writer, "error: invalid direction (expected `rx` or `tx`)")?;
+                            continue;
+                        }
+                    };
+
+                    // Subscribe and stream the data to the client
+                    let (id, receiver) = self.tail.subscribe(direction);
+                    let stream = writer.try_clone()?;
+                    scope.spawn(move || Self::stream(stream, receiver));
+                    subscriptions.push(id);
+                }
+                _ => writeln!(writer, "error: unknown command")?,
+            }
+        }
+        Ok(())
+    }
+    /// Writes the response for a command result
+    fn respond(writer: &mut TcpStream, result: Result<(), Error>) -> io::Result<()> {
+        match result {
+            Ok(_) => writeln!(writer, "ok"),
+            // Note: the error display is already terminated by a newline
+            Err(e) => write!(writer, "error: {e}"),
+        }
+    }
+    /// Streams the tail events to the client
+    fn stream(mut stream: TcpStream, receiver: Receiver<Event>) -> io::Result<()> {
+        for event in receiver {
+            match event {
+                Event::Data(data) => stream.write_all(&data)?,
+                Event::Lagged(dropped) => write!(stream, "\n[lagged: {dropped} chunks dropped]\n")?,
+            }
+        }
+        Ok(())
+    }
+}
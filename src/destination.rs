@@ -0,0 +1,275 @@
+//! Implements the UDP send destination
+
+use std::{
+    fmt::{self, Display, Formatter},
+    net::{SocketAddr, ToSocketAddrs},
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The interval between resolution attempts for an unresolvable address
+const RESOLVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The send target
+#[derive(Debug)]
+enum Target {
+    /// Nothing is sent
+    None,
+    /// The destination is adopted from the sender of the most recent inbound packet
+    Auto {
+        /// The adopted address
+        adopted: Option<SocketAddr>,
+        /// The sender that competes with the adopted address
+        candidate: Option<Candidate>,
+    },
+    /// The destination is resolved from a configured address
+    Address {
+        /// The configured address
+        address: String,
+        /// The resolved address
+        resolved: Option<SocketAddr>,
+        /// The time of the last resolution attempt
+        last_attempt: Option<Instant>,
+    },
+}
+impl Target {
+    /// Creates a new target from the configured send address
+    fn new(send: Option<&str>) -> Self {
+        match send {
+            None => Self::None,
+            Some(Destination::AUTO) => Self::Auto { adopted: None, candidate: None },
+            Some(address) => {
+                // Note: socket addresses are known right away; names are resolved by the resolver runloop
+                let resolved = address.parse().ok();
+                Self::Address { address: address.to_string(), resolved, last_attempt: None }
+            }
+        }
+    }
+}
+
+/// A sender that competes with the adopted address in auto mode
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    /// The sender address
+    address: SocketAddr,
+    /// The amount of consecutive packets from the sender
+    packets: u64,
+    /// The time of the first of these packets
+    since: Instant,
+}
+
+/// The UDP send destination
+#[derive(Debug)]
+pub struct Destination {
+    /// The send target
+    target: Mutex<Target>,
+    /// Signals that the target has been replaced
+    changed: Condvar,
+    /// The interval to re-resolve a resolved address, or `None` to resolve it only once
+    resolve_interval: Option<Duration>,
+    /// The amount of consecutive packets after which a new sender replaces the adopted address in auto mode
+    adopt_after_packets: Option<u64>,
+    /// The time after which a new sender that keeps sending replaces the adopted address in auto mode
+    adopt_after: Option<Duration>,
+}
+impl Destination {
+    /// The magic send address to adopt the sender of inbound packets
+    pub const AUTO: &'static str = "auto";
+
+    /// Creates a new destination from the configured send address, the interval to re-resolve it and the grace before
+    /// a new sender replaces the adopted address in auto mode
+    ///
+    /// # Adoption grace
+    /// If neither `adopt_after_packets` nor `adopt_after` is set, each new sender is adopted immediately. Otherwise, a
+    /// new sender is adopted once it has sent the given amount of consecutive packets or has kept sending for the given
+    /// time, whichever comes first; a packet from the adopted address resets the competing sender. The first sender is
+    /// always adopted immediately since there is nothing to flap from.
+    pub fn new(
+        send: Option<&str>,
+        resolve_interval: Option<Duration>,
+        adopt_after_packets: Option<u64>,
+        adopt_after: Option<Duration>,
+    ) -> Self {
+        let target = Mutex::new(Target::new(send));
+        Self { target, changed: Condvar::new(), resolve_interval, adopt_after_packets, adopt_after }
+    }
+
+    /// Replaces the destination with a new configured send address
+    pub fn set(&self, send: Option<&str>) {
+        let mut target = self.target.lock().expect("Destination is poisoned");
+        *target = Target::new(send);
+        self.changed.notify_all();
+    }
+
+    /// Whether nothing is sent at all
+    pub fn is_none(&self) -> bool {
+        let target = self.target.lock().expect("Destination is poisoned");
+        matches!(*target, Target::None)
+    }
+
+    /// Gets the destination address if it is known yet
+    ///
+    /// # Note
+    /// This never blocks on name resolution since configured addresses are resolved by [`Destination::runloop`].
+    pub fn get(&self) -> Option<SocketAddr> {
+        let target = self.target.lock().expect("Destination is poisoned");
+        match &*target {
+            Target::None => None,
+            Target::Auto { adopted, .. } => *adopted,
+            Target::Address { resolved, .. } => *resolved,
+        }
+    }
+
+    /// The resolver runloop which resolves and re-resolves a configured address in the background and calls
+    /// `on_resolved` once an unknown address has been resolved
+    ///
+    /// # Note
+    /// The name is resolved without holding the lock, so a slow or unreachable resolver never stalls the forwarding.
+    pub fn runloop<F>(&self, mut on_resolved: F)
+    where
+        F: FnMut(),
+    {
+        let mut target = self.target.lock().expect("Destination is poisoned");
+        loop {
+            // Wait until a resolution is due
+            // Note: a resolved address is re-resolved only if appropriate, an unresolved one is retried periodically
+            let Target::Address { address, resolved, last_attempt } = &*target else {
+                target = self.changed.wait(target).expect("Destination is poisoned");
+                continue;
+            };
+            let interval = if resolved.is_some() { self.resolve_interval } else { Some(RESOLVE_INTERVAL) };
+            match (interval, *last_attempt) {
+                (_, None) => (),
+                (None, Some(_)) => {
+                    target = self.changed.wait(target).expect("Destination is poisoned");
+                    continue;
+                }
+                (Some(interval), Some(last_attempt)) if last_attempt.elapsed() < interval => {
+                    let remaining = interval - last_attempt.elapsed();
+                    (target, _) = self.changed.wait_timeout(target, remaining).expect("Destination is poisoned");
+                    continue;
+                }
+                (Some(_), Some(_)) => (),
+            }
+
+            // Resolve the address without holding the lock
+            let (address, first_attempt) = (address.clone(), last_attempt.is_none());
+            drop(target);
+            let result = address.to_socket_addrs().map(|mut addresses| addresses.next());
+            target = self.target.lock().expect("Destination is poisoned");
+
+            // Store the result unless the address has been replaced in the meantime
+            // Note: a re-resolution failure keeps the previous address since it might still be valid
+            let Target::Address { address: current, resolved, last_attempt } = &mut *target else {
+                continue;
+            };
+            if *current != address {
+                continue;
+            }
+            let was_unknown = resolved.is_none();
+            match result {
+                Ok(Some(address_)) if resolved.is_some_and(|resolved| resolved != address_) => {
+                    let previous = resolved.replace(address_).expect("Resolved address has vanished");
+                    info!("Destination {address} has changed from {previous} to {address_}");
+                }
+                Ok(Some(address_)) => *resolved = Some(address_),
+                Ok(None) if first_attempt => warn!("{address} did not resolve to any address; retrying"),
+                Err(e) if first_attempt => warn!("failed to resolve {address} ({e}); retrying"),
+                _ => (),
+            }
+            *last_attempt = Some(Instant::now());
+
+            // Notify about the newly known address without holding the lock
+            if was_unknown && resolved.is_some() {
+                drop(target);
+                on_resolved();
+                target = self.target.lock().expect("Destination is poisoned");
+            }
+        }
+    }
+
+    /// Adopts the sender of an inbound packet if the destination is `auto` and the sender has passed the adoption
+    /// grace, and returns whether the destination has been unknown until now
+    pub fn adopt(&self, sender: SocketAddr) -> bool {
+        let mut target = self.target.lock().expect("Destination is poisoned");
+        let Target::Auto { adopted, candidate } = &mut *target else {
+            return false;
+        };
+
+        // Adopt the first sender immediately and reset the competing sender if the adopted address is still active
+        let Some(previous) = *adopted else {
+            *adopted = Some(sender);
+            return true;
+        };
+        if previous == sender {
+            *candidate = None;
+            return false;
+        }
+
+        // Track the competing sender
+        let candidate_ = match candidate {
+            Some(candidate) if candidate.address == sender => {
+                candidate.packets += 1;
+                *candidate
+            }
+            _ => *candidate.insert(Candidate { address: sender, packets: 1, since: Instant::now() }),
+        };
+
+        // Adopt the competing sender once it has passed the grace
+        let grace_packets = self.adopt_after_packets.is_some_and(|packets| candidate_.packets >= packets);
+        let grace_time = self.adopt_after.is_some_and(|grace| candidate_.since.elapsed() >= grace);
+        let no_grace = self.adopt_after_packets.is_none() && self.adopt_after.is_none();
+        if grace_packets || grace_time || no_grace {
+            info!("Destination has changed from {previous} to {sender}");
+            (*adopted, *candidate) = (Some(sender), None);
+        }
+        false
+    }
+}
+impl Display for Destination {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let target = self.target.lock().expect("Destination is poisoned");
+        match &*target {
+            Target::None => write!(f, "none"),
+            Target::Auto { adopted: Some(adopted), .. } => write!(f, "auto ({adopted})"),
+            Target::Auto { adopted: None, .. } => write!(f, "auto (unknown)"),
+            Target::Address { address, resolved: Some(resolved), .. } => write!(f, "{address} ({resolved})"),
+            Target::Address { address, resolved: None, .. } => write!(f, "{address} (unresolved)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{mpsc, Arc},
+        thread,
+    };
+
+    #[test]
+    fn socket_addresses_are_known_immediately() {
+        let destination = Destination::new(Some("127.0.0.1:6666"), None, None, None);
+        assert_eq!(destination.get(), Some("127.0.0.1:6666".parse().expect("Invalid address")));
+    }
+
+    #[test]
+    fn hostnames_are_resolved_in_the_background() {
+        let destination = Arc::new(Destination::new(Some("localhost:6666"), None, None, None));
+        assert_eq!(destination.get(), None);
+
+        // Start the resolver and wait until the address has been resolved
+        let (sender, receiver) = mpsc::channel();
+        {
+            let destination = destination.clone();
+            thread::spawn(move || destination.runloop(|| sender.send(()).expect("Test has finished")));
+        }
+        receiver.recv_timeout(Duration::from_secs(10)).expect("Address has not been resolved");
+        let resolved = destination.get().expect("Resolved address is missing");
+        assert!(resolved.ip().is_loopback() && resolved.port() == 6666);
+
+        // Replace the address and check that the new one is used right away
+        destination.set(Some("127.0.0.2:7777"));
+        assert_eq!(destination.get(), Some("127.0.0.2:7777".parse().expect("Invalid address")));
+    }
+}
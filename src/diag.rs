@@ -0,0 +1,103 @@
+//! Implements the diagnostic log for operational messages like warnings and lifecycle events
+//!
+//! # Note
+//! The diagnostic log is always written to stderr and is kept apart from the I/O log in [`crate::logger`], so that the
+//! forwarded data never ends up between the operational messages.
+
+use crate::config::LogLevel;
+use std::{
+    env,
+    fmt::Arguments,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+/// The environment variable to override the configured log level
+const LEVEL_ENV: &str = "SERIALSERVER_LOG";
+
+/// The current log level
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+/// Whether the log level has been overridden via the environment
+static OVERRIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Writes a diagnostic message if the given level is enabled
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {{
+        let level: $crate::config::LogLevel = $level;
+        if $crate::diag::enabled(level) {
+            $crate::diag::write(level, format_args!($($arg)*));
+        }
+    }};
+}
+/// Writes a diagnostic message with [`LogLevel::Error`]
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::log!($crate::config::LogLevel::Error, $($arg)*)
+    };
+}
+/// Writes a diagnostic message with [`LogLevel::Warn`]
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::log!($crate::config::LogLevel::Warn, $($arg)*)
+    };
+}
+/// Writes a diagnostic message with [`LogLevel::Info`]
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log!($crate::config::LogLevel::Info, $($arg)*)
+    };
+}
+/// Writes a diagnostic message with [`LogLevel::Debug`]
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::log!($crate::config::LogLevel::Debug, $($arg)*)
+    };
+}
+
+/// Applies the log level from the environment if set
+///
+/// # Note
+/// This should be called first thing in `main` so that the messages emitted while loading the config are filtered too.
+pub fn init() {
+    let Ok(level) = env::var(LEVEL_ENV) else {
+        return;
+    };
+    match toml::Value::String(level.to_lowercase()).try_into::<LogLevel>() {
+        Ok(level) => {
+            LEVEL.store(level as u8, Ordering::Relaxed);
+            OVERRIDDEN.store(true, Ordering::Relaxed);
+        }
+        Err(_) => warn!("ignoring invalid {LEVEL_ENV}={level} (expected off, error, warn, info or debug)"),
+    }
+}
+
+/// Sets the configured log level unless it has been overridden via the environment
+pub fn set_level(level: LogLevel) {
+    if !OVERRIDDEN.load(Ordering::Relaxed) {
+        LEVEL.store(level as u8, Ordering::Relaxed);
+    }
+}
+
+/// Whether messages of the given level are written
+pub fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Writes a diagnostic message to stderr
+///
+/// # Note
+/// Use the [`error!`](crate::error!), [`warn!`](crate::warn!), [`info!`](crate::info!) and [`debug!`](crate::debug!)
+/// macros instead, which skip formatting the message if the level is disabled.
+pub fn write(level: LogLevel, message: Arguments) {
+    let prefix = match level {
+        LogLevel::Off | LogLevel::Info => "",
+        LogLevel::Error => "Error: ",
+        LogLevel::Warn => "Warning: ",
+        LogLevel::Debug => "Debug: ",
+    };
+    eprintln!("{prefix}{message}");
+}
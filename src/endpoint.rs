@@ -0,0 +1,66 @@
+//! A lightweight TCP endpoint that serves the statistics as JSON
+
+use crate::{error::Error, server::Server};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+/// Serves the statistics as JSON document to each connection, framed as HTTP response if the client sends an HTTP
+/// request
+#[derive(Debug)]
+pub struct MetricsEndpoint {
+    /// The TCP listener
+    listener: TcpListener,
+}
+impl MetricsEndpoint {
+    /// The time to wait for an HTTP request before the document is sent without HTTP framing
+    const REQUEST_TIMEOUT: Duration = Duration::from_millis(100);
+
+    /// The time to wait for a slow client to accept the document
+    const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Creates a new metrics endpoint listening on the given address
+    pub fn new(listen: &str) -> Result<Self, Error> {
+        let listener =
+            TcpListener::bind(listen).map_err(|e| eio!("Failed to bind the metrics endpoint {listen} ({e})"))?;
+        Ok(Self { listener })
+    }
+
+    /// Starts the metrics endpoint runloop
+    pub fn runloop(&self, server: &Server) -> Result<(), Error> {
+        for stream in self.listener.incoming() {
+            // Ignore failed connection attempts
+            let Ok(stream) = stream else {
+                continue;
+            };
+
+            // Serve the connection and ignore clients that disconnect early
+            let _ = Self::serve(server, stream);
+        }
+        Ok(())
+    }
+    /// Writes the statistics to a connection
+    fn serve(server: &Server, mut stream: TcpStream) -> Result<(), Error> {
+        // Check whether the client sends an HTTP request
+        // Note: the request is not parsed any further since the endpoint serves a single document
+        stream.set_read_timeout(Some(Self::REQUEST_TIMEOUT))?;
+        stream.set_write_timeout(Some(Self::WRITE_TIMEOUT))?;
+        let mut request = [0; 4];
+        let is_http = stream.read_exact(&mut request).is_ok() && &request == b"GET ";
+
+        // Write the document
+        let document = server.stats_json();
+        match is_http {
+            true => write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n\
+                {document}",
+                document.len()
+            )?,
+            false => writeln!(stream, "{document}")?,
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //! Implements the crate's error type
 
+#[cfg(feature = "backtrace")]
+use std::{backtrace::Backtrace, env, sync::OnceLock};
 use std::{
-    backtrace::{Backtrace, BacktraceStatus},
     error,
     ffi::NulError,
     fmt::{self, Display, Formatter},
@@ -17,24 +18,89 @@ macro_rules! eio {
     }};
 }
 
+/// The class of an error for programmatic handling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A transient error where the operation can simply be retried (`EINTR`, `EAGAIN`)
+    Transient,
+    /// The device has been disconnected (`ENODEV`, `ENXIO`, `EIO`)
+    Disconnected,
+    /// Any other error
+    Other,
+}
+
+/// The origin of an error, which determines the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// The config is missing or invalid
+    Config,
+    /// The serial device cannot be opened, set up, read or written
+    Serial,
+    /// A UDP socket cannot be bound, read or written
+    Udp,
+    /// Any other I/O error, e.g. of a log file or the control socket
+    Io,
+    /// Any other error
+    Other,
+}
+impl ErrorCategory {
+    /// The process exit code for a fatal error of this category
+    ///
+    /// # Note
+    /// Exit code 3 is reserved for giving up after the maximum amount of reconnect cycles.
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Self::Other => 1,
+            Self::Config => 2,
+            Self::Serial => 4,
+            Self::Udp => 5,
+            Self::Io => 6,
+        }
+    }
+}
+
 /// The crates error type
 #[derive(Debug)]
 pub struct Error {
     /// The error description
     error: String,
+    /// The raw OS error number if the error has been caused by an OS error
+    errno: Option<i32>,
+    /// The origin of the error if known
+    category: Option<ErrorCategory>,
     /// The underlying error
     source: Option<Box<dyn std::error::Error + Send>>,
-    /// The backtrace
-    backtrace: Backtrace,
+    /// The backtrace if backtraces are enabled
+    #[cfg(feature = "backtrace")]
+    backtrace: Option<Backtrace>,
 }
 impl Error {
+    /// The environment variables to enable backtraces in the order of precedence
+    ///
+    /// # Note
+    /// Like for [`std::backtrace::Backtrace::capture`], `RUST_LIB_BACKTRACE` takes precedence over `RUST_BACKTRACE`, so
+    /// backtraces of panics and errors can be toggled independently.
+    #[cfg(feature = "backtrace")]
+    const BACKTRACE_ENVS: [&'static str; 3] = ["SERIALSERVER_BACKTRACE", "RUST_LIB_BACKTRACE", "RUST_BACKTRACE"];
+    /// The error numbers that indicate a disconnected device (`EIO`, `ENXIO`, `ENODEV`)
+    ///
+    /// # Note
+    /// These error numbers are identical on all supported Unix platforms.
+    const ERRNO_DISCONNECTED: [i32; 3] = [5, 6, 19];
+
     /// Creates a new error
     pub fn new<T>(error: T) -> Self
     where
         T: ToString,
     {
-        let backtrace = Backtrace::capture();
-        Self { error: error.to_string(), source: None, backtrace }
+        Self {
+            error: error.to_string(),
+            errno: None,
+            category: None,
+            source: None,
+            #[cfg(feature = "backtrace")]
+            backtrace: Self::capture_backtrace(),
+        }
     }
     /// Creates a new error
     pub fn with_error<T>(error: T) -> Self
@@ -42,8 +108,62 @@ impl Error {
         T: std::error::Error + Send + 'static,
     {
         let error = Box::new(error);
-        let backtrace = Backtrace::capture();
-        Self { error: error.to_string(), source: Some(error), backtrace }
+        Self {
+            error: error.to_string(),
+            errno: None,
+            category: None,
+            source: Some(error),
+            #[cfg(feature = "backtrace")]
+            backtrace: Self::capture_backtrace(),
+        }
+    }
+
+    /// The raw OS error number if the error has been caused by an OS error
+    pub const fn errno(&self) -> Option<i32> {
+        self.errno
+    }
+    /// The class of the error
+    pub fn kind(&self) -> ErrorKind {
+        // Classify the transient errors via the standard library since `EAGAIN` differs between platforms
+        let kind = (self.source.as_ref()).and_then(|source| source.downcast_ref::<io::Error>()).map(io::Error::kind);
+        match (self.errno, kind) {
+            (_, Some(io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock)) => ErrorKind::Transient,
+            (Some(errno), _) if Self::ERRNO_DISCONNECTED.contains(&errno) => ErrorKind::Disconnected,
+            _ => ErrorKind::Other,
+        }
+    }
+    /// The origin of the error
+    ///
+    /// # Note
+    /// Errors without a known origin are classified as [`ErrorCategory::Io`] if they have been caused by an I/O error.
+    pub fn category(&self) -> ErrorCategory {
+        let is_io = self.source.as_ref().is_some_and(|source| source.is::<io::Error>());
+        match (self.category, is_io) {
+            (Some(category), _) => category,
+            (None, true) => ErrorCategory::Io,
+            (None, false) => ErrorCategory::Other,
+        }
+    }
+    /// Sets the origin of the error unless a more specific origin has already been set
+    pub fn in_category(mut self, category: ErrorCategory) -> Self {
+        self.category.get_or_insert(category);
+        self
+    }
+
+    /// Captures a backtrace if backtraces are enabled via the first set variable of [`Error::BACKTRACE_ENVS`] with any
+    /// value other than `0`
+    ///
+    /// # Note
+    /// Capturing a backtrace is expensive, so errors that occur in the runloops are cheap unless backtraces have been
+    /// explicitly requested. Builds without the `backtrace` feature never capture backtraces.
+    #[cfg(feature = "backtrace")]
+    fn capture_backtrace() -> Option<Backtrace> {
+        static ENABLED: OnceLock<bool> = OnceLock::new();
+        let enabled = ENABLED.get_or_init(|| {
+            let value = Self::BACKTRACE_ENVS.iter().find_map(|name| env::var(name).ok());
+            value.is_some_and(|value| value != "0")
+        });
+        enabled.then(Backtrace::force_capture)
     }
 }
 impl Display for Error {
@@ -52,10 +172,11 @@ impl Display for Error {
         writeln!(f, "{}", self.error)?;
 
         // Print backtrace
-        if self.backtrace.status() == BacktraceStatus::Captured {
+        #[cfg(feature = "backtrace")]
+        if let Some(backtrace) = self.backtrace.as_ref() {
             writeln!(f)?;
             writeln!(f, "Backtrace:")?;
-            writeln!(f, "{}", self.backtrace)?;
+            writeln!(f, "{backtrace}")?;
         }
         Ok(())
     }
@@ -68,7 +189,8 @@ impl error::Error for Error {
 }
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Self {
-        Self::with_error(error)
+        let errno = error.raw_os_error();
+        Self { errno, ..Self::with_error(error) }
     }
 }
 impl From<NulError> for Error {
@@ -81,3 +203,8 @@ impl From<toml::de::Error> for Error {
         Self::with_error(error)
     }
 }
+impl From<toml::ser::Error> for Error {
+    fn from(error: toml::ser::Error) -> Self {
+        Self::with_error(error)
+    }
+}
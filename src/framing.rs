@@ -0,0 +1,239 @@
+//! Implements the serial framing
+
+use crate::{
+    config::{self, Endianness, Framing, UdpToSerialFraming},
+    serial::{SerialDevice, Terminator},
+};
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// How frames are delimited on the serial line
+#[derive(Debug, Clone)]
+enum Encoding {
+    /// Frames are terminated by the delimiter
+    Delimiter(Vec<u8>),
+    /// Frames are preceded by a length prefix of the given size and byte order
+    LengthPrefix(usize, Endianness),
+}
+
+/// Re-encodes datagrams with the configured serial framing
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    /// The frame encoding
+    encoding: Encoding,
+}
+impl Encoder {
+    /// Creates a new encoder, or returns `None` if datagrams are written as-is
+    pub fn new(config: &config::Serial) -> Option<Self> {
+        match (config.udp_to_serial_framing, config.framing) {
+            (UdpToSerialFraming::Preserve, _) | (_, Framing::Raw) => None,
+            (UdpToSerialFraming::Reframe, Framing::Line) => {
+                Some(Self { encoding: Encoding::Delimiter(config.delimiter.as_bytes().to_vec()) })
+            }
+            (UdpToSerialFraming::Reframe, Framing::LengthPrefixed) => {
+                Some(Self { encoding: Encoding::LengthPrefix(config.length_bytes, config.endianness) })
+            }
+        }
+    }
+
+    /// Creates a frame buffer that holds the frames of datagrams up to `max_len` bytes without reallocating
+    pub fn buffer(&self, max_len: usize) -> Vec<u8> {
+        match &self.encoding {
+            Encoding::Delimiter(delimiter) => Vec::with_capacity(max_len + delimiter.len()),
+            Encoding::LengthPrefix(length_bytes, _) => Vec::with_capacity(max_len + length_bytes),
+        }
+    }
+
+    /// Encodes a datagram into a serial frame, or returns `None` if the datagram is too long for the length prefix
+    ///
+    /// # Note
+    /// The delimiter is only appended if the datagram is not already terminated by it.
+    pub fn encode<'a>(&self, datagram: &'a [u8], buf: &'a mut Vec<u8>) -> Option<&'a [u8]> {
+        match &self.encoding {
+            // Write already terminated datagrams as-is
+            Encoding::Delimiter(delimiter) if datagram.ends_with(delimiter) => Some(datagram),
+            Encoding::Delimiter(delimiter) => {
+                // Append the delimiter
+                buf.clear();
+                buf.extend_from_slice(datagram);
+                buf.extend_from_slice(delimiter);
+                Some(buf)
+            }
+            Encoding::LengthPrefix(length_bytes, endian) => {
+                // Prepend the length prefix
+                let len = datagram.len() as u64;
+                if *length_bytes < 8 && len >> (length_bytes * 8) != 0 {
+                    return None;
+                }
+                buf.clear();
+                match endian {
+                    Endianness::Big => buf.extend_from_slice(&len.to_be_bytes()[8 - length_bytes..]),
+                    Endianness::Little => buf.extend_from_slice(&len.to_le_bytes()[..*length_bytes]),
+                }
+                buf.extend_from_slice(datagram);
+                Some(buf)
+            }
+        }
+    }
+}
+
+/// Reassembles length-prefixed frames from the serial output
+#[derive(Debug)]
+pub struct Deframer {
+    /// The size of the length prefix
+    length_bytes: usize,
+    /// The byte order of the length prefix
+    endian: Endianness,
+    /// The length prefix and the payload of the current frame read so far
+    pending: Vec<u8>,
+}
+impl Deframer {
+    /// Creates a new deframer, or returns `None` if the serial output is not length-prefixed
+    pub fn new(config: &config::Serial) -> Option<Self> {
+        match config.framing {
+            Framing::Raw | Framing::Line => None,
+            Framing::LengthPrefixed => {
+                Some(Self { length_bytes: config.length_bytes, endian: config.endianness, pending: Vec::new() })
+            }
+        }
+    }
+
+    /// Reads the next frame payload into `frame` and returns its length, or `0` if no complete frame has been read
+    ///
+    /// # Note
+    /// `observe` receives the raw bytes as they are read. A declared length that exceeds `frame` is treated as garbage,
+    /// so the first byte is dropped via `drop` and the stream is resynchronized at the next byte. A partial frame is
+    /// kept across reads that return early due to the read timeout, but dropped once `cancel` is set. Empty frames are
+    /// skipped.
+    pub fn read_frame<O, D>(
+        &mut self,
+        serial: &mut SerialDevice,
+        frame: &mut [u8],
+        cancel: &AtomicBool,
+        mut observe: O,
+        mut drop: D,
+    ) -> io::Result<usize>
+    where
+        O: FnMut(&[u8]),
+        D: FnMut(&'static str, &[u8]),
+    {
+        loop {
+            // Determine the amount of missing bytes
+            let missing = match self.declared_len() {
+                None => self.length_bytes - self.pending.len(),
+                Some(declared) if declared > frame.len() as u64 => {
+                    // Resynchronize at the next byte
+                    drop("invalid frame length", &self.pending[..1]);
+                    self.pending.remove(0);
+                    continue;
+                }
+                Some(declared) if self.pending.len() as u64 == self.length_bytes as u64 + declared => {
+                    // Return the payload
+                    let len = declared as usize;
+                    frame[..len].copy_from_slice(&self.pending[self.length_bytes..]);
+                    self.pending.clear();
+                    return Ok(len);
+                }
+                Some(declared) => (self.length_bytes as u64 + declared) as usize - self.pending.len(),
+            };
+
+            // Read the missing bytes
+            let start = self.pending.len();
+            self.pending.resize(start + missing, 0);
+            let result = serial.read_chunk(&mut self.pending[start..], Terminator::Full, None, cancel);
+            let bytes_read = result.as_ref().map(|(bytes_read, _)| *bytes_read).unwrap_or_default();
+            self.pending.truncate(start + bytes_read);
+            observe(&self.pending[start..]);
+
+            // Return early if the read has been interrupted
+            let (_, complete) = result?;
+            if !complete {
+                if cancel.load(Ordering::Relaxed) {
+                    self.reset(&mut drop);
+                }
+                return Ok(0);
+            }
+        }
+    }
+
+    /// Drops the partial frame, e.g. because the device has been reopened
+    pub fn reset<D>(&mut self, mut drop: D)
+    where
+        D: FnMut(&'static str, &[u8]),
+    {
+        if !self.pending.is_empty() {
+            drop("incomplete frame", &self.pending);
+            self.pending.clear();
+        }
+    }
+
+    /// The declared payload length if the length prefix is complete
+    fn declared_len(&self) -> Option<u64> {
+        let prefix = self.pending.get(..self.length_bytes)?;
+        let declared = match self.endian {
+            Endianness::Big => prefix.iter().fold(0, |len, byte| len << 8 | *byte as u64),
+            Endianness::Little => prefix.iter().rev().fold(0, |len, byte| len << 8 | *byte as u64),
+        };
+        Some(declared)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::serial::tests::Pty;
+    use std::{io::Write, thread, time::Duration};
+
+    /// Reads the next frame, failing on any dropped data, and passes the raw bytes to `observed`
+    fn read_frame(deframer: &mut Deframer, serial: &mut SerialDevice, observed: &mut Vec<u8>) -> Vec<u8> {
+        let (cancel, mut frame) = (AtomicBool::new(false), [0; 64]);
+        let observe = |raw: &[u8]| observed.extend_from_slice(raw);
+        let drop = |reason, data: &[u8]| panic!("Unexpected drop of {data:?} ({reason})");
+        let len = deframer.read_frame(serial, &mut frame, &cancel, observe, drop).expect("Failed to read frame");
+        frame[..len].to_vec()
+    }
+
+    #[test]
+    fn frames_are_reassembled_across_reads_that_split_the_length_prefix() {
+        let pty = Pty::new();
+        let config = pty.config("framing = \"length-prefixed\"\nlength_bytes = 2");
+        let mut serial = SerialDevice::new(&config).expect("Failed to open PTY");
+        let mut deframer = Deframer::new(&config).expect("Missing deframer for length-prefixed framing");
+
+        // Write the frames in parts that split the first length prefix between its bytes and the second one before its
+        // last byte, so that the reads of the prefixes straddle the writes
+        let parts: [&[u8]; 4] = [&[0x00], &[0x05, b'h', b'e'], &[b'l', b'l', b'o', 0x00], &[0x03, b'a', b'b', b'c']];
+        let mut observed = Vec::new();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for part in parts {
+                    (&pty.primary).write_all(part).expect("Failed to write to PTY");
+                    thread::sleep(Duration::from_millis(20));
+                }
+            });
+            assert_eq!(read_frame(&mut deframer, &mut serial, &mut observed), b"hello");
+            assert_eq!(read_frame(&mut deframer, &mut serial, &mut observed), b"abc");
+        });
+        assert_eq!(observed, parts.concat());
+    }
+
+    #[test]
+    fn length_prefix_is_kept_if_the_payload_is_late() {
+        let pty = Pty::new();
+        let config = pty.config("framing = \"length-prefixed\"\nlength_bytes = 2\nread_timeout_ms = 50");
+        let mut serial = SerialDevice::new(&config).expect("Failed to open PTY");
+        let mut deframer = Deframer::new(&config).expect("Missing deframer for length-prefixed framing");
+
+        // Write the length prefix only, so that the read of the payload times out
+        let mut observed = Vec::new();
+        (&pty.primary).write_all(&[0x00, 0x05]).expect("Failed to write to PTY");
+        assert_eq!(read_frame(&mut deframer, &mut serial, &mut observed), b"");
+
+        // Write the payload which completes the frame
+        (&pty.primary).write_all(b"hello").expect("Failed to write to PTY");
+        assert_eq!(read_frame(&mut deframer, &mut serial, &mut observed), b"hello");
+        assert_eq!(observed, b"\x00\x05hello");
+    }
+}
@@ -0,0 +1,332 @@
+//! Implements a minimal gzip encoder for the rotated log files
+//!
+//! # Format
+//! The data is compressed as a single deflate block with the fixed Huffman codes (RFC 1951) and LZ77 matches found via
+//! hash chains, and wrapped into a gzip member (RFC 1952). This trades some compression ratio for simplicity; the
+//! output is readable by all standard tools like `gzip -d` or `zcat`.
+
+/// The size of the LZ77 window
+const WINDOW: usize = 32 * 1024;
+/// The minimum length of a match
+const MIN_MATCH: usize = 3;
+/// The maximum length of a match
+const MAX_MATCH: usize = 258;
+/// The maximum amount of candidates to check per position
+const MAX_CHAIN: usize = 64;
+/// The amount of bits of the hash of the next three bytes
+const HASH_BITS: u32 = 15;
+
+/// The base lengths of the length symbols 257 to 285
+const LENGTH_BASE: [u16; 29] =
+    [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+/// The amount of extra bits of the length symbols 257 to 285
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+/// The base distances of the distance symbols 0 to 29
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+/// The amount of extra bits of the distance symbols 0 to 29
+const DISTANCE_EXTRA: [u8; 30] =
+    [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// The CRC-32 lookup table (IEEE polynomial, reflected)
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+/// Writes bits in deflate order (least significant bit first)
+#[derive(Debug, Default)]
+struct BitWriter {
+    /// The output
+    out: Vec<u8>,
+    /// The pending bits
+    bits: u64,
+    /// The amount of pending bits
+    len: u32,
+}
+impl BitWriter {
+    /// Writes the lowest `len` bits of `value`
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits |= (value as u64) << self.len;
+        self.len += len;
+        while self.len >= 8 {
+            self.out.push(self.bits as u8);
+            (self.bits, self.len) = (self.bits >> 8, self.len - 8);
+        }
+    }
+    /// Writes a Huffman code, which is stored most significant bit first
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+    /// Writes a literal or length symbol with the fixed Huffman code
+    fn write_symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xc0 + symbol - 280, 8),
+        }
+    }
+    /// Writes a match with the given length and distance
+    fn write_match(&mut self, len: usize, distance: usize) {
+        // Note: the tables are sorted, so the symbol is the last entry with a base that does not exceed the value
+        let index = LENGTH_BASE.partition_point(|base| *base as usize <= len) - 1;
+        self.write_symbol(257 + index as u16);
+        self.write((len - LENGTH_BASE[index] as usize) as u32, LENGTH_EXTRA[index] as u32);
+        let index = DISTANCE_BASE.partition_point(|base| *base as usize <= distance) - 1;
+        self.write_code(index as u32, 5);
+        self.write((distance - DISTANCE_BASE[index] as usize) as u32, DISTANCE_EXTRA[index] as u32);
+    }
+    /// Flushes the pending bits padded to a full byte and returns the output
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Computes the CRC-32 of the data
+pub fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!0u32, |crc, byte| CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8));
+    !crc
+}
+
+/// Compresses the data into a deflate stream
+fn deflate(data: &[u8]) -> Vec<u8> {
+    /// Hashes the three bytes at the start of the slice
+    fn hash(bytes: &[u8]) -> usize {
+        let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        (value.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    }
+    /// Inserts the position into the hash chains
+    fn insert(data: &[u8], pos: usize, head: &mut [usize], prev: &mut [usize]) {
+        if pos + MIN_MATCH <= data.len() {
+            let hash = hash(&data[pos..]);
+            (prev[pos % WINDOW], head[hash]) = (head[hash], pos + 1);
+        }
+    }
+
+    // Write the block header (final block, fixed Huffman codes)
+    let mut writer = BitWriter::default();
+    writer.write(0b1, 1);
+    writer.write(0b01, 2);
+
+    // Find the matches via hash chains of the positions with the same hash
+    // Note: the chains store the position plus one so that zero marks the end of a chain; since the chains are only
+    // followed within the window, the links are stored in a ring buffer of the window size
+    let (mut head, mut prev) = (vec![0usize; 1 << HASH_BITS], vec![0usize; WINDOW]);
+    let mut pos = 0;
+    while pos < data.len() {
+        // Find the longest match within the window
+        let (mut best_len, mut best_distance) = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let (max_len, mut candidate, mut chain) = ((data.len() - pos).min(MAX_MATCH), head[hash(&data[pos..])], 0);
+            while candidate > 0 && pos - (candidate - 1) <= WINDOW && chain < MAX_CHAIN {
+                let start = candidate - 1;
+                let len = data[start..].iter().zip(&data[pos..pos + max_len]).take_while(|(a, b)| a == b).count();
+                if len > best_len {
+                    (best_len, best_distance) = (len, pos - start);
+                }
+                (candidate, chain) = (prev[start % WINDOW], chain + 1);
+            }
+        }
+
+        // Write the match or the literal
+        if best_len >= MIN_MATCH {
+            writer.write_match(best_len, best_distance);
+            for pos in pos..pos + best_len {
+                insert(data, pos, &mut head, &mut prev);
+            }
+            pos += best_len;
+        } else {
+            writer.write_symbol(data[pos] as u16);
+            insert(data, pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+
+    // Write the end of block
+    writer.write_symbol(256);
+    writer.finish()
+}
+
+/// Compresses the data into a gzip member
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    // Write the header (no file name, no modification time, unknown OS)
+    let mut gzip = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    gzip.extend(deflate(data));
+
+    // Write the trailer
+    gzip.extend(crc32(data).to_le_bytes());
+    gzip.extend((data.len() as u32).to_le_bytes());
+    gzip
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    /// Reads bits in deflate order (least significant bit first)
+    struct BitReader<'a> {
+        /// The input
+        data: &'a [u8],
+        /// The position of the next bit
+        pos: usize,
+    }
+    impl BitReader<'_> {
+        /// Reads `len` bits
+        fn read(&mut self, len: u32) -> u32 {
+            let mut value = 0;
+            for bit in 0..len {
+                let byte = *self.data.get(self.pos / 8).expect("Truncated deflate stream");
+                value |= ((byte >> (self.pos % 8)) as u32 & 1) << bit;
+                self.pos += 1;
+            }
+            value
+        }
+        /// Reads a Huffman code of `len` bits, which is stored most significant bit first
+        fn read_code(&mut self, len: u32) -> u32 {
+            (0..len).fold(0, |code, _| code << 1 | self.read(1))
+        }
+        /// Reads a literal or length symbol with the fixed Huffman code
+        fn read_symbol(&mut self) -> u16 {
+            let code = self.read_code(7);
+            if code <= 0x17 {
+                return 256 + code as u16;
+            }
+            match code << 1 | self.read(1) {
+                code @ 0x30..=0xbf => (code - 0x30) as u16,
+                code @ 0xc0..=0xc7 => (280 + code - 0xc0) as u16,
+                code => (144 + (code << 1 | self.read(1)) - 0x190) as u16,
+            }
+        }
+    }
+
+    /// Decompresses a deflate stream with fixed Huffman blocks
+    ///
+    /// # Note
+    /// This only implements the subset of deflate that [`deflate`] emits, so it panics on stored or dynamic blocks.
+    fn inflate(data: &[u8]) -> Vec<u8> {
+        let (mut reader, mut out) = (BitReader { data, pos: 0 }, Vec::new());
+        loop {
+            let last = reader.read(1) == 1;
+            assert_eq!(reader.read(2), 0b01, "Unsupported block type");
+            loop {
+                match reader.read_symbol() {
+                    literal @ 0..=255 => out.push(literal as u8),
+                    256 => break,
+                    symbol => {
+                        let index = (symbol - 257) as usize;
+                        let len = LENGTH_BASE[index] as usize + reader.read(LENGTH_EXTRA[index] as u32) as usize;
+                        let index = reader.read_code(5) as usize;
+                        let distance =
+                            DISTANCE_BASE[index] as usize + reader.read(DISTANCE_EXTRA[index] as u32) as usize;
+                        assert!(distance <= out.len(), "Distance exceeds the output");
+                        for _ in 0..len {
+                            out.push(out[out.len() - distance]);
+                        }
+                    }
+                }
+            }
+            if last {
+                assert_eq!(reader.pos.div_ceil(8), data.len(), "Trailing data after the last block");
+                return out;
+            }
+        }
+    }
+
+    /// Decompresses a gzip member and checks its header and trailer
+    pub(crate) fn decode(gzip: &[u8]) -> Vec<u8> {
+        assert!(gzip.len() >= 18, "Truncated gzip member");
+        assert_eq!(gzip[..4], [0x1f, 0x8b, 8, 0], "Invalid gzip header");
+        let (body, trailer) = gzip[10..].split_at(gzip.len() - 18);
+        let data = inflate(body);
+        assert_eq!(trailer[..4], crc32(&data).to_le_bytes(), "Invalid CRC-32");
+        assert_eq!(trailer[4..], (data.len() as u32).to_le_bytes(), "Invalid size");
+        data
+    }
+
+    /// Decompresses the data via the system `gzip`, or returns `None` if `gzip` is not available
+    fn gunzip(data: &[u8]) -> Option<Vec<u8>> {
+        let child = Command::new("gzip").arg("-dc").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn();
+        let Ok(mut child) = child else {
+            eprintln!("Skipping the check against the system gzip: gzip is not available");
+            return None;
+        };
+        child.stdin.take().expect("Missing stdin").write_all(data).expect("Failed to write to gzip");
+        let output = child.wait_with_output().expect("Failed to wait for gzip");
+        assert!(output.status.success(), "gzip rejected the data");
+        Some(output.stdout)
+    }
+
+    /// Asserts that the data survives a round-trip through the decoder and, if available, the system `gzip`
+    fn assert_roundtrip(data: &[u8]) {
+        let encoded = encode(data);
+        assert_eq!(decode(&encoded), data);
+        if let Some(decoded) = gunzip(&encoded) {
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn roundtrip_empty() {
+        assert_roundtrip(b"");
+    }
+
+    #[test]
+    fn roundtrip_text() {
+        let text: String = (0..2000).map(|line| format!("[{line:>8}] RX Hello, world\\x0d\\x0a\n")).collect();
+        assert_roundtrip(text.as_bytes());
+        assert!(encode(text.as_bytes()).len() < text.len() / 4, "Repetitive text should compress well");
+    }
+
+    #[test]
+    fn roundtrip_binary() {
+        // Note: a simple xorshift generator yields incompressible data with all byte values
+        let mut state = 0x2545_f491_u32;
+        let data: Vec<u8> = (0..100_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        assert_roundtrip(&data);
+    }
+
+    #[test]
+    fn roundtrip_long_matches_and_distances() {
+        // Repeat a block with a distance close to the window size and long runs of the same byte
+        let block: Vec<u8> = (0..WINDOW as u32 - 7).map(|index| (index * 7 % 251) as u8).collect();
+        let mut data = block.clone();
+        data.extend(&block);
+        data.extend([b'a'; 10_000]);
+        assert_roundtrip(&data);
+    }
+}
@@ -0,0 +1,103 @@
+//! Implements the configurable datagram header
+
+use crate::{
+    clock,
+    config::{self, Endianness, HeaderFieldKind, TimestampClock},
+    error::Error,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Builds and parses the configured datagram header
+#[derive(Debug)]
+pub struct Header {
+    /// The ordered header fields
+    fields: Vec<config::HeaderField>,
+    /// The source ID
+    source_id: u64,
+    /// The next outgoing sequence number
+    sequence: AtomicU64,
+    /// The total header size
+    size: usize,
+    /// The clock for the timestamp field
+    clock: TimestampClock,
+}
+impl Header {
+    /// Creates a new header with the given clock for the timestamp field
+    pub fn new(config: &config::Header, clock: TimestampClock) -> Self {
+        let (fields, source_id, size) = (config.fields.clone(), config.source_id, config.size());
+        Self { fields, source_id, sequence: AtomicU64::new(0), size, clock }
+    }
+
+    /// The total header size
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Creates a datagram buffer that holds the datagrams for payloads up to `max_len` bytes without reallocating
+    pub fn buffer(&self, max_len: usize) -> Vec<u8> {
+        Vec::with_capacity(self.size + max_len)
+    }
+
+    /// Encodes the header for the given payload followed by the payload itself into `buf`
+    pub fn encode<'a>(&self, payload: &[u8], buf: &'a mut Vec<u8>) -> &'a [u8] {
+        buf.clear();
+        for field in &self.fields {
+            // Get the field value
+            let value = match field.field {
+                HeaderFieldKind::Length => payload.len() as u64,
+                HeaderFieldKind::Sequence => self.sequence.fetch_add(1, Ordering::Relaxed),
+                HeaderFieldKind::Timestamp => self.timestamp(),
+                HeaderFieldKind::SourceId => self.source_id,
+            };
+
+            // Write the truncated value with the configured endianness
+            match field.endian {
+                Endianness::Big => buf.extend_from_slice(&value.to_be_bytes()[8 - field.size..]),
+                Endianness::Little => buf.extend_from_slice(&value.to_le_bytes()[..field.size]),
+            }
+        }
+        buf.extend_from_slice(payload);
+        buf
+    }
+    /// Decodes and strips the header from a datagram and returns the payload
+    pub fn decode<'a>(&self, datagram: &'a [u8]) -> Result<&'a [u8], Error> {
+        // Split the datagram
+        if datagram.len() < self.size {
+            return Err(eio!("Datagram is shorter than the header ({} < {} bytes)", datagram.len(), self.size));
+        }
+        let (mut header, payload) = datagram.split_at(self.size);
+
+        // Parse and validate the fields
+        for field in &self.fields {
+            let (bytes, rest) = header.split_at(field.size);
+            header = rest;
+
+            // Validate the length field
+            if field.field == HeaderFieldKind::Length {
+                let length = Self::parse(bytes, field.endian);
+                if length != payload.len() as u64 {
+                    return Err(eio!("Invalid header length field ({length} != {} bytes)", payload.len()));
+                }
+            }
+        }
+        Ok(payload)
+    }
+
+    /// Parses a field value
+    fn parse(bytes: &[u8], endian: Endianness) -> u64 {
+        let mut value = [0; 8];
+        match endian {
+            Endianness::Big => value[8 - bytes.len()..].copy_from_slice(bytes),
+            Endianness::Little => value[..bytes.len()].copy_from_slice(bytes),
+        }
+        match endian {
+            Endianness::Big => u64::from_be_bytes(value),
+            Endianness::Little => u64::from_le_bytes(value),
+        }
+    }
+    /// The current timestamp in milliseconds since the clock's epoch
+    fn timestamp(&self) -> u64 {
+        let elapsed = clock::now(self.clock).unwrap_or_default();
+        elapsed.as_millis() as u64
+    }
+}
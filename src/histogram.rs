@@ -0,0 +1,112 @@
+//! Implements windowed chunk size histograms and cumulative latency histograms
+
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// The inclusive upper bounds of the chunk size buckets; larger chunks are counted in an additional overflow bucket
+const BOUNDS: [usize; 5] = [1, 8, 64, 256, 1024];
+
+/// Counts the chunk sizes of a direction within the current window
+#[derive(Debug, Default)]
+pub struct ChunkHistogram {
+    /// The amount of chunks per bucket within the current window
+    buckets: [AtomicU64; BOUNDS.len() + 1],
+}
+impl ChunkHistogram {
+    /// Records a chunk
+    pub fn record(&self, len: usize) {
+        let index = BOUNDS.iter().position(|bound| len <= *bound).unwrap_or(BOUNDS.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Takes the counts of the current window and starts a new window
+    pub fn take(&self) -> Histogram {
+        let counts = self.buckets.each_ref().map(|bucket| bucket.swap(0, Ordering::Relaxed));
+        Histogram { counts }
+    }
+}
+
+/// The chunk size counts of a window
+#[derive(Debug, Clone, Copy)]
+pub struct Histogram {
+    /// The amount of chunks per bucket
+    pub counts: [u64; BOUNDS.len() + 1],
+}
+impl Histogram {
+    /// Whether no chunks have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|count| *count == 0)
+    }
+}
+impl Display for Histogram {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Format each bucket as `<range>=<count>`
+        let mut lower = 1;
+        for (index, count) in self.counts.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            match BOUNDS.get(index) {
+                Some(upper) if *upper == lower => write!(f, "{upper}={count}")?,
+                Some(upper) => write!(f, "{lower}-{upper}={count}")?,
+                None => write!(f, ">{}={count}", lower - 1)?,
+            }
+            lower = BOUNDS.get(index).map_or(lower, |upper| upper + 1);
+        }
+        Ok(())
+    }
+}
+
+/// Accumulates latency observations in cumulative buckets for the Prometheus histogram format
+///
+/// # Note
+/// Observations only touch atomic counters, so recording never blocks and never contends with a scrape.
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    /// The inclusive upper bounds of the buckets in seconds
+    bounds: Vec<f64>,
+    /// The amount of observations per bucket; the last bucket counts the observations above all bounds
+    buckets: Vec<AtomicU64>,
+    /// The sum of all observations in microseconds
+    sum_us: AtomicU64,
+}
+impl LatencyHistogram {
+    /// Creates a new latency histogram with the given bucket bounds in milliseconds
+    pub fn new(bounds_ms: &[f64]) -> Self {
+        let bounds = bounds_ms.iter().map(|bound| bound / 1000.0).collect();
+        let buckets = (0..=bounds_ms.len()).map(|_| AtomicU64::new(0)).collect();
+        Self { bounds, buckets, sum_us: AtomicU64::new(0) }
+    }
+
+    /// Records an observation
+    pub fn observe(&self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        let index = self.bounds.iter().position(|bound| seconds <= *bound).unwrap_or(self.bounds.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Appends the histogram in the Prometheus text format with the given metric name and help text to `out`
+    pub fn write_prometheus(&self, name: &str, help: &str, out: &mut String) -> fmt::Result {
+        writeln!(out, "# HELP {name} {help}")?;
+        writeln!(out, "# TYPE {name} histogram")?;
+
+        // Write the cumulative buckets
+        let mut count = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            count += bucket.load(Ordering::Relaxed);
+            match self.bounds.get(index) {
+                Some(bound) => writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {count}")?,
+                None => writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {count}")?,
+            }
+        }
+
+        // Write the sum and the count
+        let sum = self.sum_us.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        writeln!(out, "{name}_sum {sum}")?;
+        writeln!(out, "{name}_count {count}")
+    }
+}
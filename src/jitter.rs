@@ -0,0 +1,65 @@
+//! Implements a jitter buffer for the UDP->serial direction
+
+use std::{
+    collections::VecDeque,
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Holds datagrams for a fixed delay to smooth out bursty arrival
+#[derive(Debug)]
+pub struct JitterBuffer {
+    /// The buffered datagrams with their arrival time
+    queue: Mutex<VecDeque<(Instant, Vec<u8>)>>,
+    /// Signals a new datagram
+    available: Condvar,
+    /// The delay before a datagram is released
+    delay: Duration,
+    /// The time to transmit a single byte at the configured baudrate
+    byte_time: Duration,
+}
+impl JitterBuffer {
+    /// The amount of bits per transmitted byte (start bit, eight data bits, stop bit)
+    const BITS_PER_BYTE: f64 = 10.0;
+
+    /// Creates a new jitter buffer
+    pub fn new(delay: Duration, baudrate: u64) -> Self {
+        let byte_time = match baudrate {
+            0 => Duration::ZERO,
+            baudrate => Duration::from_secs_f64(Self::BITS_PER_BYTE / baudrate as f64),
+        };
+        Self { queue: Mutex::default(), available: Condvar::new(), delay, byte_time }
+    }
+
+    /// Buffers a datagram
+    pub fn push(&self, datagram: &[u8]) {
+        let mut queue = self.queue.lock().expect("Jitter buffer is poisoned");
+        queue.push_back((Instant::now(), datagram.to_vec()));
+        self.available.notify_one();
+    }
+
+    /// Waits until the oldest datagram is due and returns it together with its arrival time
+    pub fn pop(&self) -> (Instant, Vec<u8>) {
+        let mut queue = self.queue.lock().expect("Jitter buffer is poisoned");
+        loop {
+            // Wait for a datagram
+            let Some((arrival, _)) = queue.front() else {
+                queue = self.available.wait(queue).expect("Jitter buffer is poisoned");
+                continue;
+            };
+
+            // Wait until the datagram is due
+            let release = *arrival + self.delay;
+            let now = Instant::now();
+            if now >= release {
+                return queue.pop_front().expect("Jitter buffer is empty");
+            }
+            (queue, _) = self.available.wait_timeout(queue, release - now).expect("Jitter buffer is poisoned");
+        }
+    }
+
+    /// The time it takes to transmit the given amount of bytes at the configured baudrate
+    pub fn transmit_time(&self, len: usize) -> Duration {
+        self.byte_time.saturating_mul(len as u32)
+    }
+}
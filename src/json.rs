@@ -0,0 +1,389 @@
+//! Implements a minimal JSON renderer and parser for TOML values
+
+use crate::error::Error;
+use std::{fmt::Write, iter::Peekable, str::CharIndices};
+use toml::{value::Table, Value};
+
+/// The key fragments that mark a value as secret
+const SECRET_KEYS: &[&str] = &["password", "secret", "token", "key"];
+
+/// Replaces the values of all secret keys with a placeholder
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                // Redact secret keys and descend into the other values
+                let key = key.to_lowercase();
+                match SECRET_KEYS.iter().any(|secret| key.contains(secret)) {
+                    true => *value = Value::String("<redacted>".to_string()),
+                    false => redact(value),
+                }
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(redact),
+        _ => (),
+    }
+}
+
+/// Renders a value as JSON
+pub fn to_json(value: &Value) -> String {
+    let mut json = String::new();
+    write_value(&mut json, value);
+    json
+}
+
+/// Writes a value as JSON
+fn write_value(json: &mut String, value: &Value) {
+    match value {
+        Value::String(string) => write_string(json, string),
+        Value::Integer(integer) => _ = write!(json, "{integer}"),
+        Value::Float(float) if float.is_finite() => _ = write!(json, "{float}"),
+        Value::Float(_) => json.push_str("null"),
+        Value::Boolean(boolean) => _ = write!(json, "{boolean}"),
+        Value::Datetime(datetime) => write_string(json, &datetime.to_string()),
+        Value::Array(array) => {
+            json.push('[');
+            for (index, value) in array.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write_value(json, value);
+            }
+            json.push(']');
+        }
+        Value::Table(table) => {
+            json.push('{');
+            for (index, (key, value)) in table.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write_string(json, key);
+                json.push(':');
+                write_value(json, value);
+            }
+            json.push('}');
+        }
+    }
+}
+/// Writes a string as escaped JSON string
+fn write_string(json: &mut String, string: &str) {
+    json.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char.is_control() => _ = write!(json, "\\u{:04x}", char as u32),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+}
+
+/// Parses a JSON document into a TOML value
+///
+/// # Note
+/// Since TOML has no null, `null` object members are omitted so that they take their default, and `null` array elements
+/// are rejected.
+pub fn from_json(json: &str) -> Result<Value, Error> {
+    let mut parser = Parser { json, chars: json.char_indices().peekable() };
+    let value = parser.value()?.ok_or_else(|| parser.error("expected a value instead of null"))?;
+    parser.skip_whitespace();
+    match parser.chars.peek() {
+        Some(_) => Err(parser.error("unexpected trailing characters")),
+        None => Ok(value),
+    }
+}
+
+/// A recursive descent JSON parser
+struct Parser<'a> {
+    /// The JSON document
+    json: &'a str,
+    /// The remaining characters and their byte offsets
+    chars: Peekable<CharIndices<'a>>,
+}
+impl Parser<'_> {
+    /// Parses a value, or returns `None` for null
+    fn value(&mut self) -> Result<Option<Value>, Error> {
+        self.skip_whitespace();
+        match self.chars.peek().map(|(_, char)| *char) {
+            Some('{') => self.object().map(Some),
+            Some('[') => self.array().map(Some),
+            Some('"') => self.string().map(|string| Some(Value::String(string))),
+            Some('-' | '0'..='9') => self.number().map(Some),
+            Some('t') => self.literal("true", Some(Value::Boolean(true))),
+            Some('f') => self.literal("false", Some(Value::Boolean(false))),
+            Some('n') => self.literal("null", None),
+            Some(char) => Err(self.error(&format!("unexpected character `{char}`"))),
+            None => Err(self.error("unexpected end of document")),
+        }
+    }
+    /// Parses an object
+    fn object(&mut self) -> Result<Value, Error> {
+        let mut table = Table::new();
+        self.expect('{')?;
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, char)| *char == '}').is_some() {
+            return Ok(Value::Table(table));
+        }
+        loop {
+            // Parse the member
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            if table.contains_key(&key) {
+                return Err(self.error(&format!("duplicate key `{key}`")));
+            }
+            if let Some(value) = value {
+                table.insert(key, value);
+            }
+
+            // Continue with the next member or finish the object
+            self.skip_whitespace();
+            match self.chars.next().map(|(_, char)| char) {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+    /// Parses an array
+    fn array(&mut self) -> Result<Value, Error> {
+        let mut array = Vec::new();
+        self.expect('[')?;
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, char)| *char == ']').is_some() {
+            return Ok(Value::Array(array));
+        }
+        loop {
+            // Parse the element
+            let value = self.value()?.ok_or_else(|| self.error("null array elements are not supported"))?;
+            array.push(value);
+
+            // Continue with the next element or finish the array
+            self.skip_whitespace();
+            match self.chars.next().map(|(_, char)| char) {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(array)),
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+    /// Parses a string
+    fn string(&mut self) -> Result<String, Error> {
+        let mut string = String::new();
+        self.expect('"')?;
+        loop {
+            match self.chars.next().map(|(_, char)| char) {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(self.escape()?),
+                Some(char) if char.is_control() => return Err(self.error("unescaped control character in string")),
+                Some(char) => string.push(char),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+    /// Parses the escape sequence after a backslash
+    fn escape(&mut self) -> Result<char, Error> {
+        let escaped = match self.chars.next().map(|(_, char)| char) {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                // Combine surrogate pairs
+                let high = self.hex4()?;
+                let code = match high {
+                    0xD800..=0xDBFF => {
+                        self.expect('\\')?;
+                        self.expect('u')?;
+                        let low = self.hex4()?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(self.error("invalid surrogate pair"));
+                        }
+                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    code => code,
+                };
+                char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?
+            }
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+        Ok(escaped)
+    }
+    /// Parses the four hex digits of a unicode escape
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.chars.next().and_then(|(_, char)| char.to_digit(16));
+            code = code << 4 | digit.ok_or_else(|| self.error("invalid unicode escape"))?;
+        }
+        Ok(code)
+    }
+    /// Parses a number as integer if it has no fraction or exponent, or as float otherwise
+    fn number(&mut self) -> Result<Value, Error> {
+        let start = self.chars.peek().map(|(offset, _)| *offset).unwrap_or(self.json.len());
+        while self.chars.next_if(|(_, char)| matches!(char, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')).is_some() {
+            // Consume the number
+        }
+        let end = self.chars.peek().map(|(offset, _)| *offset).unwrap_or(self.json.len());
+        let number = &self.json[start..end];
+        if !Self::is_number(number) {
+            return Err(self.error(&format!("invalid number `{number}`")));
+        }
+        let value = match number.contains(['.', 'e', 'E']) {
+            true => number.parse().map(Value::Float).ok(),
+            false => number.parse().map(Value::Integer).ok(),
+        };
+        value.ok_or_else(|| self.error(&format!("invalid number `{number}`")))
+    }
+    /// Whether the text matches the JSON number grammar, which is stricter than Rust's number parsing
+    fn is_number(number: &str) -> bool {
+        /// Strips the leading ASCII digits and returns the remainder, or `None` if there are none
+        fn digits(text: &str) -> Option<&str> {
+            let rest = text.trim_start_matches(|char: char| char.is_ascii_digit());
+            (rest.len() < text.len()).then_some(rest)
+        }
+
+        // Parse the integer part without leading zeros
+        let unsigned = number.strip_prefix('-').unwrap_or(number);
+        let Some(mut rest) = digits(unsigned) else {
+            return false;
+        };
+        if unsigned.starts_with('0') && unsigned.len() - rest.len() > 1 {
+            return false;
+        }
+
+        // Parse the fraction and the exponent
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let Some(rest_) = digits(fraction) else {
+                return false;
+            };
+            rest = rest_;
+        }
+        if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+            let Some(rest_) = digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)) else {
+                return false;
+            };
+            rest = rest_;
+        }
+        rest.is_empty()
+    }
+    /// Parses a literal like `true`
+    fn literal(&mut self, literal: &str, value: Option<Value>) -> Result<Option<Value>, Error> {
+        for expected in literal.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    /// Consumes the expected character
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        match self.chars.next_if(|(_, char)| *char == expected) {
+            Some(_) => Ok(()),
+            None => Err(self.error(&format!("expected `{expected}`"))),
+        }
+    }
+    /// Skips whitespace
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, char)| matches!(char, ' ' | '\t' | '\n' | '\r')).is_some() {
+            // Consume the whitespace
+        }
+    }
+    /// Creates an error at the current position
+    fn error(&mut self, message: &str) -> Error {
+        let offset = self.chars.peek().map(|(offset, _)| *offset).unwrap_or(self.json.len());
+        let line = self.json[..offset].matches('\n').count() + 1;
+        let column = self.json[..offset].rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+        eio!("Invalid JSON at line {line}, column {column} ({message})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the JSON document and asserts that it equals the TOML document
+    fn assert_parses(json: &str, toml: &str) {
+        let expected: Value = toml::from_str(toml).expect("Invalid TOML in test");
+        assert_eq!(from_json(json).expect("Failed to parse JSON"), expected);
+    }
+
+    /// Asserts that the JSON document is rejected
+    fn assert_rejects(json: &str) {
+        assert!(from_json(json).is_err(), "Invalid JSON has been accepted: {json}");
+    }
+
+    #[test]
+    fn nested_values() {
+        let json = r#"{ "serial": { "path": "/dev/ttyUSB0", "baudrate": 115200 }, "ports": [1, -2, 3.5e1],
+            "enabled": true, "unset": null, "routes": [{ "name": "a" }, {}] }"#;
+        let toml = "serial = { path = '/dev/ttyUSB0', baudrate = 115200 }\nports = [1, -2, 35.0]\nenabled = true\n\
+            routes = [{ name = 'a' }, {}]";
+        assert_parses(json, toml);
+    }
+
+    #[test]
+    fn string_escapes() {
+        let json = r#"{ "a": "quote\" backslash\\ slash\/ tab\t newline\n e\u00e9 clef\ud834\udd1e" }"#;
+        assert_parses(json, "a = \"quote\\\" backslash\\\\ slash/ tab\\t newline\\n e\u{e9} clef\u{1d11e}\"");
+    }
+
+    #[test]
+    fn roundtrip() {
+        let value: Value =
+            toml::from_str("a = \"x\\ty\\u0001\"\nb = [1, 2.5, false]\nc = { d = {} }").expect("Invalid TOML");
+        assert_eq!(from_json(&to_json(&value)).expect("Failed to parse rendered JSON"), value);
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        for json in [
+            "",
+            "{",
+            "{ \"a\": 1, }",
+            "[1, 2,]",
+            "{ \"a\": 1 } x",
+            "{ 'a': 1 }",
+            "{ \"a\": tru }",
+            "{ \"a\": [null] }",
+            "{ \"a\": 1, \"a\": 2 }",
+            "\"unterminated",
+            "\"\\x\"",
+            "null",
+        ] {
+            assert_rejects(json);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        for number in ["01", "-", "1.", "-.5", ".5", "1e", "1e+", "+1", "0x10", "1.5.2"] {
+            assert_rejects(&format!("{{ \"a\": {number} }}"));
+        }
+        assert_parses(r#"{ "a": 0, "b": -0.5, "c": 1E2, "d": 2e-1 }"#, "a = 0\nb = -0.5\nc = 100.0\nd = 0.2");
+    }
+
+    #[test]
+    fn rejects_invalid_surrogates() {
+        assert_rejects(r#"{ "a": "\ud834" }"#);
+        assert_rejects(r#"{ "a": "\udd1e" }"#);
+        assert_rejects(r#"{ "a": "\ud834\u0041" }"#);
+    }
+
+    #[test]
+    fn redacts_secrets() {
+        let mut value: Value =
+            toml::from_str("password = 'x'\n[nested]\napi_token = 'y'\nname = 'z'").expect("Invalid TOML");
+        redact(&mut value);
+        assert_eq!(to_json(&value), r#"{"nested":{"api_token":"<redacted>","name":"z"},"password":"<redacted>"}"#);
+    }
+}
@@ -1,36 +1,569 @@
 //! The logging facility
 
-use std::{io, io::Write};
+use crate::{
+    clock,
+    config::{self, LogFormat, LogSink, Timestamp, TimestampClock},
+    error::Error,
+    gzip,
+    server::Direction,
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// The amount of bytes per hex dump line
+const HEXDUMP_WIDTH: usize = 16;
+
+/// A log file that is rotated once it exceeds its maximum size
+///
+/// # Rotation
+/// Before a write would exceed the maximum size, the rotated files are shifted from `<path>.1` to `<path>.2` and so on,
+/// dropping the oldest one, the log file is renamed to `<path>.1` and a new log file is started. Rotation happens
+/// between writes, so a line may be split across two files.
+///
+/// # Compression
+/// If compression is enabled, the rotated files are named `<path>.N.gz`; `<path>.1` is compressed to `<path>.1.gz` in
+/// the background so that the logging is not stalled, and the next rotation waits for the compression to complete.
+#[derive(Debug)]
+struct LogFile {
+    /// The path to the log file
+    path: String,
+    /// The log file, or `None` if file logging has been disabled after an error
+    file: Option<File>,
+    /// The current size of the log file
+    size: u64,
+    /// The size at which the log file is rotated
+    max_size: u64,
+    /// The amount of rotated log files to keep
+    max_files: usize,
+    /// Whether to compress the rotated log files
+    compress: bool,
+    /// The compression of the most recently rotated log file if it is still running
+    compressing: Option<JoinHandle<()>>,
+}
+impl LogFile {
+    /// Opens the log file for appending
+    fn open(config: &config::Log, path: &str) -> Result<Self, Error> {
+        let file = Self::create(path)?;
+        let size = file.metadata()?.len();
+        let (max_size, max_files, compress) = (config.max_size_bytes, config.max_files, config.compress);
+        Ok(Self { path: path.to_string(), file: Some(file), size, max_size, max_files, compress, compressing: None })
+    }
+    /// Opens or creates a log file for appending
+    fn create(path: &str) -> Result<File, Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path);
+        file.map_err(|e| eio!("Failed to open the log file {path} ({e})"))
+    }
+
+    /// Writes a message to the log file
+    ///
+    /// # Note
+    /// File logging is disabled with a warning if the log file cannot be written so that the forwarding is unaffected.
+    fn write(&mut self, message: &[u8]) {
+        if self.file.is_none() {
+            return;
+        }
+        if let Err(e) = self.try_write(message) {
+            warn!("failed to write the log file ({}); disabling the log", e.to_string().trim_end());
+            self.file = None;
+        }
+    }
+    /// Writes a message to the log file and rotates the log file if necessary
+    fn try_write(&mut self, message: &[u8]) -> Result<(), Error> {
+        // Rotate the log file if the message does not fit
+        if self.size > 0 && self.size + message.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        // Write the message
+        let file = self.file.as_mut().expect("Log file has vanished");
+        file.write_all(message)?;
+        self.size += message.len() as u64;
+        Ok(())
+    }
+    /// Shifts the rotated log files and starts a new log file
+    fn rotate(&mut self) -> Result<(), Error> {
+        // Note: the old file must be closed before it is renamed on all platforms
+        self.file = None;
+        self.wait_compressed();
+        let extension = if self.compress { ".gz" } else { "" };
+        for index in (1..self.max_files).rev() {
+            let from = format!("{}.{index}{extension}", self.path);
+            let to = format!("{}.{}{extension}", self.path, index + 1);
+            match fs::rename(from, to) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => (),
+            }
+        }
+        let rotated = format!("{}.1", self.path);
+        fs::rename(&self.path, &rotated)?;
+
+        // Compress the rotated file in the background if appropriate
+        if self.compress {
+            self.compressing = Some(thread::spawn(move || Self::compress(&rotated)));
+        }
+
+        // Start a new log file
+        self.file = Some(Self::create(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+    /// Waits until the compression of the most recently rotated log file has completed
+    fn wait_compressed(&mut self) {
+        if let Some(compressing) = self.compressing.take() {
+            compressing.join().expect("Log compression thread has panicked");
+        }
+    }
+    /// Compresses a rotated log file to `<rotated>.gz` and removes the uncompressed file
+    ///
+    /// # Note
+    /// If the compression fails, the uncompressed file is kept with a warning.
+    fn compress(rotated: &str) {
+        let compressed = format!("{rotated}.gz");
+        let result = fs::read(rotated).and_then(|data| fs::write(&compressed, gzip::encode(&data)));
+        match result.and_then(|_| fs::remove_file(rotated)) {
+            Ok(_) => (),
+            Err(e) => warn!("failed to compress the rotated log file {rotated} ({e}); keeping it uncompressed"),
+        }
+    }
+}
+impl Drop for LogFile {
+    fn drop(&mut self) {
+        // Note: wait for the compression so that a reload does not race with a pending compression
+        self.wait_compressed();
+    }
+}
 
 /// Logs messages
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct Logger {
-    _private: (),
+    /// The stream to write the log to
+    sink: LogSink,
+    /// The format of the logged data
+    format: LogFormat,
+    /// The file to write the log to instead of the stream
+    file: Option<Mutex<LogFile>>,
+    /// The buffer to assemble a message
+    buf: Mutex<Vec<u8>>,
+    /// The prefix for each log line
+    prefix: Option<String>,
+    /// Whether to tag each data line with its direction
+    include_direction: bool,
+    /// The direction of the current data line
+    line_direction: Mutex<Option<Direction>>,
+    /// The timestamp for each log line
+    timestamp: Option<Timestamp>,
+    /// The server start as reference for monotonic timestamps
+    started: Instant,
+    /// The clock for the absolute timestamps
+    clock: TimestampClock,
+    /// Whether the next byte starts a new line
+    at_line_start: AtomicBool,
 }
 impl Logger {
-    /// Creates a new logger
-    pub const fn new() -> Self {
-        Self { _private: () }
+    /// Creates a new logger with `started` as reference for monotonic timestamps and `clock` for absolute timestamps
+    pub fn new(config: &config::Log, device: &str, started: Instant, clock: TimestampClock) -> Result<Self, Error> {
+        let file = config.path.as_deref().map(|path| LogFile::open(config, path)).transpose()?.map(Mutex::new);
+        let prefix = config.include_device.then(|| format!("[{device}] "));
+        let at_line_start = AtomicBool::new(true);
+        let (buf, line_direction) = (Mutex::default(), Mutex::default());
+        Ok(Self {
+            sink: config.sink,
+            format: config.format,
+            file,
+            buf,
+            prefix,
+            include_direction: config.include_direction,
+            line_direction,
+            timestamp: config.timestamp,
+            started,
+            clock,
+            at_line_start,
+        })
     }
 
-    /// Logs some data
-    pub fn log<T>(&self, data: T)
+    /// Lets `f` assemble a message and writes it to the configured file or stream
+    ///
+    /// # Note
+    /// The message is written with a single write while the buffer lock is held, so messages from concurrent runloops
+    /// never interleave and the line state stays consistent.
+    fn with_sink<F>(&self, f: F)
+    where
+        F: FnOnce(&mut Vec<u8>),
+    {
+        let mut buf = self.buf.lock().expect("Log buffer is poisoned");
+        buf.clear();
+        f(&mut buf);
+        match (self.file.as_ref(), self.sink) {
+            (Some(file), _) => file.lock().expect("Log file is poisoned").write(&buf),
+            (None, LogSink::Stdout) => _ = io::stdout().lock().write_all(&buf),
+            (None, LogSink::Stderr) => _ = io::stderr().lock().write_all(&buf),
+        }
+    }
+
+    /// Writes the prefix and timestamp for a new line
+    fn line_start<W>(&self, out: &mut W)
+    where
+        W: Write + ?Sized,
+    {
+        if let Some(prefix) = self.prefix.as_ref() {
+            _ = write!(out, "{prefix}");
+        }
+
+        // Write the timestamp
+        let monotonic_us = self.started.elapsed().as_micros();
+        match self.timestamp {
+            None => (),
+            Some(Timestamp::Wallclock) => _ = write!(out, "[{}] ", self.absolute()),
+            Some(Timestamp::MonotonicUs) => _ = write!(out, "[+{monotonic_us}us] "),
+            Some(Timestamp::Both) => _ = write!(out, "[{} +{monotonic_us}us] ", self.absolute()),
+        }
+    }
+    /// Formats the current time of the configured clock, as RFC 3339 timestamp for the realtime clock or as seconds
+    /// since the clock's epoch otherwise
+    fn absolute(&self) -> String {
+        let now = clock::now(self.clock).unwrap_or_default();
+        match self.clock {
+            TimestampClock::Realtime => rfc3339(now),
+            _ => format!("{}.{:06}s", now.as_secs(), now.subsec_micros()),
+        }
+    }
+
+    /// Logs some data of the given direction in the configured format
+    pub fn log<T>(&self, direction: Direction, data: T)
     where
         T: AsRef<[u8]>,
     {
-        // Write the bytes to stdout
-        let mut stdout = io::stdout();
-        for &byte in data.as_ref() {
-            // Check if the char can be printed
-            let mut is_valid = byte.is_ascii_alphanumeric();
-            is_valid |= byte.is_ascii_punctuation();
-            is_valid |= byte.is_ascii_whitespace();
-
-            // Print the char
-            match is_valid {
-                true => _ = write!(&mut stdout, "{}", byte as char),
-                false => _ = write!(&mut stdout, "\\x{byte:02x}"),
+        match self.format {
+            LogFormat::Ascii => self.log_ascii(direction, data.as_ref()),
+            LogFormat::Hexdump => self.log_hexdump(direction, data.as_ref()),
+        }
+    }
+    /// Logs some data as text with the non-printable bytes escaped as `\xNN`
+    fn log_ascii(&self, direction: Direction, data: &[u8]) {
+        // Write the bytes to the sink
+        self.with_sink(|out| {
+            let mut line_direction = self.line_direction.lock().expect("Log line direction is poisoned");
+            for &byte in data {
+                // Start a new line if the direction changes mid-line so that each line has a single direction
+                let at_line_start = self.at_line_start.load(Ordering::Relaxed);
+                let switched = self.include_direction && *line_direction != Some(direction);
+                if switched && !at_line_start {
+                    _ = writeln!(out);
+                }
+
+                // Write the prefix and direction tag at the start of each line
+                if at_line_start || switched {
+                    self.line_start(out);
+                    if self.include_direction {
+                        _ = write!(out, "{} ", direction_tag(direction));
+                    }
+                    *line_direction = Some(direction);
+                }
+                self.at_line_start.store(byte == b'\n', Ordering::Relaxed);
+
+                // Print the char
+                match is_printable(byte) || byte.is_ascii_whitespace() {
+                    true => _ = write!(out, "{}", byte as char),
+                    false => _ = write!(out, "\\x{byte:02x}"),
+                };
+            }
+        });
+    }
+    /// Logs some data as `xxd`-style hex dump with an offset column relative to the start of the chunk, 16 bytes per
+    /// line in groups of two and an ASCII gutter
+    fn log_hexdump(&self, direction: Direction, data: &[u8]) {
+        self.with_sink(|out| {
+            // Start a new line if necessary
+            if !self.at_line_start.swap(true, Ordering::Relaxed) {
+                _ = writeln!(out);
+            }
+            *self.line_direction.lock().expect("Log line direction is poisoned") = Some(direction);
+
+            // Write the lines
+            for (index, line) in data.chunks(HEXDUMP_WIDTH).enumerate() {
+                self.line_start(out);
+                if self.include_direction {
+                    _ = write!(out, "{} ", direction_tag(direction));
+                }
+                _ = write!(out, "{:08x}:", index * HEXDUMP_WIDTH);
+
+                // Write the hex bytes and pad a partial line so that the ASCII gutter stays aligned
+                for column in 0..HEXDUMP_WIDTH {
+                    if column % 2 == 0 {
+                        _ = write!(out, " ");
+                    }
+                    match line.get(column) {
+                        Some(byte) => _ = write!(out, "{byte:02x}"),
+                        None => _ = write!(out, "  "),
+                    }
+                }
+
+                // Write the ASCII gutter
+                _ = write!(out, "  ");
+                for &byte in line {
+                    match is_printable(byte) {
+                        true => _ = write!(out, "{}", byte as char),
+                        false => _ = write!(out, "."),
+                    }
+                }
+                _ = writeln!(out);
+            }
+        });
+    }
+    /// Logs a note on a separate line
+    pub fn note(&self, note: &str) {
+        self.with_sink(|out| {
+            if !self.at_line_start.swap(true, Ordering::Relaxed) {
+                _ = writeln!(out);
+            }
+            self.line_start(out);
+            _ = writeln!(out, "[{note}]");
+        });
+    }
+}
+
+/// The log tag of a direction, `RX` for serial->UDP and `TX` for UDP->serial
+const fn direction_tag(direction: Direction) -> &'static str {
+    match direction {
+        Direction::SerialToUdp => "RX",
+        Direction::UdpToSerial => "TX",
+    }
+}
+
+/// Whether the byte is a printable ASCII character other than whitespace
+const fn is_printable(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte.is_ascii_punctuation() || byte == b' '
+}
+
+/// Escapes the data as single line where all non-printable bytes including line breaks are written as `\xNN`
+pub fn escape_line(data: &[u8]) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for &byte in data {
+        match is_printable(byte) {
+            true => escaped.push(byte as char),
+            false => escaped.push_str(&format!("\\x{byte:02x}")),
+        }
+    }
+    escaped
+}
+
+/// Formats a time since the Unix epoch as RFC 3339 timestamp in UTC with microsecond precision
+fn rfc3339(elapsed: Duration) -> String {
+    let (days, seconds) = (elapsed.as_secs() / 86400, elapsed.as_secs() % 86400);
+
+    // Convert the days since the epoch to a civil date (see http://howardhinnant.github.io/date_algorithms.html)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    // Format the timestamp
+    let (hour, minute, second) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let micros = elapsed.subsec_micros();
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{micros:06}Z")
+}
+
+/// The deduplication state of a direction
+#[derive(Debug, Default)]
+struct DedupState {
+    /// The most recent chunk
+    last: Vec<u8>,
+    /// The amount of suppressed repetitions of the most recent chunk
+    repeats: u64,
+    /// The time of the first suppressed repetition
+    since: Option<Instant>,
+}
+impl DedupState {
+    /// Logs the amount of suppressed repetitions if any and resets the counter
+    fn report(&mut self, logger: &Logger) {
+        if self.repeats > 0 {
+            let note = format!("last chunk repeated {} times", self.repeats);
+            logger.note(&note);
+        }
+        self.repeats = 0;
+        self.since = None;
+    }
+}
+
+/// Collapses consecutive identical chunks into one chunk and a repetition count
+#[derive(Debug)]
+pub struct Dedup {
+    /// The maximum time to suppress repetitions before the count is reported
+    timeout: Duration,
+    /// The serial->UDP and UDP->serial state
+    state: Mutex<[DedupState; 2]>,
+}
+impl Dedup {
+    /// Creates a new deduplicator
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, state: Mutex::default() }
+    }
+
+    /// The maximum time to suppress repetitions before the count is reported
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Logs the chunk unless it is identical to the previous chunk of the same direction
+    pub fn log(&self, logger: &Logger, direction: Direction, data: &[u8]) {
+        let mut state = self.state.lock().expect("Dedup state is poisoned");
+        let state = &mut state[direction as usize];
+
+        // Suppress repetitions
+        if state.last == data {
+            state.repeats += 1;
+            state.since.get_or_insert_with(Instant::now);
+            return;
+        }
+
+        // Report the suppressed repetitions and log the new chunk
+        state.report(logger);
+        state.last = data.to_vec();
+        logger.log(direction, data);
+    }
+    /// Reports the suppressed repetitions that are older than the timeout
+    pub fn expire(&self, logger: &Logger) {
+        let mut state = self.state.lock().expect("Dedup state is poisoned");
+        for state in state.iter_mut() {
+            if state.since.is_some_and(|since| since.elapsed() >= self.timeout) {
+                state.report(logger);
+            }
+        }
+    }
+    /// Reports all suppressed repetitions regardless of their age
+    pub fn report_all(&self, logger: &Logger) {
+        let mut state = self.state.lock().expect("Dedup state is poisoned");
+        for state in state.iter_mut() {
+            state.report(logger);
+        }
+    }
+}
+
+/// A logger together with its optional deduplicator
+#[derive(Debug)]
+pub struct Logging {
+    /// The logger
+    pub logger: Logger,
+    /// The log deduplicator
+    pub dedup: Option<Dedup>,
+}
+impl Logging {
+    /// Creates the logging facility for the given config, or returns `None` if logging is disabled
+    pub fn new(
+        config: &config::Log,
+        device: &str,
+        started: Instant,
+        clock: TimestampClock,
+    ) -> Result<Option<Self>, Error> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let logger = Logger::new(config, device, started, clock)?;
+        let dedup = config.dedup_consecutive.then(|| Dedup::new(Duration::from_millis(config.dedup_timeout_ms)));
+        Ok(Some(Self { logger, dedup }))
+    }
+
+    /// Logs the chunk, deduplicating it if appropriate
+    pub fn log(&self, direction: Direction, data: &[u8]) {
+        match self.dedup.as_ref() {
+            Some(dedup) => dedup.log(&self.logger, direction, data),
+            None => self.logger.log(direction, data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, path::PathBuf, process};
+
+    /// Creates an empty temporary directory for the test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("serial-server-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("Failed to create the temporary directory");
+        dir
+    }
+
+    #[test]
+    fn rotated_files_are_compressed() {
+        let dir = temp_dir("compress");
+        let path = dir.join("io.log").to_str().expect("Invalid temporary path").to_string();
+        let config = config::Log { max_size_bytes: 1024, max_files: 2, compress: true, ..Default::default() };
+
+        // Write enough lines for three rotations
+        let mut file = LogFile::open(&config, &path).expect("Failed to open the log file");
+        for line in 0..100 {
+            file.write(format!("line {line:>4} of the log file\n").as_bytes());
+        }
+        drop(file);
+
+        // Check that the rotated files have been compressed and the oldest one has been dropped
+        let mut names: Vec<_> = (fs::read_dir(&dir).expect("Failed to list the temporary directory"))
+            .map(|entry| entry.expect("Failed to read a directory entry").file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["io.log", "io.log.1.gz", "io.log.2.gz"]);
+
+        // Check that the newest rotated file holds the lines right before the active log file
+        let active = fs::read_to_string(&path).expect("Failed to read the log file");
+        assert!(active.ends_with("line   99 of the log file\n"));
+        let rotated = gzip::tests::decode(&fs::read(format!("{path}.1.gz")).expect("Failed to read the rotated file"));
+        let rotated = String::from_utf8(rotated).expect("Invalid rotated file");
+        let first_active = active.lines().next().expect("Empty log file");
+        let line_number = |line: &str| line[5..9].trim().parse::<u32>().expect("Invalid line");
+        assert_eq!(line_number(rotated.lines().last().expect("Empty rotated file")) + 1, line_number(first_active));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn concurrent_messages_are_not_split() {
+        const MESSAGES: usize = 2000;
+        let dir = temp_dir("concurrent");
+        let path = dir.join("io.log").to_str().expect("Invalid temporary path").to_string();
+        let config = config::Log { path: Some(path.clone()), include_direction: true, ..Default::default() };
+
+        // Log complete lines from two threads with different directions at the same time
+        let logger = Logger::new(&config, "test", Instant::now(), TimestampClock::Realtime)
+            .expect("Failed to create the logger");
+        thread::scope(|scope| {
+            for (direction, name) in [(Direction::SerialToUdp, "rx"), (Direction::UdpToSerial, "tx")] {
+                let logger = &logger;
+                scope.spawn(move || {
+                    for index in 0..MESSAGES {
+                        logger.log(direction, format!("{name} message {index:>4} with some padding\n"));
+                    }
+                });
+            }
+        });
+        drop(logger);
+
+        // Check that every line is a complete message with the matching direction tag, in order per direction
+        let log = fs::read_to_string(&path).expect("Failed to read the log file");
+        let (mut rx, mut tx) = (0, 0);
+        for line in log.lines() {
+            let (expected, next) = match line.get(..3) {
+                Some("RX ") => (format!("RX rx message {rx:>4} with some padding"), &mut rx),
+                Some("TX ") => (format!("TX tx message {tx:>4} with some padding"), &mut tx),
+                _ => panic!("Invalid log line: {line:?}"),
             };
+            assert_eq!(line, expected);
+            *next += 1;
         }
+        assert_eq!((rx, tx), (MESSAGES, MESSAGES));
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -2,26 +2,91 @@
 
 #[macro_use]
 pub mod error;
+#[macro_use]
+pub mod diag;
+pub mod affinity;
+pub mod allowlist;
+pub mod autobaud;
+pub mod batch;
+pub mod capture;
+pub mod clock;
+pub mod coalesce;
 pub mod config;
+pub mod control;
+pub mod destination;
+pub mod endpoint;
+pub mod framing;
+pub mod gzip;
+pub mod header;
+pub mod histogram;
+pub mod jitter;
+pub mod json;
 pub mod logger;
+pub mod multicast;
+pub mod pcap;
+pub mod pending;
+pub mod queue;
+pub mod rate;
+pub mod reconnect;
+pub mod routing;
+#[cfg(feature = "sandbox")]
+pub mod sandbox;
+pub mod sched;
 pub mod serial;
 pub mod server;
+pub mod signal;
+pub mod stats;
+pub mod translate;
+pub mod transport;
+pub mod trigger;
+pub mod verify;
+pub mod yaml;
 
 use crate::{config::Config, error::Error, server::Server};
-use std::process;
+use std::{env, process};
 
 pub fn main() {
     /// The real main function
     fn _main() -> Result<(), Error> {
+        // Apply the log level from the environment before anything is logged
+        diag::init();
+
+        // List the serial ports if requested
+        if env::args().nth(1).as_deref() == Some("--list-ports") {
+            let ports = serial::list_ports()?;
+            if ports.is_empty() {
+                warn!("no serial ports found");
+            }
+            for port in ports {
+                println!("{port}");
+            }
+            return Ok(());
+        }
+
+        // Only load and validate the config if requested
+        // Note: neither the serial device nor the sockets are opened so that configs can be checked anywhere
+        if env::args().nth(1).as_deref() == Some("--check") {
+            Config::load_from(env::args().nth(2))?;
+            println!("config OK");
+            return Ok(());
+        }
+
         // Parse the args and start the server
-        let config = Config::load()?;
-        let server = Server::new(config)?;
-        server.runloop()
+        let configs = Config::load()?;
+        if let Some(config) = configs.first() {
+            // Note: the `[log]` section is the same for all bridges
+            diag::set_level(config.log.level);
+        }
+        let servers = (configs.into_iter().enumerate())
+            .map(|(bridge, config)| Server::new(config, bridge))
+            .collect::<Result<Vec<_>, _>>()?;
+        Server::runloop(servers)
     }
 
     // Call the real main function
+    // Note: the exit code reflects the origin of the error so that supervisors can react
     if let Err(e) = _main() {
-        eprintln!("{e}");
-        process::exit(1);
+        error!("{}", e.to_string().trim_end());
+        process::exit(e.category().exit_code());
     }
 }
@@ -0,0 +1,47 @@
+//! Provides the multicast and hop limit socket options that are not available in `std`
+
+#[cfg(unix)]
+use std::os::fd::AsRawFd;
+#[cfg(windows)]
+use std::os::windows::io::AsRawSocket;
+use std::{
+    io,
+    net::{Ipv4Addr, UdpSocket},
+};
+
+extern "C" {
+    // int32_t multicast_set_interface_v4(int64_t fd, uint32_t address)
+    fn multicast_set_interface_v4(fd: i64, address: u32) -> i32;
+    // int32_t multicast_set_hops_v6(int64_t fd, uint32_t hops, uint8_t multicast)
+    fn multicast_set_hops_v6(fd: i64, hops: u32, multicast: u8) -> i32;
+}
+
+/// Gets the raw handle of a socket
+fn raw_fd(socket: &UdpSocket) -> i64 {
+    #[cfg(unix)]
+    let fd = socket.as_raw_fd() as i64;
+    #[cfg(windows)]
+    let fd = socket.as_raw_socket() as i64;
+    fd
+}
+
+/// Selects the interface with the given address for outgoing IPv4 multicast datagrams
+pub fn set_interface_v4(socket: &UdpSocket, interface: Ipv4Addr) -> io::Result<()> {
+    let result = unsafe { multicast_set_interface_v4(raw_fd(socket), u32::from(interface)) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Sets the hop limit for outgoing IPv6 unicast or multicast datagrams
+///
+/// # Note
+/// This is the IPv6 equivalent of [`UdpSocket::set_ttl`] and [`UdpSocket::set_multicast_ttl_v4`].
+pub fn set_hops_v6(socket: &UdpSocket, hops: u32, multicast: bool) -> io::Result<()> {
+    let result = unsafe { multicast_set_hops_v6(raw_fd(socket), hops, multicast as u8) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
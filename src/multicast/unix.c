@@ -0,0 +1,36 @@
+#include <stdint.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <sys/socket.h>
+
+/**
+ * @brief Selects the interface for outgoing IPv4 multicast datagrams
+ * 
+ * @param fd The socket file descriptor
+ * @param address The IPv4 address of the interface in host byte order
+ * @return `0` or `-1` on error
+ */
+int32_t multicast_set_interface_v4(int64_t fd, uint32_t address) {
+    struct in_addr interface = { .s_addr = htonl(address) };
+    if (setsockopt((int)fd, IPPROTO_IP, IP_MULTICAST_IF, &interface, sizeof(interface)) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Sets the hop limit for outgoing IPv6 datagrams
+ * 
+ * @param fd The socket file descriptor
+ * @param hops The hop limit
+ * @param multicast Whether to set the hop limit for multicast instead of unicast datagrams
+ * @return `0` or `-1` on error
+ */
+int32_t multicast_set_hops_v6(int64_t fd, uint32_t hops, uint8_t multicast) {
+    int value = (int)hops;
+    int option = multicast ? IPV6_MULTICAST_HOPS : IPV6_UNICAST_HOPS;
+    if (setsockopt((int)fd, IPPROTO_IPV6, option, &value, sizeof(value)) != 0) {
+        return -1;
+    }
+    return 0;
+}
@@ -0,0 +1,38 @@
+#include <stdint.h>
+#include <winsock2.h>
+#include <ws2tcpip.h>
+
+/**
+ * @brief Selects the interface for outgoing IPv4 multicast datagrams
+ *
+ * @param fd The socket handle
+ * @param address The IPv4 address of the interface in host byte order
+ * @return `0` or `-1` on error
+ */
+int32_t multicast_set_interface_v4(int64_t fd, uint32_t address) {
+    struct in_addr interface;
+    interface.s_addr = htonl(address);
+    if (setsockopt((SOCKET)fd, IPPROTO_IP, IP_MULTICAST_IF, (const char*)&interface, sizeof(interface)) != 0) {
+        SetLastError((DWORD)WSAGetLastError());
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Sets the hop limit for outgoing IPv6 datagrams
+ *
+ * @param fd The socket handle
+ * @param hops The hop limit
+ * @param multicast Whether to set the hop limit for multicast instead of unicast datagrams
+ * @return `0` or `-1` on error
+ */
+int32_t multicast_set_hops_v6(int64_t fd, uint32_t hops, uint8_t multicast) {
+    DWORD value = (DWORD)hops;
+    int option = multicast ? IPV6_MULTICAST_HOPS : IPV6_UNICAST_HOPS;
+    if (setsockopt((SOCKET)fd, IPPROTO_IPV6, option, (const char*)&value, sizeof(value)) != 0) {
+        SetLastError((DWORD)WSAGetLastError());
+        return -1;
+    }
+    return 0;
+}
@@ -0,0 +1,194 @@
+//! Implements a pcap capture file of the bridged UDP datagrams
+
+use crate::{
+    clock,
+    config::{self, TimestampClock},
+    error::Error,
+};
+use std::{
+    fs::{self, File},
+    io::Write,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// The capture file state
+#[derive(Debug)]
+struct State {
+    /// The capture file, or `None` if capturing has been disabled after an error
+    file: Option<File>,
+    /// The current size of the capture file
+    size: u64,
+    /// The buffer to assemble a record
+    record: Vec<u8>,
+}
+
+/// Writes the bridged UDP datagrams as synthetic IP packets to a pcap file that can be opened with Wireshark
+///
+/// # Rotation
+/// If the capture file would exceed the maximum size, it is renamed to `<path>.1`, replacing the previous rotated file,
+/// and a new capture file is started; so at most twice the maximum size is used.
+#[derive(Debug)]
+pub struct PcapWriter {
+    /// The path to the capture file
+    path: String,
+    /// The maximum size of the capture file
+    max_bytes: u64,
+    /// The clock for the record timestamps
+    clock: TimestampClock,
+    /// The capture file state
+    state: Mutex<State>,
+}
+impl PcapWriter {
+    /// The pcap file header with the microsecond magic, version 2.4, a snapshot length of 65535 and link type raw IP
+    const FILE_HEADER: [u8; 24] = [
+        0xd4, 0xc3, 0xb2, 0xa1, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff,
+        0x00, 0x00, 0x65, 0x00, 0x00, 0x00,
+    ];
+    /// The IP protocol number of UDP
+    const PROTOCOL_UDP: u8 = 17;
+    /// The TTL or hop limit of the synthetic IP packets
+    const TTL: u8 = 64;
+    /// The minimum value for the maximum capture file size
+    pub const MAX_BYTES_MIN: u64 = 64 * 1024;
+
+    /// Creates a new pcap capture file with the given clock for the record timestamps, truncating any existing file
+    pub fn new(path: &str, config: &config::Capture, clock: TimestampClock) -> Result<Self, Error> {
+        let file = Self::create(path)?;
+        let state = State { file: Some(file), size: Self::FILE_HEADER.len() as u64, record: Vec::new() };
+        Ok(Self { path: path.to_string(), max_bytes: config.pcap_max_bytes, clock, state: Mutex::new(state) })
+    }
+    /// Creates a capture file and writes the file header
+    fn create(path: &str) -> Result<File, Error> {
+        let mut file = File::create(path).map_err(|e| eio!("Failed to create the pcap file {path} ({e})"))?;
+        file.write_all(&Self::FILE_HEADER)?;
+        Ok(file)
+    }
+
+    /// Appends a datagram from `source` to `destination` to the capture file
+    ///
+    /// # Note
+    /// Capturing is disabled with a warning if the capture file cannot be written so that the forwarding is unaffected.
+    pub fn record(&self, source: SocketAddr, destination: SocketAddr, datagram: &[u8]) {
+        let mut state = self.state.lock().expect("Pcap state is poisoned");
+        if state.file.is_none() {
+            return;
+        }
+
+        // Assemble the record
+        let mut record = std::mem::take(&mut state.record);
+        let now = clock::now(self.clock).unwrap_or_default();
+        Self::encode(now, source, destination, datagram, &mut record);
+        if let Err(e) = self.write(&mut state, &record) {
+            warn!("failed to write the pcap file ({}); disabling the capture", e.to_string().trim_end());
+            state.file = None;
+        }
+        state.record = record;
+    }
+    /// Writes a record to the capture file and rotates the capture file if necessary
+    fn write(&self, state: &mut State, record: &[u8]) -> Result<(), Error> {
+        // Rotate the capture file if the record does not fit
+        // Note: the old file must be closed before it is renamed on all platforms
+        if state.size + record.len() as u64 > self.max_bytes {
+            state.file = None;
+            fs::rename(&self.path, format!("{}.1", self.path))?;
+            state.file = Some(Self::create(&self.path)?);
+            state.size = Self::FILE_HEADER.len() as u64;
+        }
+
+        // Write the record
+        let file = state.file.as_mut().expect("Pcap file has vanished");
+        file.write_all(record)?;
+        state.size += record.len() as u64;
+        Ok(())
+    }
+
+    /// Encodes a datagram as pcap record with a synthetic IP and UDP header
+    fn encode(now: Duration, source: SocketAddr, destination: SocketAddr, datagram: &[u8], record: &mut Vec<u8>) {
+        // Assemble the UDP header and payload
+        // Note: the synthetic packet is truncated to the maximum IP packet size
+        let datagram = &datagram[..datagram.len().min(u16::MAX as usize - 48)];
+        let udp_len = (8 + datagram.len()) as u16;
+        let mut udp = Vec::with_capacity(udp_len as usize);
+        udp.extend_from_slice(&source.port().to_be_bytes());
+        udp.extend_from_slice(&destination.port().to_be_bytes());
+        udp.extend_from_slice(&udp_len.to_be_bytes());
+        udp.extend_from_slice(&[0, 0]);
+        udp.extend_from_slice(datagram);
+
+        // Assemble the IP packet
+        let mut packet = Vec::with_capacity(40 + udp.len());
+        match (source.ip(), destination.ip()) {
+            (IpAddr::V4(source_ip), IpAddr::V4(destination_ip)) => {
+                // Note: the UDP checksum is optional for IPv4
+                let total_len = (20 + udp.len()) as u16;
+                packet.extend_from_slice(&[0x45, 0x00]);
+                packet.extend_from_slice(&total_len.to_be_bytes());
+                packet.extend_from_slice(&[0x00, 0x00, 0x40, 0x00, Self::TTL, Self::PROTOCOL_UDP, 0x00, 0x00]);
+                packet.extend_from_slice(&source_ip.octets());
+                packet.extend_from_slice(&destination_ip.octets());
+                let checksum = Self::checksum(&[&packet]);
+                packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+            }
+            (source_ip, destination_ip) => {
+                // Note: IPv4 addresses are mapped if the address families differ
+                let (source_ip, destination_ip) =
+                    (Self::to_ipv6(source_ip).octets(), Self::to_ipv6(destination_ip).octets());
+                let pseudo_header = [&(udp_len as u32).to_be_bytes()[..], &[0, 0, 0, Self::PROTOCOL_UDP]].concat();
+                let checksum = match Self::checksum(&[&source_ip, &destination_ip, &pseudo_header, &udp]) {
+                    0 => 0xffff,
+                    checksum => checksum,
+                };
+                udp[6..8].copy_from_slice(&checksum.to_be_bytes());
+
+                // Assemble the IPv6 header
+                packet.extend_from_slice(&[0x60, 0x00, 0x00, 0x00]);
+                packet.extend_from_slice(&udp_len.to_be_bytes());
+                packet.extend_from_slice(&[Self::PROTOCOL_UDP, Self::TTL]);
+                packet.extend_from_slice(&source_ip);
+                packet.extend_from_slice(&destination_ip);
+            }
+        }
+        packet.extend_from_slice(&udp);
+
+        // Assemble the record header
+        let len = packet.len() as u32;
+        record.clear();
+        record.extend_from_slice(&(now.as_secs() as u32).to_le_bytes());
+        record.extend_from_slice(&now.subsec_micros().to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&packet);
+    }
+    /// Converts an IP address into an IPv6 address, mapping IPv4 addresses
+    fn to_ipv6(address: IpAddr) -> Ipv6Addr {
+        match address {
+            IpAddr::V4(address) => address.to_ipv6_mapped(),
+            IpAddr::V6(address) => address,
+        }
+    }
+    /// Computes the internet checksum over the concatenation of the given parts
+    ///
+    /// # Note
+    /// All parts except the last one must have an even length.
+    fn checksum(parts: &[&[u8]]) -> u16 {
+        let mut sum: u32 = 0;
+        for part in parts {
+            for pair in part.chunks(2) {
+                let word = match pair {
+                    [high, low] => u16::from_be_bytes([*high, *low]),
+                    [high] => u16::from_be_bytes([*high, 0]),
+                    _ => unreachable!("Chunks have one or two bytes"),
+                };
+                sum += word as u32;
+            }
+        }
+
+        // Fold the carries and complement the sum
+        while sum > 0xffff {
+            sum = (sum & 0xffff) + (sum >> 16);
+        }
+        !(sum as u16)
+    }
+}
@@ -0,0 +1,109 @@
+//! Implements a bounded buffer for output that cannot be sent yet
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// A bounded buffer that holds the most recent chunks until they can be sent
+#[derive(Debug)]
+pub struct PendingBuffer {
+    /// The buffered chunks and the time they have been buffered
+    chunks: VecDeque<(Instant, Vec<u8>)>,
+    /// The total size of the buffered chunks
+    size: usize,
+    /// The maximum total size of the buffered chunks
+    capacity: usize,
+    /// The maximum age of the buffered chunks
+    max_age: Option<Duration>,
+}
+impl PendingBuffer {
+    /// Creates a new pending buffer with the given capacity in bytes and the given maximum chunk age
+    pub const fn new(capacity: usize, max_age: Option<Duration>) -> Self {
+        Self { chunks: VecDeque::new(), size: 0, capacity, max_age }
+    }
+
+    /// The total size of the buffered chunks
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+    /// The amount of buffered chunks
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+    /// Whether the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Whether a chunk of the given size fits into the buffer without dropping other chunks
+    pub const fn has_space(&self, len: usize) -> bool {
+        self.size + len <= self.capacity
+    }
+
+    /// Buffers a chunk, dropping the oldest chunks if the buffer is full, and returns the amount of dropped chunks;
+    /// each dropped chunk is passed to `on_drop`
+    pub fn push<F>(&mut self, chunk: &[u8], mut on_drop: F) -> usize
+    where
+        F: FnMut(&[u8]),
+    {
+        // Drop chunks that can never fit
+        if chunk.len() > self.capacity {
+            on_drop(chunk);
+            return 1;
+        }
+
+        // Drop the oldest chunks until the chunk fits
+        let mut dropped = 0;
+        while !self.has_space(chunk.len()) {
+            let (_, chunk) = self.chunks.pop_front().expect("Pending buffer size does not match its chunks");
+            self.size -= chunk.len();
+            on_drop(&chunk);
+            dropped += 1;
+        }
+
+        // Buffer the chunk
+        self.chunks.push_back((Instant::now(), chunk.to_vec()));
+        self.size += chunk.len();
+        dropped
+    }
+    /// Buffers a chunk if it fits into the buffer, and returns whether it has been buffered
+    pub fn try_push(&mut self, chunk: &[u8]) -> bool {
+        if !self.has_space(chunk.len()) {
+            return false;
+        }
+
+        // Buffer the chunk
+        self.chunks.push_back((Instant::now(), chunk.to_vec()));
+        self.size += chunk.len();
+        true
+    }
+
+    /// Drops the chunks that exceed the maximum age and returns the amount of dropped chunks; each dropped chunk is
+    /// passed to `on_drop`
+    pub fn expire<F>(&mut self, mut on_drop: F) -> usize
+    where
+        F: FnMut(&[u8]),
+    {
+        let Some(max_age) = self.max_age else {
+            return 0;
+        };
+
+        // Drop the oldest chunks while they are too old
+        let mut expired = 0;
+        while self.chunks.front().is_some_and(|(buffered, _)| buffered.elapsed() > max_age) {
+            let (_, chunk) = self.chunks.pop_front().expect("Pending buffer front has vanished");
+            self.size -= chunk.len();
+            on_drop(&chunk);
+            expired += 1;
+        }
+        expired
+    }
+
+    /// Removes and returns the oldest buffered chunk together with the time it has been buffered
+    pub fn pop(&mut self) -> Option<(Instant, Vec<u8>)> {
+        let (buffered, chunk) = self.chunks.pop_front()?;
+        self.size -= chunk.len();
+        Some((buffered, chunk))
+    }
+}
@@ -0,0 +1,156 @@
+//! Implements the bounded write queue for the UDP->serial direction
+
+use crate::config::Overflow;
+use std::{
+    collections::VecDeque,
+    sync::{Condvar, Mutex},
+    time::Instant,
+};
+
+/// A queued message and its arrival time
+type Entry = (Instant, Vec<u8>);
+
+/// Decouples the receiving of messages from the serial writes so that bursts are queued up to a fixed depth
+#[derive(Debug)]
+pub struct WriteQueue {
+    /// The queued messages with their arrival time
+    queue: Mutex<VecDeque<Entry>>,
+    /// Signals that messages have been added or taken
+    changed: Condvar,
+    /// The maximum amount of queued messages
+    depth: usize,
+    /// How a message is handled if the queue is full
+    overflow: Overflow,
+}
+impl WriteQueue {
+    /// Creates a new write queue that holds up to `depth` messages
+    pub fn new(depth: usize, overflow: Overflow) -> Self {
+        Self { queue: Mutex::new(VecDeque::with_capacity(depth)), changed: Condvar::new(), depth, overflow }
+    }
+
+    /// Appends a message and passes the message that has been dropped because the queue is full to `on_drop`
+    ///
+    /// # Note
+    /// If the overflow policy is [`Overflow::Block`], this call waits until there is space in the queue. `on_drop` is
+    /// called after the queue has been unlocked, so it may take its time without stalling the writer.
+    pub fn push<F>(&self, message: &[u8], on_drop: F)
+    where
+        F: FnOnce(&[u8]),
+    {
+        let entry = (Instant::now(), message.to_vec());
+        let mut queue = self.queue.lock().expect("Write queue is poisoned");
+
+        // Make room for the message according to the overflow policy
+        // Note: the capacity is checked and the oldest message is evicted under the same lock
+        let dropped = match (self.overflow, queue.len() >= self.depth) {
+            (_, false) => None,
+            (Overflow::Block, true) => {
+                queue = (self.changed.wait_while(queue, |queue| queue.len() >= self.depth))
+                    .expect("Write queue is poisoned");
+                None
+            }
+            (Overflow::DropNew, true) => {
+                drop(queue);
+                return on_drop(&entry.1);
+            }
+            (Overflow::DropOld, true) => queue.pop_front().map(|(_, oldest)| oldest),
+        };
+
+        // Append the message
+        queue.push_back(entry);
+        self.changed.notify_all();
+        drop(queue);
+        if let Some(dropped) = dropped {
+            on_drop(&dropped);
+        }
+    }
+
+    /// Waits for the next message and returns it together with its arrival time
+    pub fn pop(&self) -> (Instant, Vec<u8>) {
+        let queue = self.queue.lock().expect("Write queue is poisoned");
+        let mut queue = self.changed.wait_while(queue, |queue| queue.is_empty()).expect("Write queue is poisoned");
+        let entry = queue.pop_front().expect("Write queue is empty");
+        self.changed.notify_all();
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread, time::Duration};
+
+    /// Pushes the messages and returns the dropped messages
+    fn push_all(queue: &WriteQueue, messages: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut dropped = Vec::new();
+        for message in messages {
+            queue.push(message, |message| dropped.push(message.to_vec()));
+        }
+        dropped
+    }
+
+    #[test]
+    fn drop_new_keeps_the_queued_messages() {
+        let queue = WriteQueue::new(2, Overflow::DropNew);
+        let dropped = push_all(&queue, &[b"a", b"b", b"c"]);
+        assert_eq!(dropped, [b"c"]);
+        assert_eq!(queue.pop().1, b"a");
+        assert_eq!(queue.pop().1, b"b");
+    }
+
+    #[test]
+    fn drop_old_evicts_the_oldest_message() {
+        let queue = WriteQueue::new(2, Overflow::DropOld);
+        let dropped = push_all(&queue, &[b"a", b"b", b"c"]);
+        assert_eq!(dropped, [b"a"]);
+        assert_eq!(queue.pop().1, b"b");
+        assert_eq!(queue.pop().1, b"c");
+    }
+
+    #[test]
+    fn drop_old_does_not_evict_after_space_has_been_freed() {
+        let queue = WriteQueue::new(1, Overflow::DropOld);
+        push_all(&queue, &[b"a"]);
+        assert_eq!(queue.pop().1, b"a");
+        assert!(push_all(&queue, &[b"b"]).is_empty());
+        assert_eq!(queue.pop().1, b"b");
+    }
+
+    #[test]
+    fn block_waits_for_space() {
+        let queue = Arc::new(WriteQueue::new(1, Overflow::Block));
+        push_all(&queue, &[b"a"]);
+
+        // Push a second message which blocks until the first one has been taken
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || push_all(&queue, &[b"b"]))
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!producer.is_finished());
+        assert_eq!(queue.pop().1, b"a");
+        assert!(producer.join().expect("Producer has panicked").is_empty());
+        assert_eq!(queue.pop().1, b"b");
+    }
+
+    #[test]
+    fn concurrent_drop_old_does_not_deadlock() {
+        let queue = Arc::new(WriteQueue::new(1, Overflow::DropOld));
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    queue.pop();
+                }
+            })
+        };
+
+        // Push until the consumer has seen the last message
+        let mut pushed = 0u32;
+        while !consumer.is_finished() {
+            queue.push(&pushed.to_be_bytes(), |_| ());
+            pushed += 1;
+        }
+        consumer.join().expect("Consumer has panicked");
+    }
+}
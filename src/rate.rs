@@ -0,0 +1,51 @@
+//! Implements windowed data rate counters
+
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Counts the bytes and packets of a direction within the current window
+#[derive(Debug, Default)]
+pub struct RateCounter {
+    /// The amount of bytes within the current window
+    bytes: AtomicU64,
+    /// The amount of packets within the current window
+    packets: AtomicU64,
+}
+impl RateCounter {
+    /// Records a packet
+    pub fn record(&self, len: usize) {
+        self.bytes.fetch_add(len as u64, Ordering::Relaxed);
+        self.packets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Computes the rate over the elapsed window and starts a new window
+    pub fn take(&self, elapsed: Duration) -> Rate {
+        let bytes = self.bytes.swap(0, Ordering::Relaxed);
+        let packets = self.packets.swap(0, Ordering::Relaxed);
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        Rate { bytes_per_sec: bytes as f64 / seconds, packets_per_sec: packets as f64 / seconds }
+    }
+}
+
+/// A data rate
+#[derive(Debug, Clone, Copy)]
+pub struct Rate {
+    /// The bytes per second
+    pub bytes_per_sec: f64,
+    /// The packets per second
+    pub packets_per_sec: f64,
+}
+impl Display for Rate {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Select a human-readable unit
+        let (value, unit) = match self.bytes_per_sec {
+            rate if rate >= 1_000_000.0 => (rate / 1_000_000.0, "MB/s"),
+            rate if rate >= 1_000.0 => (rate / 1_000.0, "KB/s"),
+            rate => (rate, "B/s"),
+        };
+        write!(f, "{value:.1} {unit} ({:.1} packets/s)", self.packets_per_sec)
+    }
+}
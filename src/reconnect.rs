@@ -0,0 +1,166 @@
+//! Implements a shared serial device that can be reopened after I/O errors
+
+use crate::{
+    config,
+    error::{Error, ErrorCategory, ErrorKind},
+    serial::SerialDevice,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// The current device and its generation
+#[derive(Debug)]
+struct Slot {
+    /// The current device, or `None` while it is being reopened
+    device: Option<SerialDevice>,
+    /// The generation which is incremented on each reopen
+    generation: u64,
+}
+
+/// A serial device shared between the runloops
+///
+/// # Coordination
+/// Each runloop works on its own clone of the current device and remembers the generation of that clone. If a runloop
+/// encounters an I/O error, it calls [`SharedDevice::reopen`] with its generation: the first runloop closes the stale
+/// device and reopens it while holding the lock; all other runloops block on the lock and then simply receive a clone
+/// of the reopened device since their generation is outdated.
+///
+/// # Shared state
+/// The clones share the open file description and thus the blocking mode and the termios settings; see
+/// [`SerialDevice::try_clone`].
+#[derive(Debug)]
+pub struct SharedDevice {
+    /// The current device
+    slot: Mutex<Slot>,
+    /// The amount of reconnect cycles, i.e. connections that failed after they have been established successfully
+    cycles: AtomicU64,
+}
+impl SharedDevice {
+    /// Creates a new shared device
+    pub fn new(device: SerialDevice) -> Self {
+        Self { slot: Mutex::new(Slot { device: Some(device), generation: 0 }), cycles: AtomicU64::new(0) }
+    }
+
+    /// The amount of reconnect cycles so far
+    pub fn cycles(&self) -> u64 {
+        self.cycles.load(Ordering::SeqCst)
+    }
+
+    /// Gets a clone of the current device and its generation
+    pub fn get(&self) -> Result<(SerialDevice, u64), Error> {
+        let slot = self.slot.lock().expect("Shared device is poisoned");
+        Ok((Self::clone_current(&slot)?, slot.generation))
+    }
+    /// Gets a clone of the current device without blocking, or `None` if the device is currently being reopened
+    pub fn try_get(&self) -> Option<SerialDevice> {
+        let slot = self.slot.try_lock().ok()?;
+        slot.device.as_ref()?.try_clone().ok()
+    }
+
+    /// Reopens the device via `open` after `error` unless it has already been reopened since `generation`, and returns
+    /// a clone of the current device, or `None` if the maximum amount of reconnect cycles has been reached
+    pub fn reopen<F>(
+        &self,
+        generation: u64,
+        error: &Error,
+        config: &config::Reconnect,
+        open: F,
+    ) -> Result<Option<(SerialDevice, u64)>, Error>
+    where
+        F: FnMut() -> Result<SerialDevice, Error>,
+    {
+        // Use the current device if it has already been reopened by another runloop
+        let mut slot = self.slot.lock().expect("Shared device is poisoned");
+        if slot.generation != generation {
+            return Ok(Some((Self::clone_current(&slot)?, slot.generation)));
+        }
+
+        // Count the cycle
+        let cause = match error.kind() {
+            ErrorKind::Disconnected => "serial device disconnected",
+            _ => "serial I/O error",
+        };
+        warn!("{cause} ({}); reopening the serial device", error.to_string().trim_end());
+        let cycles = self.cycles.fetch_add(1, Ordering::SeqCst) + 1;
+        if config.max_cycles != 0 && cycles >= config.max_cycles {
+            return Ok(None);
+        }
+
+        // Reopen the device
+        let note = format!("{cycles} reconnect cycles total");
+        Ok(Some(Self::reopen_slot(&mut slot, config, open, &note)?))
+    }
+    /// Closes and reopens the device via `open` on request of the operator, and returns the new generation
+    ///
+    /// # Note
+    /// Operator-triggered reopens do not count as reconnect cycles. The runloops switch to the reopened device before
+    /// their next I/O via [`SharedDevice::refresh`].
+    pub fn reopen_now<F>(&self, config: &config::Reconnect, open: F) -> Result<u64, Error>
+    where
+        F: FnMut() -> Result<SerialDevice, Error>,
+    {
+        let mut slot = self.slot.lock().expect("Shared device is poisoned");
+        info!("Reopening the serial device on request");
+        let (_, generation) = Self::reopen_slot(&mut slot, config, open, "on request")?;
+        Ok(generation)
+    }
+    /// Replaces `device` with a clone of the current device if it has been reopened since `generation`
+    pub fn refresh(&self, device: &mut SerialDevice, generation: &mut u64) -> Result<(), Error> {
+        let slot = self.slot.lock().expect("Shared device is poisoned");
+        if slot.generation != *generation {
+            (*device, *generation) = (Self::clone_current(&slot)?, slot.generation);
+        }
+        Ok(())
+    }
+
+    /// Clones the current device in the locked slot
+    fn clone_current(slot: &Slot) -> Result<SerialDevice, Error> {
+        let device = slot.device.as_ref().ok_or(eio!("Serial device is not available"));
+        let device = device.and_then(|device| Ok(device.try_clone()?));
+        device.map_err(|e| e.in_category(ErrorCategory::Serial))
+    }
+    /// Closes the device in the locked slot and reopens it via `open` with an exponential backoff, and logs the success
+    /// with the given note
+    fn reopen_slot<F>(
+        slot: &mut Slot,
+        config: &config::Reconnect,
+        mut open: F,
+        note: &str,
+    ) -> Result<(SerialDevice, u64), Error>
+    where
+        F: FnMut() -> Result<SerialDevice, Error>,
+    {
+        // Close the stale device so that the device node can be reused and retry with an exponential backoff
+        slot.device = None;
+        let (mut delay, max_delay) =
+            (Duration::from_millis(config.initial_delay_ms), Duration::from_millis(config.max_delay_ms));
+        for attempt in 1.. {
+            thread::sleep(delay);
+            match open() {
+                Ok(device) => {
+                    info!("Reopened serial device after {attempt} attempts ({note})");
+                    let clone = device.try_clone().map_err(|e| Error::from(e).in_category(ErrorCategory::Serial))?;
+                    (slot.device, slot.generation) = (Some(device), slot.generation + 1);
+                    return Ok((clone, slot.generation));
+                }
+                Err(e) if config.max_retries != 0 && attempt >= config.max_retries => {
+                    let e = e.to_string();
+                    let error = eio!("Failed to reopen serial device after {attempt} attempts ({})", e.trim_end());
+                    return Err(error.in_category(ErrorCategory::Serial));
+                }
+                Err(e) => {
+                    let e = e.to_string();
+                    delay = (delay * 2).min(max_delay);
+                    warn!("failed to reopen serial device ({}); retrying in {delay:?}", e.trim_end());
+                }
+            }
+        }
+        unreachable!("The retry loop is infinite")
+    }
+}
@@ -0,0 +1,54 @@
+//! Implements the content-based routing of serial frames to UDP destinations
+
+use crate::{
+    config::{self, Unrouted},
+    destination::Destination,
+};
+use std::time::Duration;
+
+/// The routing decision for a frame
+#[derive(Debug)]
+pub enum Route<'a> {
+    /// Send the frame to the destination of the matching rule
+    Rule(&'a Destination),
+    /// Send the frame to the default destination
+    Default,
+    /// Drop the frame
+    Drop,
+}
+
+/// Routes serial frames to UDP destinations by their prefix
+#[derive(Debug)]
+pub struct Router {
+    /// The frame prefixes and their destinations in rule order
+    rules: Vec<(Vec<u8>, Destination)>,
+    /// What happens to frames that match no rule
+    unmatched: Unrouted,
+}
+impl Router {
+    /// Creates a new router where each rule destination is re-resolved like the default destination
+    pub fn new(config: &config::Routing, resolve_interval: Option<Duration>) -> Self {
+        let rules = (config.rules.iter())
+            .map(|rule| {
+                let destination = Destination::new(Some(&rule.send), resolve_interval, None, None);
+                (rule.prefix.as_bytes().to_vec(), destination)
+            })
+            .collect();
+        Self { rules, unmatched: config.unmatched }
+    }
+
+    /// The destinations of the rules in rule order
+    pub fn destinations(&self) -> impl Iterator<Item = &Destination> {
+        self.rules.iter().map(|(_, destination)| destination)
+    }
+
+    /// Routes a frame according to the first rule whose prefix matches
+    pub fn route(&self, frame: &[u8]) -> Route<'_> {
+        let rule = self.rules.iter().find(|(prefix, _)| frame.starts_with(prefix));
+        match (rule, self.unmatched) {
+            (Some((_, destination)), _) => Route::Rule(destination),
+            (None, Unrouted::Default) => Route::Default,
+            (None, Unrouted::Drop) => Route::Drop,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Provides an OS-specific syscall sandbox
+
+use std::io;
+
+extern "C" {
+    // int32_t sandbox_apply(uint8_t allow_open)
+    fn sandbox_apply(allow_open: u8) -> i32;
+}
+
+/// Restricts the calling thread and all threads spawned by it afterwards to the syscalls that are needed after setup;
+/// all other syscalls fail with `EPERM`
+///
+/// # Note
+/// This must be called after setup but before spawning any threads so that the filter applies to the whole process.
+/// This fails with [`io::ErrorKind::Unsupported`] if sandboxing is not supported on this platform (Linux on x86_64 and
+/// aarch64 only).
+pub fn apply(allow_open: bool) -> io::Result<()> {
+    let result = unsafe { sandbox_apply(allow_open as u8) };
+    if result == -2 {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "Sandboxing is not supported on this platform"));
+    }
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
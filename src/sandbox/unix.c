@@ -0,0 +1,153 @@
+#include <stdint.h>
+#include <stddef.h>
+#include <errno.h>
+#ifdef __linux__
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <linux/audit.h>
+#include <linux/filter.h>
+#include <linux/seccomp.h>
+
+#if defined(__x86_64__)
+/// The audit architecture of the allowed syscalls
+#define SANDBOX_ARCH AUDIT_ARCH_X86_64
+#elif defined(__aarch64__)
+/// The audit architecture of the allowed syscalls
+#define SANDBOX_ARCH AUDIT_ARCH_AARCH64
+#endif
+#endif
+
+#ifdef SANDBOX_ARCH
+/// The syscall numbers at or above this limit belong to the x32 ABI on x86_64 and are always denied
+#define SANDBOX_X32_SYSCALL_BIT 0x40000000
+
+/**
+ * @brief The syscalls the server needs after setup (threads, memory, time, signals, the serial device and sockets)
+ */
+static const uint32_t sandbox_syscalls_base[] = {
+    // Threads and synchronization
+    SYS_futex, SYS_clone, SYS_set_robust_list, SYS_sched_yield, SYS_gettid, SYS_getpid, SYS_tgkill, SYS_exit,
+    SYS_exit_group, SYS_restart_syscall,
+    // Real-time scheduling of the serial threads
+    SYS_sched_setscheduler,
+#ifdef SYS_clone3
+    SYS_clone3,
+#endif
+#ifdef SYS_rseq
+    SYS_rseq,
+#endif
+    // Memory
+    SYS_brk, SYS_mmap, SYS_munmap, SYS_mremap, SYS_mprotect, SYS_madvise,
+    // Time
+    SYS_clock_gettime, SYS_clock_nanosleep, SYS_nanosleep, SYS_gettimeofday,
+    // Signals
+    SYS_rt_sigprocmask, SYS_rt_sigaction, SYS_rt_sigreturn, SYS_rt_sigtimedwait, SYS_sigaltstack,
+    // File descriptor I/O
+    SYS_read, SYS_write, SYS_readv, SYS_writev, SYS_close, SYS_dup, SYS_fcntl, SYS_ioctl, SYS_ppoll, SYS_lseek,
+    SYS_fstat, SYS_newfstatat,
+#ifdef SYS_poll
+    SYS_poll,
+#endif
+#ifdef SYS_statx
+    SYS_statx,
+#endif
+    // Sockets
+    SYS_socket, SYS_connect, SYS_accept, SYS_accept4, SYS_sendto, SYS_recvfrom, SYS_sendmsg, SYS_recvmsg,
+    SYS_sendmmsg, SYS_recvmmsg, SYS_getsockname, SYS_getpeername, SYS_setsockopt, SYS_getsockopt, SYS_shutdown,
+};
+
+/**
+ * @brief The syscalls to open files and change file attributes (reopening the device, reloading the config, resolving
+ * hostnames, rotating the pcap file)
+ */
+static const uint32_t sandbox_syscalls_open[] = {
+    SYS_openat, SYS_fchmodat, SYS_fchownat, SYS_fchmod, SYS_fchown, SYS_renameat,
+#ifdef SYS_renameat2
+    SYS_renameat2,
+#endif
+#ifdef SYS_rename
+    SYS_rename,
+#endif
+#ifdef SYS_open
+    SYS_open,
+#endif
+#ifdef SYS_chmod
+    SYS_chmod,
+#endif
+#ifdef SYS_chown
+    SYS_chown,
+#endif
+};
+
+/// The amount of base syscalls
+#define SANDBOX_BASE_LEN (sizeof(sandbox_syscalls_base) / sizeof(sandbox_syscalls_base[0]))
+/// The amount of open syscalls
+#define SANDBOX_OPEN_LEN (sizeof(sandbox_syscalls_open) / sizeof(sandbox_syscalls_open[0]))
+/// The maximum filter length (the arch and ABI checks, two instructions per syscall and the default action)
+#define SANDBOX_FILTER_MAX (6 + 2 * (SANDBOX_BASE_LEN + SANDBOX_OPEN_LEN) + 1)
+
+/**
+ * @brief Appends a check to the filter that allows the given syscalls
+ * 
+ * @param filter The filter
+ * @param len The current filter length which is updated accordingly
+ * @param syscalls The syscalls to allow
+ * @param count The amount of syscalls
+ */
+static void sandbox_allow(struct sock_filter* filter, size_t* len, const uint32_t* syscalls, size_t count) {
+    for (size_t index = 0; index < count; index++) {
+        struct sock_filter check = BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, syscalls[index], 0, 1);
+        struct sock_filter allow = BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW);
+        filter[(*len)++] = check;
+        filter[(*len)++] = allow;
+    }
+}
+#endif
+
+/**
+ * @brief Restricts the calling thread and all threads spawned by it afterwards to the syscalls needed after setup;
+ * all other syscalls fail with `EPERM`
+ * 
+ * @param allow_open Whether to allow the syscalls to open files and change file attributes
+ * @return `0`, `-1` on error or `-2` if sandboxing is not supported
+ */
+int32_t sandbox_apply(uint8_t allow_open) {
+#ifdef SANDBOX_ARCH
+    // Validate the architecture and the ABI and load the syscall number
+    struct sock_filter filter[SANDBOX_FILTER_MAX];
+    size_t len = 0;
+    struct sock_filter header[] = {
+        BPF_STMT(BPF_LD | BPF_W | BPF_ABS, offsetof(struct seccomp_data, arch)),
+        BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, SANDBOX_ARCH, 1, 0),
+        BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+        BPF_STMT(BPF_LD | BPF_W | BPF_ABS, offsetof(struct seccomp_data, nr)),
+        BPF_JUMP(BPF_JMP | BPF_JGE | BPF_K, SANDBOX_X32_SYSCALL_BIT, 0, 1),
+        BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | EPERM),
+    };
+    for (size_t index = 0; index < sizeof(header) / sizeof(header[0]); index++) {
+        filter[len++] = header[index];
+    }
+
+    // Allow the syscalls and deny everything else
+    sandbox_allow(filter, &len, sandbox_syscalls_base, SANDBOX_BASE_LEN);
+    if (allow_open) {
+        sandbox_allow(filter, &len, sandbox_syscalls_open, SANDBOX_OPEN_LEN);
+    }
+    struct sock_filter deny = BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | EPERM);
+    filter[len++] = deny;
+
+    // Apply the filter
+    struct sock_fprog program = { .len = (unsigned short)len, .filter = filter };
+    if (prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0) {
+        return -1;
+    }
+    if (prctl(PR_SET_SECCOMP, SECCOMP_MODE_FILTER, &program) != 0) {
+        return -1;
+    }
+    return 0;
+#else
+    // Sandboxing is not supported on this platform
+    (void)allow_open;
+    return -2;
+#endif
+}
@@ -0,0 +1,12 @@
+#include <stdint.h>
+
+/**
+ * @brief Restricts the process to the syscalls needed after setup
+ *
+ * @param allow_open Whether to allow the syscalls to open files and change file attributes
+ * @return `-2` since sandboxing is not supported on this platform
+ */
+int32_t sandbox_apply(uint8_t allow_open) {
+    (void)allow_open;
+    return -2;
+}
@@ -0,0 +1,48 @@
+//! Provides OS-specific real-time scheduling policies
+
+use crate::config::{Scheduling, SchedulingPolicy};
+use std::io;
+
+extern "C" {
+    // int32_t sched_apply(uint32_t policy, int32_t priority)
+    fn sched_apply(policy: u32, priority: i32) -> i32;
+    // int32_t sched_check(uint32_t policy, int32_t priority)
+    fn sched_check(policy: u32, priority: i32) -> i32;
+}
+
+/// Converts a native result into an I/O result
+fn to_io_result(result: i32) -> io::Result<()> {
+    if result == -2 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Real-time scheduling is not supported on this platform",
+        ));
+    }
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The native policy index of a scheduling policy
+const fn policy_id(policy: SchedulingPolicy) -> u32 {
+    match policy {
+        SchedulingPolicy::Fifo => 0,
+        SchedulingPolicy::Rr => 1,
+    }
+}
+
+/// Applies the real-time scheduling policy to the calling thread
+pub fn apply(config: &Scheduling) -> io::Result<()> {
+    to_io_result(unsafe { sched_apply(policy_id(config.policy), config.priority as i32) })
+}
+
+/// Checks whether the real-time scheduling policy can be applied without changing the policy of the calling thread
+///
+/// # Note
+/// This fails with [`io::ErrorKind::PermissionDenied`] if the process lacks the privileges (`CAP_SYS_NICE` or a
+/// sufficient `RLIMIT_RTPRIO` on Linux), and with [`io::ErrorKind::Unsupported`] if real-time scheduling is not
+/// supported on this platform (Unix only).
+pub fn check(config: &Scheduling) -> io::Result<()> {
+    to_io_result(unsafe { sched_check(policy_id(config.policy), config.priority as i32) })
+}
@@ -0,0 +1,84 @@
+#include <stdint.h>
+#include <errno.h>
+#include <pthread.h>
+#include <sched.h>
+
+/**
+ * @brief Converts a policy index into a scheduling policy and validates the priority against its range
+ *
+ * @param policy The policy (`0` for `SCHED_FIFO`, `1` for `SCHED_RR`)
+ * @param priority The static priority
+ * @param native The target for the native scheduling policy
+ * @return `0` or `-1` on error (`EINVAL` if the policy or priority is invalid)
+ */
+static int32_t sched_native(uint32_t policy, int32_t priority, int* native) {
+    switch (policy) {
+        case 0:
+            *native = SCHED_FIFO;
+            break;
+        case 1:
+            *native = SCHED_RR;
+            break;
+        default:
+            errno = EINVAL;
+            return -1;
+    }
+    if (priority < sched_get_priority_min(*native) || priority > sched_get_priority_max(*native)) {
+        errno = EINVAL;
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Applies a real-time scheduling policy to the calling thread
+ *
+ * @param policy The policy (`0` for `SCHED_FIFO`, `1` for `SCHED_RR`)
+ * @param priority The static priority
+ * @return `0` or `-1` on error
+ */
+int32_t sched_apply(uint32_t policy, int32_t priority) {
+    int native;
+    if (sched_native(policy, priority, &native) != 0) {
+        return -1;
+    }
+
+    // Apply the policy
+    struct sched_param param = { .sched_priority = priority };
+    int result = pthread_setschedparam(pthread_self(), native, &param);
+    if (result != 0) {
+        errno = result;
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Checks whether a real-time scheduling policy can be applied by applying it to the calling thread and restoring
+ *        the previous policy afterwards
+ *
+ * @param policy The policy (`0` for `SCHED_FIFO`, `1` for `SCHED_RR`)
+ * @param priority The static priority
+ * @return `0` or `-1` on error
+ */
+int32_t sched_check(uint32_t policy, int32_t priority) {
+    // Get the previous policy
+    int previous;
+    struct sched_param previous_param;
+    int result = pthread_getschedparam(pthread_self(), &previous, &previous_param);
+    if (result != 0) {
+        errno = result;
+        return -1;
+    }
+
+    // Apply the policy and restore the previous policy
+    if (sched_apply(policy, priority) != 0) {
+        return -1;
+    }
+    result = pthread_setschedparam(pthread_self(), previous, &previous_param);
+    if (result != 0) {
+        errno = result;
+        return -1;
+    }
+    return 0;
+}
@@ -0,0 +1,27 @@
+#include <stdint.h>
+
+/**
+ * @brief Applies a real-time scheduling policy to the calling thread
+ *
+ * @param policy The policy (`0` for `SCHED_FIFO`, `1` for `SCHED_RR`)
+ * @param priority The static priority
+ * @return `-2` since real-time scheduling policies are not supported on this platform
+ */
+int32_t sched_apply(uint32_t policy, int32_t priority) {
+    (void)policy;
+    (void)priority;
+    return -2;
+}
+
+/**
+ * @brief Checks whether a real-time scheduling policy can be applied
+ *
+ * @param policy The policy (`0` for `SCHED_FIFO`, `1` for `SCHED_RR`)
+ * @param priority The static priority
+ * @return `-2` since real-time scheduling policies are not supported on this platform
+ */
+int32_t sched_check(uint32_t policy, int32_t priority) {
+    (void)policy;
+    (void)priority;
+    return -2;
+}
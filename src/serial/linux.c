@@ -0,0 +1,38 @@
+// Note: this file is separate from `unix.c` since `<asm/termbits.h>` conflicts with `<termios.h>`
+#ifdef __linux__
+#include <stdint.h>
+#include <errno.h>
+#include <sys/ioctl.h>
+#include <asm/termbits.h>
+
+/**
+ * @brief Sets an arbitrary baud rate via `termios2` and `BOTHER`
+ *
+ * @param fd The file descriptor to configure
+ * @param bauds The baud rate to configure
+ * @return `0` or `-1` in case of an error
+ */
+int32_t serial_linux_set_custom_speed(int64_t fd, uint64_t bauds) {
+    // Reject baud rates that do not fit into `speed_t`
+    if (bauds > UINT32_MAX) {
+        errno = EINVAL;
+        return -1;
+    }
+
+    // Get the device attributes
+    struct termios2 tty;
+    if (ioctl(fd, TCGETS2, &tty) != 0) {
+        return -1;
+    }
+
+    // Set and apply the speed
+    tty.c_cflag &= ~(CBAUD | (CBAUD << IBSHIFT));
+    tty.c_cflag |= BOTHER | (BOTHER << IBSHIFT);
+    tty.c_ispeed = (speed_t)bauds;
+    tty.c_ospeed = (speed_t)bauds;
+    if (ioctl(fd, TCSETS2, &tty) != 0) {
+        return -1;
+    }
+    return 0;
+}
+#endif
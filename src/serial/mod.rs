@@ -1,14 +1,47 @@
 //! Provides OS-specific implementations
 
-use crate::error::Error;
+pub mod sysfs;
+
+use crate::{
+    config::{self, FlowControl, FlushMode, Parity},
+    error::Error,
+};
 use std::{
     ffi::CString,
+    fmt::{self, Display, Formatter},
     io::{self, Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    fs::{self, Permissions},
+    os::unix::fs::{self as unix_fs, PermissionsExt},
 };
 
 extern "C" {
-    // int64_t serial_open(const char* path, uint64_t bauds)
-    fn serial_open(path: *const u8, bauds: u64) -> i64;
+    // int64_t serial_open(const char* path, uint64_t bauds, uint32_t flow_control, uint8_t data_bits, uint8_t parity,
+    //     uint8_t stop_bits)
+    fn serial_open(path: *const u8, bauds: u64, flow_control: u32, data_bits: u8, parity: u8, stop_bits: u8) -> i64;
+
+    // int32_t serial_adopt(int64_t fd, uint8_t configure)
+    fn serial_adopt(fd: i64, configure: u8) -> i32;
+
+    // int32_t serial_set_baudrate(int64_t fd, uint64_t bauds)
+    fn serial_set_baudrate(fd: i64, bauds: u64) -> i32;
+
+    // int64_t serial_read_window(int64_t fd, uint8_t* buf, uint64_t len, uint64_t timeout_ms)
+    fn serial_read_window(fd: i64, buf: *mut u8, len: u64, timeout_ms: u64) -> i64;
+
+    // int32_t serial_poll(int64_t fd, uint64_t timeout_ms)
+    fn serial_poll(fd: i64, timeout_ms: u64) -> i32;
+
+    // int32_t serial_poll_write(int64_t fd, uint64_t timeout_ms)
+    fn serial_poll_write(fd: i64, timeout_ms: u64) -> i32;
+
+    // int64_t serial_output_pending(int64_t fd)
+    fn serial_output_pending(fd: i64) -> i64;
 
     // int64_t serial_duplicate(int64_t fd)
     fn serial_duplicate(fd: i64) -> i64;
@@ -19,31 +52,300 @@ extern "C" {
     // int32_t serial_write_one(int64_t fd, const uint8_t* byte)
     fn serial_write_one(fd: i64, byte: *const u8) -> i32;
 
+    // int32_t serial_drain(int64_t fd)
+    fn serial_drain(fd: i64) -> i32;
+
+    // int32_t serial_probe_write(int64_t fd)
+    fn serial_probe_write(fd: i64) -> i32;
+
+    // int32_t serial_get_icounts(int64_t fd, uint64_t* counts)
+    fn serial_get_icounts(fd: i64, counts: *mut u64) -> i32;
+
+    // int32_t serial_set_dtr(int64_t fd, uint8_t on)
+    fn serial_set_dtr(fd: i64, on: u8) -> i32;
+
+    // int32_t serial_set_rts(int64_t fd, uint8_t on)
+    fn serial_set_rts(fd: i64, on: u8) -> i32;
+
+    // int32_t serial_send_break(int64_t fd, uint64_t duration_ms)
+    fn serial_send_break(fd: i64, duration_ms: u64) -> i32;
+
     // void serial_close(int64_t fd)
     fn serial_close(fd: i64);
+
+    // int64_t serial_uid_by_name(const char* name)
+    #[cfg(unix)]
+    fn serial_uid_by_name(name: *const u8) -> i64;
+
+    // int64_t serial_gid_by_name(const char* name)
+    #[cfg(unix)]
+    fn serial_gid_by_name(name: *const u8) -> i64;
+
+    // int64_t serial_list_ports(uint8_t* buf, uint64_t len)
+    #[cfg(windows)]
+    fn serial_list_ports(buf: *mut u8, len: u64) -> i64;
+}
+
+/// The cumulative error counts reported by the serial driver
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SerialErrorCounts {
+    /// The amount of framing errors
+    pub frame: u64,
+    /// The amount of parity errors
+    pub parity: u64,
+    /// The amount of hardware overruns
+    pub overrun: u64,
+    /// The amount of buffer overruns
+    pub buffer_overrun: u64,
+}
+impl Display for SerialErrorCounts {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let Self { frame, parity, overrun, buffer_overrun } = self;
+        write!(f, "frame={frame} parity={parity} overrun={overrun} buffer_overrun={buffer_overrun}")
+    }
+}
+
+/// A serial port found by [`list_ports`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortInfo {
+    /// The device path to configure as `serial.device`
+    pub path: String,
+    /// A description like the USB product name or the driver name if available
+    pub description: Option<String>,
+}
+impl Display for PortInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.description.as_deref() {
+            Some(description) => write!(f, "{} ({description})", self.path),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// Lists the available serial ports sorted by their path
+///
+/// # Note
+/// On Linux, the ttys in `/dev` that are backed by serial hardware according to sysfs are listed; on other Unix
+/// platforms, the callout devices in `/dev` (`cu.*` and `cua*`) are listed without description.
+#[cfg(unix)]
+pub fn list_ports() -> Result<Vec<PortInfo>, Error> {
+    let mut ports = Vec::new();
+    for entry in fs::read_dir("/dev")? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let description = match cfg!(target_os = "linux") {
+            true => match sysfs::tty_description(&name) {
+                Some(description) => Some(description),
+                None => continue,
+            },
+            false => {
+                // Note: FreeBSD also creates `.init` and `.lock` nodes for each port
+                let callout = name.starts_with("cu.") || name.starts_with("cua");
+                if !callout || name.ends_with(".init") || name.ends_with(".lock") {
+                    continue;
+                }
+                None
+            }
+        };
+        ports.push(PortInfo { path: format!("/dev/{name}"), description });
+    }
+    ports.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ports)
+}
+/// Lists the available serial ports sorted by their path
+///
+/// # Note
+/// The ports registered in the serial port map of the registry are listed with their device name as description.
+#[cfg(windows)]
+pub fn list_ports() -> Result<Vec<PortInfo>, Error> {
+    // Read the list and retry with the required size if it has been truncated
+    let mut buf = vec![0; 4096];
+    loop {
+        let len = unsafe { serial_list_ports(buf.as_mut_ptr(), buf.len() as u64) };
+        if len < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        if len as usize <= buf.len() {
+            buf.truncate(len as usize);
+            break;
+        }
+        buf.resize(len as usize, 0);
+    }
+
+    // Parse the `port\tdevice` lines
+    let list = String::from_utf8_lossy(&buf);
+    let mut ports: Vec<_> = (list.lines())
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(port, device)| PortInfo { path: port.to_string(), description: Some(device.to_string()) })
+        .collect();
+    ports.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ports)
+}
+
+/// What completes a chunk read via [`SerialDevice::read_chunk`]
+#[derive(Debug, Clone, Copy)]
+pub enum Terminator<'a> {
+    /// A newline or a full buffer
+    Newline,
+    /// The delimiter only, so a frame that exceeds the buffer is returned as partial chunk
+    Delimiter(&'a [u8]),
+    /// A full buffer only
+    Full,
 }
 
 /// A serial device
+///
+/// # I/O
+/// The device implements [`Read`] and [`Write`], also for shared references like [`std::fs::File`], so it can be used
+/// with the `std::io` adapters like [`io::copy`] or [`io::BufReader`]. The frame-aware reads of the forwarding use
+/// [`SerialDevice::read_chunk`] instead of [`io::BufRead::read_until`] since a buffered reader could neither return a
+/// partial frame after the idle timeout nor observe the cancellation flag.
+#[derive(Debug)]
 pub struct SerialDevice {
     /// The underlying file descriptor
     fd: i64,
+    /// How flushing behaves
+    flush_mode: FlushMode,
+    /// The maximum time a read waits for the next byte, or `None` to wait indefinitely
+    read_timeout: Option<Duration>,
+    /// The maximum time a write or flush may take, or `None` to wait indefinitely
+    write_timeout: Option<Duration>,
 }
 impl SerialDevice {
+    /// The maximum interval to check the cancellation flag in [`SerialDevice::read_chunk`]
+    pub const CANCEL_INTERVAL: Duration = Duration::from_millis(100);
+    /// The interval to check whether the output has been transmitted if a write timeout is set
+    const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
     /// Opens a serial device
-    pub fn new(path: &str, baudrate: u64) -> Result<Self, Error> {
+    ///
+    /// # Baudrate
+    /// Standard baudrates are set via the `termios` speed constants; nonstandard baudrates are set via `termios2` on
+    /// Linux and `IOSSIOSPEED` on macOS and fail with [`io::ErrorKind::InvalidInput`] if the driver rejects them; some
+    /// drivers round them to the closest supported baudrate instead.
+    ///
+    /// # Flow control
+    /// Opening fails with [`io::ErrorKind::Unsupported`] if the platform or the driver does not support the requested
+    /// flow control; the applied settings are read back since drivers may silently ignore unsupported flags.
+    pub fn new(config: &config::Serial) -> Result<Self, Error> {
+        // Adjust the device node permissions
+        Self::set_permissions(config)?;
+
         // Prepare the path
-        let path = CString::new(path)?;
+        let path = CString::new(config.device.as_str())?;
 
         // Open the serial device
-        let fd = unsafe { serial_open(path.as_bytes_with_nul().as_ptr(), baudrate) };
+        let flow_control = match config.flow_control {
+            FlowControl::None => 0,
+            FlowControl::RtsCts => 1,
+            FlowControl::XonXoff => 2,
+        };
+        let parity = match config.parity {
+            Parity::None => 0,
+            Parity::Even => 1,
+            Parity::Odd => 2,
+        };
+        let fd = unsafe {
+            let path = path.as_bytes_with_nul().as_ptr();
+            serial_open(path, config.baudrate, flow_control, config.data_bits, parity, config.stop_bits)
+        };
         if fd < 0 {
             let errno = io::Error::last_os_error();
             return Err(errno.into());
         }
-        Ok(Self { fd })
+        let read_timeout = config.read_timeout_ms.map(Duration::from_millis);
+        let write_timeout = config.write_timeout_ms.map(Duration::from_millis);
+        let device = Self { fd, flush_mode: config.flush_mode, read_timeout, write_timeout };
+
+        // Apply the initial modem control lines
+        if let Some(on) = config.initial_dtr {
+            device.set_dtr(on).map_err(|e| Self::modem_line_error(config, "DTR", e))?;
+        }
+        if let Some(on) = config.initial_rts {
+            device.set_rts(on).map_err(|e| Self::modem_line_error(config, "RTS", e))?;
+        }
+        Ok(device)
+    }
+    /// Creates a descriptive error for a modem control line that cannot be set
+    fn modem_line_error(config: &config::Serial, line: &str, error: Error) -> Error {
+        eio!(
+            "Failed to set the {line} line of {} ({}); the port may not support modem control lines",
+            config.device,
+            error.to_string().trim_end()
+        )
+    }
+
+    /// Wraps an already open serial device file descriptor, e.g. one inherited via systemd socket activation or a PTY
+    ///
+    /// # Ownership
+    /// The serial device takes ownership of the file descriptor and closes it on drop like any other serial device; if
+    /// wrapping fails, the file descriptor is left open.
+    ///
+    /// # Configuration
+    /// If `configure` is set, the device is configured as raw 8N1 serial device without flow control like a device
+    /// opened via [`SerialDevice::new`] with the default character format, but the current baudrate is kept; use
+    /// [`SerialDevice::set_baudrate`] to change it. Flushing waits until all output has been transmitted, and reads and
+    /// writes wait indefinitely.
+    pub fn from_raw_fd(fd: i64, configure: bool) -> Result<Self, Error> {
+        let result = unsafe { serial_adopt(fd, configure as u8) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(Self { fd, flush_mode: FlushMode::Drain, read_timeout: None, write_timeout: None })
+    }
+
+    /// Applies the configured mode and owner to the device node
+    #[cfg(unix)]
+    fn set_permissions(config: &config::Serial) -> Result<(), Error> {
+        // Apply the mode
+        if let Some(mode) = config.chmod {
+            let permissions = Permissions::from_mode(mode);
+            fs::set_permissions(&config.device, permissions)
+                .map_err(|e| eio!("Failed to change the mode of {} to {mode:o} ({e})", config.device))?;
+        }
+
+        // Apply the owner
+        if let Some(owner) = config.chown.as_deref() {
+            // Parse `user`, `user:group` or `:group`
+            let (user, group) = owner.split_once(':').unwrap_or((owner, ""));
+            let uid = (!user.is_empty()).then(|| Self::id_by_name(user, serial_uid_by_name)).transpose()?;
+            let gid = (!group.is_empty()).then(|| Self::id_by_name(group, serial_gid_by_name)).transpose()?;
+            unix_fs::chown(&config.device, uid, gid)
+                .map_err(|e| eio!("Failed to change the owner of {} to {owner} ({e})", config.device))?;
+        }
+        Ok(())
+    }
+    /// Rejects a configured mode or owner since device nodes have no UNIX permissions on this platform
+    #[cfg(not(unix))]
+    fn set_permissions(config: &config::Serial) -> Result<(), Error> {
+        if config.chmod.is_some() || config.chown.is_some() {
+            return Err(eio!("Changing the mode or owner of {} is not supported on this platform", config.device));
+        }
+        Ok(())
+    }
+    /// Resolves a numeric or named user or group ID
+    #[cfg(unix)]
+    fn id_by_name(name: &str, lookup: unsafe extern "C" fn(*const u8) -> i64) -> Result<u32, Error> {
+        // Accept numeric IDs as-is
+        if let Ok(id) = name.parse() {
+            return Ok(id);
+        }
+
+        // Look up the name
+        let name_ = CString::new(name)?;
+        let id = unsafe { lookup(name_.as_bytes_with_nul().as_ptr()) };
+        u32::try_from(id).map_err(|_| eio!("Unknown user or group: {name}"))
     }
 
     /// Tries to clone the serial device by duplicating the underlying file descriptor
+    ///
+    /// # Shared state
+    /// The clone shares the open file description with the original device, so the file status flags (like
+    /// `O_NONBLOCK`), the baudrate and all other termios settings are shared between all clones; only the descriptor
+    /// itself is independent. All clones therefore rely on the same blocking mode, which is re-established on each
+    /// clone, and settings like [`SerialDevice::set_baudrate`] apply to all clones at once. Per-direction settings are
+    /// not supported; they would require opening the device twice, which exclusive devices and PTYs do not permit.
     pub fn try_clone(&self) -> io::Result<Self> {
         // Duplicate file descriptor
         let fd = unsafe { serial_duplicate(self.fd) };
@@ -51,12 +353,237 @@ impl SerialDevice {
             let errno = io::Error::last_os_error();
             return Err(errno);
         }
-        Ok(Self { fd })
+        Ok(Self { fd, flush_mode: self.flush_mode, read_timeout: self.read_timeout, write_timeout: self.write_timeout })
+    }
+
+    /// Sets the maximum time a read waits for the next byte, or `None` to wait indefinitely
+    ///
+    /// # Note
+    /// If the timeout elapses before the first byte, [`Read::read`] returns `Ok(0)`; if it elapses later, the bytes
+    /// read so far are returned. The timeout is per device and not shared with clones that have already been created.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Sets the maximum time a write or flush may take, or `None` to wait indefinitely
+    ///
+    /// # Note
+    /// If the device does not accept the whole buffer of a [`Write::write`] or does not transmit the pending output of
+    /// a [`Write::flush`] in time, e.g. because flow control holds the transmission forever, the call fails with
+    /// [`io::ErrorKind::TimedOut`]; the bytes accepted so far are not taken back. The timeout is per device and not
+    /// shared with clones that have already been created.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    /// Changes the baudrate and discards any pending input
+    ///
+    /// # Note
+    /// Nonstandard baudrates are supported if the platform and the driver support them (see [`SerialDevice::new`]).
+    pub fn set_baudrate(&self, baudrate: u64) -> Result<(), Error> {
+        let result = unsafe { serial_set_baudrate(self.fd, baudrate) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(())
+    }
+
+    /// Sets or clears the DTR (data terminal ready) line, e.g. to reset a device or to enter its bootloader
+    ///
+    /// # Note
+    /// This fails if the port has no modem control lines, e.g. for pseudo terminals or some USB serial adapters.
+    pub fn set_dtr(&self, on: bool) -> Result<(), Error> {
+        let result = unsafe { serial_set_dtr(self.fd, on as u8) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(())
+    }
+
+    /// Sets or clears the RTS (request to send) line, e.g. to reset a device or to enter its bootloader
+    ///
+    /// # Note
+    /// This fails if the port has no modem control lines, e.g. for pseudo terminals or some USB serial adapters. With
+    /// hardware flow control, the driver may override the RTS line.
+    pub fn set_rts(&self, on: bool) -> Result<(), Error> {
+        let result = unsafe { serial_set_rts(self.fd, on as u8) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(())
+    }
+
+    /// Transmits a break condition for the given duration once the pending output has been transmitted
+    ///
+    /// # Note
+    /// Platforms without `TIOCSBRK` transmit a break of the platform-defined duration instead.
+    pub fn send_break(&self, duration: Duration) -> Result<(), Error> {
+        let result = unsafe { serial_send_break(self.fd, duration.as_millis() as u64) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(())
+    }
+
+    /// Checks whether the device is writable via its access mode and a zero-byte write, without transmitting anything
+    pub fn probe_write(&self) -> Result<(), Error> {
+        let result = unsafe { serial_probe_write(self.fd) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(())
+    }
+
+    /// Reads until `buf` is full or the timeout has elapsed and returns the amount of bytes read
+    pub fn read_window(&self, buf: &mut [u8], timeout: Duration) -> Result<usize, Error> {
+        let timeout_ms = timeout.as_millis() as u64;
+        let read = unsafe { serial_read_window(self.fd, buf.as_mut_ptr(), buf.len() as u64, timeout_ms) };
+        if read < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(read as usize)
+    }
+
+    /// Reads like [`Read::read`], but returns the partial chunk early if no further byte arrives within `idle` or once
+    /// `cancel` is set, and returns the amount of bytes read and whether the chunk is complete
+    ///
+    /// # Note
+    /// Whether a chunk is complete depends on the `terminator` (see [`Terminator`]). `cancel` is checked at least every
+    /// [`SerialDevice::CANCEL_INTERVAL`], so the read may also return an empty partial chunk; the same applies if no
+    /// byte arrives within the read timeout (see [`SerialDevice::set_read_timeout`]).
+    pub fn read_chunk(
+        &mut self,
+        buf: &mut [u8],
+        terminator: Terminator,
+        idle: Option<Duration>,
+        cancel: &AtomicBool,
+    ) -> io::Result<(usize, bool)> {
+        let (len, mut last_byte) = (buf.len(), Instant::now());
+        for pos in 0..len {
+            // Wait for the next byte
+            loop {
+                // Note: the idle timeout only applies once the chunk has started, the read timeout only before
+                let (elapsed, limit) = (last_byte.elapsed(), if pos > 0 { idle } else { self.read_timeout });
+                if limit.is_some_and(|limit| elapsed >= limit) || cancel.load(Ordering::Relaxed) {
+                    return Ok((pos, false));
+                }
+                let remaining = limit.map(|limit| limit - elapsed).unwrap_or(Duration::MAX);
+                if self.poll(remaining.min(Self::CANCEL_INTERVAL))? {
+                    break;
+                }
+            }
+
+            // Read the next byte
+            let result = unsafe { serial_read_one(self.fd, &mut buf[pos]) };
+            if result < 0 {
+                let errno = io::Error::last_os_error();
+                return Err(errno);
+            }
+            last_byte = Instant::now();
+
+            // Fast return if the chunk is terminated
+            let terminated = match terminator {
+                Terminator::Newline => buf[pos] == b'\n',
+                Terminator::Delimiter(delimiter) => buf[..=pos].ends_with(delimiter),
+                Terminator::Full => false,
+            };
+            if terminated {
+                return Ok((pos + 1, true));
+            }
+        }
+        Ok((len, !matches!(terminator, Terminator::Delimiter(_))))
+    }
+    /// Waits until the device is readable or the timeout has elapsed and returns whether the device is readable
+    fn poll(&self, timeout: Duration) -> io::Result<bool> {
+        // Note: round up so that sub-millisecond timeouts do not spin
+        let timeout_ms = timeout.as_micros().div_ceil(1000) as u64;
+        let result = unsafe { serial_poll(self.fd, timeout_ms) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno);
+        }
+        Ok(result > 0)
+    }
+
+    /// Waits until the device accepts output and fails once the deadline of the write timeout has passed
+    fn poll_write(&self, deadline: Instant, timeout: Duration) -> io::Result<()> {
+        loop {
+            // Note: round up so that sub-millisecond timeouts do not spin
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let result = unsafe { serial_poll_write(self.fd, remaining.as_micros().div_ceil(1000) as u64) };
+            if result < 0 {
+                let errno = io::Error::last_os_error();
+                return Err(errno);
+            }
+            if result > 0 {
+                return Ok(());
+            }
+            if remaining.is_zero() {
+                return Err(Self::write_timeout_error("accept the output", timeout));
+            }
+        }
+    }
+    /// Waits until the pending output has been transmitted and fails once the deadline of the write timeout has passed
+    fn poll_transmitted(&self, deadline: Instant, timeout: Duration) -> io::Result<()> {
+        loop {
+            let pending = unsafe { serial_output_pending(self.fd) };
+            if pending < 0 {
+                let errno = io::Error::last_os_error();
+                return Err(errno);
+            }
+            if pending == 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Self::write_timeout_error("transmit the output", timeout));
+            }
+            thread::sleep(Self::DRAIN_POLL_INTERVAL);
+        }
+    }
+    /// Creates the error for an exceeded write timeout
+    fn write_timeout_error(action: &str, timeout: Duration) -> io::Error {
+        let message =
+            format!("The serial device did not {action} within the write timeout of {}ms", timeout.as_millis());
+        io::Error::new(io::ErrorKind::TimedOut, message)
+    }
+
+    /// Gets the cumulative error counts from the serial driver
+    ///
+    /// # Note
+    /// This is only supported on Linux and only by drivers that implement `TIOCGICOUNT`.
+    pub fn error_counts(&self) -> Result<SerialErrorCounts, Error> {
+        let mut counts = [0; 4];
+        let result = unsafe { serial_get_icounts(self.fd, counts.as_mut_ptr()) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+
+        let [frame, parity, overrun, buffer_overrun] = counts;
+        Ok(SerialErrorCounts { frame, parity, overrun, buffer_overrun })
     }
 }
 impl Read for SerialDevice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+impl Read for &SerialDevice {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         for (pos, byte) in buf.iter_mut().enumerate() {
+            // Wait for the next byte if appropriate
+            if let Some(timeout) = self.read_timeout {
+                if !self.poll(timeout)? {
+                    return Ok(pos);
+                }
+            }
+
             // Read next byte
             let result = unsafe { serial_read_one(self.fd, byte) };
             if result < 0 {
@@ -74,7 +601,21 @@ impl Read for SerialDevice {
 }
 impl Write for SerialDevice {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+impl Write for &SerialDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let deadline = self.write_timeout.map(|timeout| (Instant::now() + timeout, timeout));
         for byte in buf.iter() {
+            // Wait until the device accepts the next byte if appropriate
+            if let Some((deadline, timeout)) = deadline {
+                self.poll_write(deadline, timeout)?;
+            }
+
             // Write next byte
             let result = unsafe { serial_write_one(self.fd, byte) };
             if result < 0 {
@@ -85,6 +626,21 @@ impl Write for SerialDevice {
         Ok(buf.len())
     }
     fn flush(&mut self) -> io::Result<()> {
+        // Note: the output has already been passed to the kernel by `write`
+        if self.flush_mode == FlushMode::None {
+            return Ok(());
+        }
+
+        // Wait until all output has been transmitted
+        // Note: the drain itself cannot time out, so the output queue is polled first if a write timeout is set
+        if let Some(timeout) = self.write_timeout {
+            self.poll_transmitted(Instant::now() + timeout, timeout)?;
+        }
+        let result = unsafe { serial_drain(self.fd) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno);
+        }
         Ok(())
     }
 }
@@ -93,3 +649,219 @@ impl Drop for SerialDevice {
         unsafe { serial_close(self.fd) }
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        fs::{File, OpenOptions},
+        os::fd::AsRawFd,
+        process::Command,
+    };
+
+    extern "C" {
+        fn grantpt(fd: i32) -> i32;
+        fn unlockpt(fd: i32) -> i32;
+        fn ptsname_r(fd: i32, buf: *mut u8, len: usize) -> i32;
+    }
+
+    /// A pseudo terminal whose secondary side stands in for a serial port
+    pub struct Pty {
+        /// The primary side, which receives the output written to the device and provides its input
+        pub primary: File,
+        /// The path of the secondary side
+        pub path: String,
+    }
+    impl Pty {
+        /// Creates a new pseudo terminal
+        pub fn new() -> Self {
+            let primary = OpenOptions::new().read(true).write(true).open("/dev/ptmx").expect("Failed to open PTY");
+            let fd = primary.as_raw_fd();
+            assert_eq!(unsafe { grantpt(fd) }, 0, "Failed to grant PTY");
+            assert_eq!(unsafe { unlockpt(fd) }, 0, "Failed to unlock PTY");
+
+            // Get the path of the secondary side
+            let mut path = [0; 128];
+            assert_eq!(unsafe { ptsname_r(fd, path.as_mut_ptr(), path.len()) }, 0, "Failed to get PTY path");
+            let len = path.iter().position(|byte| *byte == 0).expect("PTY path is not terminated");
+            Self { primary, path: String::from_utf8(path[..len].to_vec()).expect("PTY path is not UTF-8") }
+        }
+
+        /// Creates a serial config for the secondary side with the given additional `[serial]` config lines
+        pub fn config(&self, config: &str) -> config::Serial {
+            let config = format!("device = \"{}\"\n{config}", self.path);
+            toml::from_str(&config).expect("Invalid serial config in test")
+        }
+        /// Opens the secondary side as serial device with the given additional `[serial]` config lines
+        pub fn open(&self, config: &str) -> Result<SerialDevice, Error> {
+            SerialDevice::new(&self.config(config))
+        }
+    }
+
+    /// Gets the flags of the device as reported by `stty`, or `None` if `stty` is not available
+    fn stty(path: &str) -> Option<Vec<String>> {
+        let output = Command::new("stty").args(["-F", path, "-a"]).output().ok()?;
+        assert!(output.status.success(), "stty failed on {path}");
+        Some(String::from_utf8_lossy(&output.stdout).split([' ', ';', '\n']).map(str::to_string).collect())
+    }
+
+    /// Asserts that the device has all given `stty` flags
+    fn assert_flags(path: &str, flags: &[&str]) {
+        let Some(settings) = stty(path) else {
+            return;
+        };
+        for flag in flags {
+            assert!(settings.iter().any(|setting| setting == flag), "Missing flag `{flag}` in {settings:?}");
+        }
+    }
+
+    /// Gets the input and output speed of the device via `termios2`
+    ///
+    /// # Note
+    /// `stty` cannot report nonstandard speeds with older C libraries, and the request number of `TCGETS2` depends on
+    /// the architecture, so this is limited to the architectures with the generic `ioctl` layout.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64"))]
+    fn speed(device: &SerialDevice) -> (u32, u32) {
+        extern "C" {
+            fn ioctl(fd: i32, request: std::ffi::c_ulong, ...) -> i32;
+        }
+        const TCGETS2: std::ffi::c_ulong = 0x802c_542a;
+
+        // Note: `struct termios2` consists of the four flag fields, the line discipline, 19 control characters and the
+        // input and output speed
+        let mut termios2 = [0u32; 11];
+        assert_eq!(unsafe { ioctl(device.fd as i32, TCGETS2, termios2.as_mut_ptr()) }, 0, "Failed to get speed");
+        (termios2[9], termios2[10])
+    }
+
+    #[test]
+    fn framing_is_applied() {
+        // Note: the PTY driver always forces 8 data bits and clears the parity enable flag, so only the stop bits, the
+        // parity mode and the input parity checking can be observed
+        let pty = Pty::new();
+        let device = pty.open("data_bits = 7\nparity = \"odd\"\nstop_bits = 2").expect("Failed to open PTY");
+        assert_flags(&pty.path, &["cstopb", "parodd", "inpck", "-crtscts", "-ixon", "-icanon", "-isig", "-opost"]);
+        drop(device);
+
+        // Reopen with the default 8N1 framing which must clear the previous flags
+        let mut device = pty.open("").expect("Failed to open PTY");
+        assert_flags(&pty.path, &["-cstopb", "-parodd", "-inpck", "-crtscts", "-ixon", "-icanon", "-isig", "-opost"]);
+
+        // Check that the input is passed through unmodified in raw mode
+        (&pty.primary).write_all(b"a\rb\x03\n").expect("Failed to write to PTY");
+        let mut buf = [0; 16];
+        let len = device.read(&mut buf).expect("Failed to read from PTY");
+        assert_eq!(&buf[..len], b"a\rb\x03\n");
+    }
+
+    #[test]
+    fn invalid_framing_is_rejected() {
+        let pty = Pty::new();
+        for config in ["data_bits = 9", "stop_bits = 3"] {
+            let error = pty.open(config).expect_err("Invalid framing has been accepted");
+            assert!(error.to_string().contains("Invalid argument"), "Unexpected error: {error}");
+        }
+    }
+
+    #[test]
+    fn read_returns_zero_after_the_timeout() {
+        let pty = Pty::new();
+        let mut device = pty.open("read_timeout_ms = 100").expect("Failed to open PTY");
+
+        // Read without any input
+        let (mut buf, start) = ([0; 16], Instant::now());
+        assert_eq!(device.read(&mut buf).expect("Failed to read from PTY"), 0);
+        assert!(start.elapsed() >= Duration::from_millis(100), "Read returned before the timeout");
+
+        // Read an unterminated input which is returned once the timeout elapses after the last byte
+        (&pty.primary).write_all(b"abc").expect("Failed to write to PTY");
+        assert_eq!(device.read(&mut buf).expect("Failed to read from PTY"), 3);
+        assert_eq!(&buf[..3], b"abc");
+    }
+
+    #[test]
+    fn write_times_out_if_the_output_is_not_drained() {
+        // Note: nobody reads the primary side, so the PTY buffer fills up and the device stops accepting output
+        let pty = Pty::new();
+        let mut device = pty.open("write_timeout_ms = 200").expect("Failed to open PTY");
+        let start = Instant::now();
+        let error = device.write(&vec![0x55; 1024 * 1024]).expect_err("Write to a full PTY has succeeded");
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut, "Unexpected error: {error}");
+        assert!(start.elapsed() >= Duration::from_millis(200), "Write timed out too early");
+
+        // Check that the written bytes are available once the output is drained
+        let mut buf = [0; 4];
+        (&pty.primary).read_exact(&mut buf).expect("Failed to read from PTY");
+        assert_eq!(buf, [0x55; 4]);
+    }
+
+    #[test]
+    fn opens_at_a_nonstandard_baudrate() {
+        let pty = Pty::new();
+        let device = pty.open("baudrate = 250000").expect("Failed to open PTY at 250000 baud");
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64"))]
+        assert_eq!(speed(&device), (250_000, 250_000));
+
+        // Switch to a standard baudrate which must replace both custom speeds
+        device.set_baudrate(9600).expect("Failed to set standard baudrate");
+        assert_flags(&pty.path, &["speed", "9600"]);
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64"))]
+        assert_eq!(speed(&device), (9600, 9600));
+    }
+
+    #[test]
+    fn break_is_held_between_the_surrounding_output() {
+        // Note: the Linux PTY driver accepts break requests but does not pass a break condition to the primary side, so
+        // only the duration and the surrounding output can be observed
+        let pty = Pty::new();
+        let mut device = pty.open("").expect("Failed to open PTY");
+        device.write_all(b"before").expect("Failed to write to PTY");
+        let start = Instant::now();
+        device.send_break(Duration::from_millis(100)).expect("Failed to send break");
+        assert!(start.elapsed() >= Duration::from_millis(100), "Break was not held for its duration");
+        device.write_all(b"after").expect("Failed to write to PTY");
+
+        // Check that the break neither dropped nor reordered the output
+        let mut buf = [0; 11];
+        (&pty.primary).read_exact(&mut buf).expect("Failed to read from PTY");
+        assert_eq!(&buf, b"beforeafter");
+    }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64"))]
+    fn clones_share_the_blocking_mode_and_settings() {
+        extern "C" {
+            fn fcntl(fd: i32, cmd: i32, ...) -> i32;
+        }
+        const F_GETFL: i32 = 3;
+        const F_SETFL: i32 = 4;
+        const O_NONBLOCK: i32 = 0o4000;
+
+        /// Gets whether the device is in nonblocking mode
+        fn is_nonblocking(device: &SerialDevice) -> bool {
+            let flags = unsafe { fcntl(device.fd as i32, F_GETFL) };
+            assert!(flags >= 0, "Failed to get the file status flags");
+            flags & O_NONBLOCK != 0
+        }
+
+        // Set one clone to nonblocking mode, which affects the shared open file description
+        let pty = Pty::new();
+        let device = pty.open("").expect("Failed to open PTY");
+        let clone = device.try_clone().expect("Failed to clone the device");
+        let flags = unsafe { fcntl(clone.fd as i32, F_GETFL) };
+        assert_eq!(unsafe { fcntl(clone.fd as i32, F_SETFL, flags | O_NONBLOCK) }, 0, "Failed to set O_NONBLOCK");
+        assert!(is_nonblocking(&device), "The file status flags are not shared between clones");
+
+        // Check that another clone re-establishes the blocking mode for all clones
+        let other = clone.try_clone().expect("Failed to clone the device");
+        for device in [&device, &clone, &other] {
+            assert!(!is_nonblocking(device), "A clone is still in nonblocking mode");
+        }
+
+        // Check that a termios change through one clone is visible through the others
+        other.set_baudrate(19200).expect("Failed to set baudrate");
+        assert_eq!(speed(&device), (19200, 19200));
+        device.set_baudrate(9600).expect("Failed to set baudrate");
+        assert_eq!((speed(&clone), speed(&other)), ((9600, 9600), (9600, 9600)));
+    }
+}
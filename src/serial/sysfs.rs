@@ -0,0 +1,82 @@
+//! Accesses the Linux sysfs attributes of serial devices
+
+use crate::error::Error;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The valid latency timer range in milliseconds
+const LATENCY_TIMER_RANGE: (u64, u64) = (1, 255);
+
+/// Gets the sysfs latency timer attribute for the given device if it exists
+fn latency_timer_path(device: &str) -> Option<PathBuf> {
+    // Resolve symlinks like `/dev/serial/by-id/...` to the real device node
+    let device = fs::canonicalize(device).ok()?;
+    let name = device.file_name()?;
+
+    // Check if the attribute exists (e.g. FTDI devices)
+    let path = Path::new("/sys/bus/usb-serial/devices").join(name).join("latency_timer");
+    path.is_file().then_some(path)
+}
+
+/// Reads the latency timer in milliseconds, or returns `None` if the device has no latency timer attribute
+pub fn latency_timer(device: &str) -> Result<Option<u64>, Error> {
+    // Get the attribute path
+    let Some(path) = latency_timer_path(device) else {
+        return Ok(None);
+    };
+
+    // Read the value
+    let value = fs::read_to_string(&path)?;
+    let value = value.trim().parse().map_err(|_| eio!("Invalid latency timer value: {value}"))?;
+    Ok(Some(value))
+}
+
+/// Sets the latency timer in milliseconds and returns whether the device has a latency timer attribute
+pub fn set_latency_timer(device: &str, milliseconds: u64) -> Result<bool, Error> {
+    // Validate the value
+    let (min, max) = LATENCY_TIMER_RANGE;
+    if !(min..=max).contains(&milliseconds) {
+        return Err(eio!("Invalid latency timer: {milliseconds}ms (expected {min}-{max}ms)"));
+    }
+
+    // Get the attribute path
+    let Some(path) = latency_timer_path(device) else {
+        return Ok(false);
+    };
+
+    // Write the value
+    fs::write(&path, milliseconds.to_string())?;
+    Ok(true)
+}
+
+/// Describes the tty with the given name by its USB product name, its driver or its subsystem, or returns `None` if it
+/// is not backed by serial hardware
+///
+/// # Note
+/// Virtual consoles and the placeholders of legacy UARTs whose hardware is not present (port type `0`) are not backed
+/// by serial hardware.
+pub fn tty_description(name: &str) -> Option<String> {
+    let class = Path::new("/sys/class/tty").join(name);
+    let device = fs::canonicalize(class.join("device")).ok()?;
+    if fs::read_to_string(class.join("type")).is_ok_and(|port_type| port_type.trim() == "0") {
+        return None;
+    }
+
+    // Prefer the USB product name of the device or its parents
+    // Note: the product is an attribute of the USB device, which is the parent of the interface
+    let product = (device.ancestors().take(3)).find_map(|path| fs::read_to_string(path.join("product")).ok());
+    if let Some(product) = product {
+        return Some(product.trim().to_string());
+    }
+
+    // Fall back to the driver or the subsystem name
+    // Note: the generic `port` and `ctrl` drivers of the serial core are not descriptive, so the driver of the
+    // underlying device is used instead
+    let driver = (device.ancestors().take(3))
+        .filter_map(|path| fs::canonicalize(path.join("driver")).ok())
+        .find(|driver| !driver.starts_with("/sys/bus/serial-base"));
+    let name = driver.or_else(|| fs::canonicalize(device.join("subsystem")).ok())?;
+    Some(name.file_name()?.to_string_lossy().into_owned())
+}
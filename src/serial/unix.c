@@ -4,48 +4,217 @@
 #include <termios.h>
 #include <fcntl.h>
 #include <unistd.h>
+#include <pwd.h>
+#include <grp.h>
+#include <poll.h>
+#include <time.h>
+#include <sys/ioctl.h>
+#ifndef CRTSCTS
+/// Hardware flow control is not supported on this platform, so the flag is never set
+#define CRTSCTS 0
+#endif
+#ifdef __linux__
+#include <linux/serial.h>
+#endif
+#ifdef __APPLE__
+#include <IOKit/serial/ioss.h>
+#endif
+
+#ifdef __linux__
+// Implemented in `linux.c`
+int32_t serial_linux_set_custom_speed(int64_t fd, uint64_t bauds);
+#endif
+
+/// A standard baud rate and its `speed_t` constant
+typedef struct {
+    uint64_t bauds;
+    speed_t speed;
+} serial_speed_t;
+
+/// The standard baud rates that are set via `cfsetispeed`/`cfsetospeed`
+static const serial_speed_t SERIAL_SPEEDS[] = {
+    { 50, B50 }, { 75, B75 }, { 110, B110 }, { 134, B134 }, { 150, B150 }, { 200, B200 }, { 300, B300 },
+    { 600, B600 }, { 1200, B1200 }, { 1800, B1800 }, { 2400, B2400 }, { 4800, B4800 }, { 9600, B9600 },
+    { 19200, B19200 }, { 38400, B38400 },
+#ifdef B57600
+    { 57600, B57600 },
+#endif
+#ifdef B115200
+    { 115200, B115200 },
+#endif
+#ifdef B230400
+    { 230400, B230400 },
+#endif
+#ifdef B460800
+    { 460800, B460800 },
+#endif
+#ifdef B500000
+    { 500000, B500000 },
+#endif
+#ifdef B576000
+    { 576000, B576000 },
+#endif
+#ifdef B921600
+    { 921600, B921600 },
+#endif
+#ifdef B1000000
+    { 1000000, B1000000 },
+#endif
+#ifdef B1152000
+    { 1152000, B1152000 },
+#endif
+#ifdef B1500000
+    { 1500000, B1500000 },
+#endif
+#ifdef B2000000
+    { 2000000, B2000000 },
+#endif
+#ifdef B2500000
+    { 2500000, B2500000 },
+#endif
+#ifdef B3000000
+    { 3000000, B3000000 },
+#endif
+#ifdef B3500000
+    { 3500000, B3500000 },
+#endif
+#ifdef B4000000
+    { 4000000, B4000000 },
+#endif
+};
 
 /**
- * @brief Opens a serial device file
+ * @brief Sets the speed of `tty` if `bauds` is a standard baud rate
  * 
- * @param path The path to open
+ * @param tty The attributes to update
  * @param bauds The baud rate to configure
- * @return The device file descriptor or `-1` in case of an error
+ * @return `1` if the speed has been set, `0` if `bauds` is a nonstandard baud rate that must be applied via
+ *         `serial_set_custom_speed` after `tcsetattr`, or `-1` in case of an error
  */
-int64_t serial_open(const uint8_t* path, uint64_t bauds) {
-    // Open the device file nonblocking
-    int devfile = open((const char*)path, O_RDWR | O_NONBLOCK);
-    if (devfile < 0) {
-        return -1;
+static int32_t serial_set_standard_speed(struct termios* tty, uint64_t bauds) {
+    for (size_t index = 0; index < sizeof(SERIAL_SPEEDS) / sizeof(SERIAL_SPEEDS[0]); index++) {
+        if (SERIAL_SPEEDS[index].bauds != bauds) {
+            continue;
+        }
+#ifdef CIBAUD
+        // Clear a previous nonstandard input speed so that the input speed follows the output speed
+        tty->c_cflag &= ~CIBAUD;
+#endif
+        if (cfsetispeed(tty, SERIAL_SPEEDS[index].speed) != 0 || cfsetospeed(tty, SERIAL_SPEEDS[index].speed) != 0) {
+            return -1;
+        }
+        return 1;
     }
+    return 0;
+}
 
-    // Make the file blocking again
-    int flags = fcntl(devfile, F_GETFL, 0);
-    if (fcntl(devfile, F_SETFL, flags & ~O_NONBLOCK) != 0) {
+/**
+ * @brief Applies a nonstandard baud rate to `fd` via `termios2` on Linux, `IOSSIOSPEED` on macOS or the numeric
+ *        `speed_t` on the other platforms
+ * 
+ * @param fd The file descriptor to configure
+ * @param bauds The baud rate to configure
+ * @return `0` or `-1` in case of an error (`EINVAL` if the baud rate is not supported)
+ * 
+ * @note This must be called after `tcsetattr` since `tcsetattr` resets the speed to a standard baud rate.
+ */
+static int32_t serial_set_custom_speed(int64_t fd, uint64_t bauds) {
+#if defined(__linux__)
+    return serial_linux_set_custom_speed(fd, bauds);
+#elif defined(__APPLE__)
+    speed_t speed = (speed_t)bauds;
+    if (ioctl(fd, IOSSIOSPEED, &speed) != 0) {
         return -1;
     }
-
-    // Get the device attributes
+    return 0;
+#else
+    // Note: the BSDs define `speed_t` as the numeric baud rate, so the driver decides which rates are supported
     struct termios tty;
-    if (tcgetattr(devfile, &tty) != 0) {
+    if (tcgetattr(fd, &tty) != 0) {
+        return -1;
+    }
+    if (cfsetispeed(&tty, (speed_t)bauds) != 0 || cfsetospeed(&tty, (speed_t)bauds) != 0) {
         return -1;
     }
+    return tcsetattr(fd, TCSANOW, &tty);
+#endif
+}
 
-    // Set the speed
-    if (cfsetispeed(&tty, bauds) != 0) {
+/**
+ * @brief Configures `fd` as raw serial device with the given speed, character format and flow control
+ * 
+ * @param fd The file descriptor to configure
+ * @param bauds The baud rate to configure or `0` to keep the current baud rate
+ * @param flow_control The flow control to configure (`0`: none, `1`: RTS/CTS, `2`: XON/XOFF)
+ * @param data_bits The amount of data bits (`5` to `8`)
+ * @param parity The parity (`0`: none, `1`: even, `2`: odd)
+ * @param stop_bits The amount of stop bits (`1` or `2`)
+ * @return `0` or `-1` in case of an error (`ENOTSUP` if the flow control is not supported, `EINVAL` if the character
+ *         format is invalid)
+ */
+static int32_t serial_configure(int64_t fd, uint64_t bauds, uint32_t flow_control, uint8_t data_bits, uint8_t parity,
+    uint8_t stop_bits) {
+    // Get the device attributes
+    struct termios tty;
+    if (tcgetattr(fd, &tty) != 0) {
         return -1;
     }
-    if (cfsetospeed(&tty, bauds) != 0) {
+
+    // Set the speed if appropriate
+    int32_t standard_speed = bauds == 0 ? 1 : serial_set_standard_speed(&tty, bauds);
+    if (standard_speed < 0) {
         return -1;
     }
 
-    // Disable parity generation on output and parity checking for input
-    tty.c_cflag &= ~PARENB;
-    // Set one stop bit instead of two
-    tty.c_cflag &= ~CSTOPB;
-    // Use eight bit characters
+    // Set the parity generation on output and the parity checking for input
+    tty.c_cflag &= ~(PARENB | PARODD);
+    tty.c_iflag &= ~INPCK;
+    switch (parity) {
+        case 0:
+            break;
+        case 1:
+            tty.c_cflag |= PARENB;
+            tty.c_iflag |= INPCK;
+            break;
+        case 2:
+            tty.c_cflag |= PARENB | PARODD;
+            tty.c_iflag |= INPCK;
+            break;
+        default:
+            errno = EINVAL;
+            return -1;
+    }
+    // Set the amount of stop bits
+    switch (stop_bits) {
+        case 1:
+            tty.c_cflag &= ~CSTOPB;
+            break;
+        case 2:
+            tty.c_cflag |= CSTOPB;
+            break;
+        default:
+            errno = EINVAL;
+            return -1;
+    }
+    // Set the character size
     tty.c_cflag &= ~CSIZE;
-    tty.c_cflag |= CS8;
+    switch (data_bits) {
+        case 5:
+            tty.c_cflag |= CS5;
+            break;
+        case 6:
+            tty.c_cflag |= CS6;
+            break;
+        case 7:
+            tty.c_cflag |= CS7;
+            break;
+        case 8:
+            tty.c_cflag |= CS8;
+            break;
+        default:
+            errno = EINVAL;
+            return -1;
+    }
     // Disable hardware flow control
     tty.c_cflag &= ~CRTSCTS;
     // Enable receiving
@@ -66,25 +235,266 @@ int64_t serial_open(const uint8_t* path, uint64_t bauds) {
     tty.c_oflag &= ~OPOST;
     // Don't map NL to CR-NL on output
     tty.c_oflag &= ~ONLCR;
+    // Enable the requested flow control
+    switch (flow_control) {
+        case 0:
+            break;
+        case 1:
+            if (CRTSCTS == 0) {
+                errno = ENOTSUP;
+                return -1;
+            }
+            tty.c_cflag |= CRTSCTS;
+            break;
+        case 2:
+            tty.c_iflag |= IXON | IXOFF;
+            break;
+        default:
+            errno = ENOTSUP;
+            return -1;
+    }
     // Minimum number of characters for noncanonical read
     tty.c_cc[VMIN] = 1;
     // Timeout in deciseconds for noncanonical read
     tty.c_cc[VTIME] = 0;
     
-    // Apply the updated TTY settings
-    if (tcsetattr(devfile, TCSANOW, &tty) != 0) {
+    // Apply the updated TTY settings and the nonstandard speed if appropriate
+    if (tcsetattr(fd, TCSANOW, &tty) != 0) {
+        return -1;
+    }
+    if (standard_speed == 0 && serial_set_custom_speed(fd, bauds) != 0) {
+        return -1;
+    }
+
+    // Check that the driver has applied the requested flow control since `tcsetattr` succeeds on partial changes
+    struct termios applied;
+    if (tcgetattr(fd, &applied) != 0) {
+        return -1;
+    }
+    if ((applied.c_cflag & CRTSCTS) != (tty.c_cflag & CRTSCTS)
+        || (applied.c_iflag & (IXON | IXOFF)) != (tty.c_iflag & (IXON | IXOFF))) {
+        errno = ENOTSUP;
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Opens a serial device file
+ * 
+ * @param path The path to open
+ * @param bauds The baud rate to configure
+ * @param flow_control The flow control to configure (`0`: none, `1`: RTS/CTS, `2`: XON/XOFF)
+ * @param data_bits The amount of data bits (`5` to `8`)
+ * @param parity The parity (`0`: none, `1`: even, `2`: odd)
+ * @param stop_bits The amount of stop bits (`1` or `2`)
+ * @return The device file descriptor or `-1` in case of an error (`ENOTSUP` if the flow control is not supported,
+ *         `EINVAL` if the character format is invalid)
+ */
+int64_t serial_open(const uint8_t* path, uint64_t bauds, uint32_t flow_control, uint8_t data_bits, uint8_t parity,
+    uint8_t stop_bits) {
+    // Open the device file nonblocking
+    int devfile = open((const char*)path, O_RDWR | O_NONBLOCK);
+    if (devfile < 0) {
+        return -1;
+    }
+
+    // Make the file blocking again
+    int flags = fcntl(devfile, F_GETFL, 0);
+    if (fcntl(devfile, F_SETFL, flags & ~O_NONBLOCK) != 0) {
+        return -1;
+    }
+
+    // Configure the device
+    if (serial_configure(devfile, bauds, flow_control, data_bits, parity, stop_bits) != 0) {
+        int error = errno;
+        close(devfile);
+        errno = error;
         return -1;
     }
     return devfile;
 }
 
 /**
- * @brief Duplicates `fd`
+ * @brief Adopts an already open serial device file and makes it blocking
+ * 
+ * @param fd The file descriptor to adopt
+ * @param configure Whether to configure the device as raw 8N1 serial device without flow control, keeping the current
+ *        baud rate
+ * @return `0` or `-1` in case of an error
+ */
+int32_t serial_adopt(int64_t fd, uint8_t configure) {
+    // Make the file blocking
+    int flags = fcntl(fd, F_GETFL, 0);
+    if (flags < 0 || fcntl(fd, F_SETFL, flags & ~O_NONBLOCK) != 0) {
+        return -1;
+    }
+
+    // Configure the device if appropriate
+    if (configure && serial_configure(fd, 0, 0, 8, 0, 1) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Changes the baud rate of `fd` and discards any pending input
+ * 
+ * @param fd The file descriptor to configure
+ * @param bauds The baud rate to configure
+ * @return `0` or `-1` on error
+ */
+int32_t serial_set_baudrate(int64_t fd, uint64_t bauds) {
+    // Get the device attributes
+    struct termios tty;
+    if (tcgetattr(fd, &tty) != 0) {
+        return -1;
+    }
+
+    // Set and apply the speed
+    int32_t standard_speed = serial_set_standard_speed(&tty, bauds);
+    if (standard_speed < 0) {
+        return -1;
+    }
+    if (tcsetattr(fd, TCSANOW, &tty) != 0) {
+        return -1;
+    }
+    if (standard_speed == 0 && serial_set_custom_speed(fd, bauds) != 0) {
+        return -1;
+    }
+
+    // Discard the input that has been received with the previous speed
+    if (tcflush(fd, TCIFLUSH) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Reads from `fd` until `buf` is full or the timeout has elapsed
+ * 
+ * @param fd The file descriptor to read from
+ * @param buf The target buffer
+ * @param len The size of the target buffer
+ * @param timeout_ms The timeout in milliseconds
+ * @return The amount of bytes read or `-1` on error
+ */
+int64_t serial_read_window(int64_t fd, uint8_t* buf, uint64_t len, uint64_t timeout_ms) {
+    // Compute the deadline
+    struct timespec now;
+    if (clock_gettime(CLOCK_MONOTONIC, &now) != 0) {
+        return -1;
+    }
+    int64_t deadline = (int64_t)now.tv_sec * 1000 + now.tv_nsec / 1000000 + (int64_t)timeout_ms;
+
+    uint64_t pos = 0;
+    while (pos < len) {
+        // Compute the remaining time
+        if (clock_gettime(CLOCK_MONOTONIC, &now) != 0) {
+            return -1;
+        }
+        int64_t remaining = deadline - ((int64_t)now.tv_sec * 1000 + now.tv_nsec / 1000000);
+        if (remaining <= 0) {
+            break;
+        }
+
+        // Wait for input
+        struct pollfd pollfd = { .fd = (int)fd, .events = POLLIN, .revents = 0 };
+        int ready = poll(&pollfd, 1, (int)remaining);
+        if (ready < 0 && errno == EINTR) {
+            continue;
+        }
+        if (ready < 0) {
+            return -1;
+        }
+        if (ready == 0) {
+            break;
+        }
+
+        // Read the available input
+        ssize_t read_ = read(fd, buf + pos, len - pos);
+        if (read_ == 0) {
+            errno = EOF;
+        }
+        if (read_ < 1) {
+            return -1;
+        }
+        pos += (uint64_t)read_;
+    }
+    return (int64_t)pos;
+}
+
+/**
+ * @brief Waits until `fd` is readable or the timeout has elapsed
+ * 
+ * @param fd The file descriptor to wait for
+ * @param timeout_ms The timeout in milliseconds
+ * @return `1` if `fd` is readable (or has been hung up), `0` if the timeout has elapsed or `-1` on error
+ */
+int32_t serial_poll(int64_t fd, uint64_t timeout_ms) {
+    struct pollfd pollfd = { .fd = (int)fd, .events = POLLIN, .revents = 0 };
+    int ready = poll(&pollfd, 1, (int)timeout_ms);
+    if (ready < 0 && errno == EINTR) {
+        return 0;
+    }
+    if (ready < 0) {
+        return -1;
+    }
+    return ready > 0;
+}
+
+/**
+ * @brief Waits until `fd` accepts output or the timeout has elapsed
+ * 
+ * @param fd The file descriptor to wait for
+ * @param timeout_ms The timeout in milliseconds
+ * @return `1` if `fd` is writable (or has failed), `0` if the timeout has elapsed or `-1` on error
+ */
+int32_t serial_poll_write(int64_t fd, uint64_t timeout_ms) {
+    struct pollfd pollfd = { .fd = (int)fd, .events = POLLOUT, .revents = 0 };
+    int ready = poll(&pollfd, 1, (int)timeout_ms);
+    if (ready < 0 && errno == EINTR) {
+        return 0;
+    }
+    if (ready < 0) {
+        return -1;
+    }
+    return ready > 0;
+}
+
+/**
+ * @brief Gets the amount of output bytes that have been written to `fd` but not transmitted yet
+ * 
+ * @param fd The file descriptor to query
+ * @return The amount of pending output bytes or `-1` on error
+ */
+int64_t serial_output_pending(int64_t fd) {
+    int pending = 0;
+    if (ioctl((int)fd, TIOCOUTQ, &pending) != 0) {
+        return -1;
+    }
+    return (int64_t)pending;
+}
+
+/**
+ * @brief Duplicates `fd` and ensures that the shared open file description is blocking
  * 
  * @param fd The file descriptor to duplicate
  * @return The duplicate file descriptor or `-1` in case of an error
+ * 
+ * @note The duplicate shares the open file description and thus the file status flags like `O_NONBLOCK` and the termios
+ * settings with `fd`; since all I/O relies on blocking reads and writes, the flags are validated here so that a change
+ * by one clone can never silently break another.
  */
 int64_t serial_duplicate(int64_t fd) {
+    // Ensure that the shared open file description is blocking
+    int flags = fcntl(fd, F_GETFL, 0);
+    if (flags < 0) {
+        return -1;
+    }
+    if ((flags & O_NONBLOCK) != 0 && fcntl(fd, F_SETFL, flags & ~O_NONBLOCK) != 0) {
+        return -1;
+    }
     return dup(fd);
 }
 
@@ -125,12 +535,156 @@ int32_t serial_write_one(int64_t fd, const uint8_t* byte) {
     }
 
     // Flush output
-    if (fsync(fd) != 0) {
+    // Note: ttys do not support `fsync` on all platforms (e.g. Linux fails with `EINVAL`), which is not an error since
+    // the byte has already been passed to the driver
+    if (fsync(fd) != 0 && errno != EINVAL) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Waits until all output written to `fd` has been transmitted
+ * 
+ * @param fd The file descriptor to drain
+ * @return `0` or `-1` on error
+ */
+int32_t serial_drain(int64_t fd) {
+    if (tcdrain(fd) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Checks whether `fd` is writable without transmitting anything
+ * 
+ * @param fd The file descriptor to probe
+ * @return `0` or `-1` on error (`EBADF` if `fd` has not been opened for writing)
+ */
+int32_t serial_probe_write(int64_t fd) {
+    // Check the access mode
+    int flags = fcntl(fd, F_GETFL);
+    if (flags < 0) {
+        return -1;
+    }
+    if ((flags & O_ACCMODE) == O_RDONLY) {
+        errno = EBADF;
+        return -1;
+    }
+
+    // Perform a zero-byte write which surfaces driver errors without transmitting anything
+    uint8_t byte = 0;
+    if (write(fd, &byte, 0) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Gets the cumulative error counts of `fd` from the driver
+ * 
+ * @param fd The file descriptor to query
+ * @param counts The target buffer for the framing, parity, overrun and buffer overrun counts
+ * @return `0` or `-1` on error
+ */
+int32_t serial_get_icounts(int64_t fd, uint64_t* counts) {
+#ifdef TIOCGICOUNT
+    // Query the counters
+    struct serial_icounter_struct icount;
+    if (ioctl(fd, TIOCGICOUNT, &icount) != 0) {
+        return -1;
+    }
+
+    // Copy the error counters
+    counts[0] = (uint64_t)icount.frame;
+    counts[1] = (uint64_t)icount.parity;
+    counts[2] = (uint64_t)icount.overrun;
+    counts[3] = (uint64_t)icount.buf_overrun;
+    return 0;
+#else
+    // The ioctl is not supported on this platform
+    (void)fd;
+    (void)counts;
+    errno = ENOTSUP;
+    return -1;
+#endif
+}
+
+/**
+ * @brief Sets or clears the given modem control lines of `fd`
+ * 
+ * @param fd The file descriptor to configure
+ * @param lines The modem control lines (`TIOCM_*`)
+ * @param on Whether to set or clear the lines
+ * @return `0` or `-1` on error (`ENOTTY` or `EINVAL` if the port has no modem control lines)
+ */
+static int32_t serial_set_modem_lines(int64_t fd, int lines, uint8_t on) {
+    if (ioctl(fd, on ? TIOCMBIS : TIOCMBIC, &lines) != 0) {
         return -1;
     }
     return 0;
 }
 
+/**
+ * @brief Sets or clears the DTR line of `fd`
+ * 
+ * @param fd The file descriptor to configure
+ * @param on Whether to set or clear the line
+ * @return `0` or `-1` on error (`ENOTTY` or `EINVAL` if the port has no modem control lines)
+ */
+int32_t serial_set_dtr(int64_t fd, uint8_t on) {
+    return serial_set_modem_lines(fd, TIOCM_DTR, on);
+}
+
+/**
+ * @brief Sets or clears the RTS line of `fd`
+ * 
+ * @param fd The file descriptor to configure
+ * @param on Whether to set or clear the line
+ * @return `0` or `-1` on error (`ENOTTY` or `EINVAL` if the port has no modem control lines)
+ */
+int32_t serial_set_rts(int64_t fd, uint8_t on) {
+    return serial_set_modem_lines(fd, TIOCM_RTS, on);
+}
+
+/**
+ * @brief Transmits a break condition on `fd` after the pending output has been transmitted
+ * 
+ * @param fd The file descriptor to transmit the break on
+ * @param duration_ms The duration of the break in milliseconds (ignored if the platform only supports breaks of a fixed
+ *        duration)
+ * @return `0` or `-1` on error
+ */
+int32_t serial_send_break(int64_t fd, uint64_t duration_ms) {
+    // Wait until the pending output has been transmitted so that the break does not truncate it
+    if (tcdrain(fd) != 0) {
+        return -1;
+    }
+
+#if defined(TIOCSBRK) && defined(TIOCCBRK)
+    // Hold the break condition for the requested duration
+    if (ioctl(fd, TIOCSBRK) != 0) {
+        return -1;
+    }
+    struct timespec duration = { .tv_sec = duration_ms / 1000, .tv_nsec = (duration_ms % 1000) * 1000000 };
+    while (nanosleep(&duration, &duration) != 0 && errno == EINTR) {
+        // Continue sleeping for the remaining duration
+    }
+    if (ioctl(fd, TIOCCBRK) != 0) {
+        return -1;
+    }
+    return 0;
+#else
+    // Fall back to the break of the platform-defined duration
+    (void)duration_ms;
+    if (tcsendbreak(fd, 0) != 0) {
+        return -1;
+    }
+    return 0;
+#endif
+}
+
 /**
  * @brief Closes `fd`
  * 
@@ -139,3 +693,31 @@ int32_t serial_write_one(int64_t fd, const uint8_t* byte) {
 void serial_close(int64_t fd) {
     close(fd);
 }
+
+/**
+ * @brief Looks up the user ID for a user name
+ * 
+ * @param name The user name
+ * @return The user ID or `-1` if the user does not exist
+ */
+int64_t serial_uid_by_name(const uint8_t* name) {
+    struct passwd* entry = getpwnam((const char*)name);
+    if (entry == NULL) {
+        return -1;
+    }
+    return entry->pw_uid;
+}
+
+/**
+ * @brief Looks up the group ID for a group name
+ * 
+ * @param name The group name
+ * @return The group ID or `-1` if the group does not exist
+ */
+int64_t serial_gid_by_name(const uint8_t* name) {
+    struct group* entry = getgrnam((const char*)name);
+    if (entry == NULL) {
+        return -1;
+    }
+    return entry->gr_gid;
+}
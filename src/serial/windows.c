@@ -0,0 +1,545 @@
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <windows.h>
+
+/// The device namespace prefix which is required for `COM10` and above
+#define SERIAL_DEVICE_PREFIX "\\\\.\\"
+/// The interval to check for pending input in milliseconds
+#define SERIAL_POLL_INTERVAL_MS 1
+
+/**
+ * @brief Configures `handle` as raw serial device with the given speed, character format and flow control
+ *
+ * @param handle The handle to configure
+ * @param bauds The baud rate to configure or `0` to keep the current baud rate
+ * @param flow_control The flow control to configure (`0`: none, `1`: RTS/CTS, `2`: XON/XOFF)
+ * @param data_bits The amount of data bits (`5` to `8`)
+ * @param parity The parity (`0`: none, `1`: even, `2`: odd)
+ * @param stop_bits The amount of stop bits (`1` or `2`)
+ * @return `0` or `-1` in case of an error (`ERROR_NOT_SUPPORTED` if the flow control is not supported,
+ *         `ERROR_INVALID_PARAMETER` if the character format is invalid)
+ */
+static int32_t serial_configure(HANDLE handle, uint64_t bauds, uint32_t flow_control, uint8_t data_bits, uint8_t parity,
+    uint8_t stop_bits) {
+    // Get the device attributes
+    DCB dcb;
+    memset(&dcb, 0, sizeof(dcb));
+    dcb.DCBlength = sizeof(dcb);
+    if (!GetCommState(handle, &dcb)) {
+        return -1;
+    }
+
+    // Set the speed if appropriate
+    if (bauds != 0) {
+        dcb.BaudRate = (DWORD)bauds;
+    }
+
+    // Validate the character format
+    if (data_bits < 5 || data_bits > 8 || parity > 2 || stop_bits < 1 || stop_bits > 2) {
+        SetLastError(ERROR_INVALID_PARAMETER);
+        return -1;
+    }
+
+    // Use the raw character format without any special character handling
+    dcb.fBinary = TRUE;
+    dcb.fParity = parity != 0;
+    dcb.Parity = parity == 0 ? NOPARITY : parity == 1 ? EVENPARITY : ODDPARITY;
+    dcb.StopBits = stop_bits == 1 ? ONESTOPBIT : TWOSTOPBITS;
+    dcb.ByteSize = data_bits;
+    dcb.fErrorChar = FALSE;
+    dcb.fNull = FALSE;
+    dcb.fAbortOnError = FALSE;
+    dcb.fDsrSensitivity = FALSE;
+    dcb.fOutxDsrFlow = FALSE;
+    dcb.fDtrControl = DTR_CONTROL_ENABLE;
+
+    // Disable all flow control and enable the requested flow control
+    dcb.fOutxCtsFlow = FALSE;
+    dcb.fRtsControl = RTS_CONTROL_ENABLE;
+    dcb.fOutX = FALSE;
+    dcb.fInX = FALSE;
+    switch (flow_control) {
+        case 0:
+            break;
+        case 1:
+            dcb.fOutxCtsFlow = TRUE;
+            dcb.fRtsControl = RTS_CONTROL_HANDSHAKE;
+            break;
+        case 2:
+            dcb.fOutX = TRUE;
+            dcb.fInX = TRUE;
+            break;
+        default:
+            SetLastError(ERROR_NOT_SUPPORTED);
+            return -1;
+    }
+
+    // Apply the updated settings
+    if (!SetCommState(handle, &dcb)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Makes reads on `handle` return immediately with the pending input and writes block until they are complete
+ *
+ * @param handle The handle to configure
+ * @return `0` or `-1` in case of an error
+ *
+ * @note Reads never block so that a pending read cannot stall a concurrent write on a duplicated handle; the blocking
+ * behavior is emulated by polling the input queue instead.
+ */
+static int32_t serial_set_timeouts(HANDLE handle) {
+    COMMTIMEOUTS timeouts;
+    memset(&timeouts, 0, sizeof(timeouts));
+    timeouts.ReadIntervalTimeout = MAXDWORD;
+    if (!SetCommTimeouts(handle, &timeouts)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Gets the amount of pending input bytes
+ *
+ * @param handle The handle to query
+ * @return The amount of pending input bytes or `-1` on error
+ */
+static int64_t serial_pending(HANDLE handle) {
+    DWORD errors = 0;
+    COMSTAT status;
+    if (!ClearCommError(handle, &errors, &status)) {
+        return -1;
+    }
+    return (int64_t)status.cbInQue;
+}
+
+/**
+ * @brief Opens a serial device
+ *
+ * @param path The device name (e.g. `COM3`) or path (e.g. `\\.\COM10`) to open
+ * @param bauds The baud rate to configure
+ * @param flow_control The flow control to configure (`0`: none, `1`: RTS/CTS, `2`: XON/XOFF)
+ * @param data_bits The amount of data bits (`5` to `8`)
+ * @param parity The parity (`0`: none, `1`: even, `2`: odd)
+ * @param stop_bits The amount of stop bits (`1` or `2`)
+ * @return The device handle or `-1` in case of an error (`ERROR_NOT_SUPPORTED` if the flow control is not supported,
+ *         `ERROR_INVALID_PARAMETER` if the character format is invalid)
+ */
+int64_t serial_open(const uint8_t* path, uint64_t bauds, uint32_t flow_control, uint8_t data_bits, uint8_t parity,
+    uint8_t stop_bits) {
+    // Prefix the device name with the device namespace if necessary
+    const char* name = (const char*)path;
+    size_t prefix_len = strlen(SERIAL_DEVICE_PREFIX);
+    char* prefixed = malloc(prefix_len + strlen(name) + 1);
+    if (prefixed == NULL) {
+        SetLastError(ERROR_NOT_ENOUGH_MEMORY);
+        return -1;
+    }
+    prefixed[0] = 0;
+    if (strncmp(name, SERIAL_DEVICE_PREFIX, prefix_len) != 0) {
+        strcat(prefixed, SERIAL_DEVICE_PREFIX);
+    }
+    strcat(prefixed, name);
+
+    // Open the device exclusively
+    HANDLE handle = CreateFileA(prefixed, GENERIC_READ | GENERIC_WRITE, 0, NULL, OPEN_EXISTING, 0, NULL);
+    free(prefixed);
+    if (handle == INVALID_HANDLE_VALUE) {
+        return -1;
+    }
+
+    // Configure the device
+    if (serial_configure(handle, bauds, flow_control, data_bits, parity, stop_bits) != 0
+        || serial_set_timeouts(handle) != 0) {
+        DWORD error = GetLastError();
+        CloseHandle(handle);
+        SetLastError(error);
+        return -1;
+    }
+    return (int64_t)(intptr_t)handle;
+}
+
+/**
+ * @brief Adopts an already open serial device handle
+ *
+ * @param fd The handle to adopt
+ * @param configure Whether to configure the device as raw 8N1 serial device without flow control, keeping the current
+ *        baud rate
+ * @return `0` or `-1` in case of an error
+ */
+int32_t serial_adopt(int64_t fd, uint8_t configure) {
+    HANDLE handle = (HANDLE)(intptr_t)fd;
+    if (serial_set_timeouts(handle) != 0) {
+        return -1;
+    }
+
+    // Configure the device if appropriate
+    if (configure && serial_configure(handle, 0, 0, 8, 0, 1) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Changes the baud rate of `fd` and discards any pending input
+ *
+ * @param fd The handle to configure
+ * @param bauds The baud rate to configure
+ * @return `0` or `-1` on error
+ */
+int32_t serial_set_baudrate(int64_t fd, uint64_t bauds) {
+    HANDLE handle = (HANDLE)(intptr_t)fd;
+
+    // Get the device attributes and apply the speed
+    DCB dcb;
+    memset(&dcb, 0, sizeof(dcb));
+    dcb.DCBlength = sizeof(dcb);
+    if (!GetCommState(handle, &dcb)) {
+        return -1;
+    }
+    dcb.BaudRate = (DWORD)bauds;
+    if (!SetCommState(handle, &dcb)) {
+        return -1;
+    }
+
+    // Discard the input that has been received with the previous speed
+    if (!PurgeComm(handle, PURGE_RXCLEAR)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Reads from `fd` until `buf` is full or the timeout has elapsed
+ *
+ * @param fd The handle to read from
+ * @param buf The target buffer
+ * @param len The size of the target buffer
+ * @param timeout_ms The timeout in milliseconds
+ * @return The amount of bytes read or `-1` on error
+ */
+int64_t serial_read_window(int64_t fd, uint8_t* buf, uint64_t len, uint64_t timeout_ms) {
+    HANDLE handle = (HANDLE)(intptr_t)fd;
+    ULONGLONG deadline = GetTickCount64() + timeout_ms;
+
+    uint64_t pos = 0;
+    while (pos < len && GetTickCount64() < deadline) {
+        // Read the available input
+        DWORD chunk = len - pos > MAXDWORD ? MAXDWORD : (DWORD)(len - pos);
+        DWORD read_ = 0;
+        if (!ReadFile(handle, buf + pos, chunk, &read_, NULL)) {
+            return -1;
+        }
+        pos += read_;
+
+        // Wait for further input
+        if (read_ == 0) {
+            Sleep(SERIAL_POLL_INTERVAL_MS);
+        }
+    }
+    return (int64_t)pos;
+}
+
+/**
+ * @brief Waits until `fd` has pending input or the timeout has elapsed
+ *
+ * @param fd The handle to wait for
+ * @param timeout_ms The timeout in milliseconds
+ * @return `1` if `fd` has pending input, `0` if the timeout has elapsed or `-1` on error
+ */
+int32_t serial_poll(int64_t fd, uint64_t timeout_ms) {
+    HANDLE handle = (HANDLE)(intptr_t)fd;
+    ULONGLONG deadline = GetTickCount64() + timeout_ms;
+    while (1) {
+        // Check the input queue
+        int64_t pending = serial_pending(handle);
+        if (pending < 0) {
+            return -1;
+        }
+        if (pending > 0) {
+            return 1;
+        }
+
+        // Wait for input
+        if (GetTickCount64() >= deadline) {
+            return 0;
+        }
+        Sleep(SERIAL_POLL_INTERVAL_MS);
+    }
+}
+
+/**
+ * @brief Waits until `fd` accepts output or the timeout has elapsed
+ *
+ * @param fd The handle to wait for
+ * @param timeout_ms The timeout in milliseconds
+ * @return `1` if the transmission is not held by flow control, `0` if the timeout has elapsed or `-1` on error
+ */
+int32_t serial_poll_write(int64_t fd, uint64_t timeout_ms) {
+    HANDLE handle = (HANDLE)(intptr_t)fd;
+    ULONGLONG deadline = GetTickCount64() + timeout_ms;
+    while (1) {
+        // Check whether the transmission is held
+        DWORD errors = 0;
+        COMSTAT status;
+        if (!ClearCommError(handle, &errors, &status)) {
+            return -1;
+        }
+        if (!status.fCtsHold && !status.fDsrHold && !status.fRlsdHold && !status.fXoffHold) {
+            return 1;
+        }
+
+        // Wait for the transmission to resume
+        if (GetTickCount64() >= deadline) {
+            return 0;
+        }
+        Sleep(SERIAL_POLL_INTERVAL_MS);
+    }
+}
+
+/**
+ * @brief Gets the amount of output bytes that have been written to `fd` but not transmitted yet
+ *
+ * @param fd The handle to query
+ * @return The amount of pending output bytes or `-1` on error
+ */
+int64_t serial_output_pending(int64_t fd) {
+    DWORD errors = 0;
+    COMSTAT status;
+    if (!ClearCommError((HANDLE)(intptr_t)fd, &errors, &status)) {
+        return -1;
+    }
+    return (int64_t)status.cbOutQue;
+}
+
+/**
+ * @brief Duplicates `fd`
+ *
+ * @param fd The handle to duplicate
+ * @return The duplicate handle or `-1` in case of an error
+ *
+ * @note The duplicate refers to the same file object and thus shares the device settings and timeouts with `fd`.
+ */
+int64_t serial_duplicate(int64_t fd) {
+    HANDLE process = GetCurrentProcess();
+    HANDLE duplicate = INVALID_HANDLE_VALUE;
+    if (!DuplicateHandle(process, (HANDLE)(intptr_t)fd, process, &duplicate, 0, FALSE, DUPLICATE_SAME_ACCESS)) {
+        return -1;
+    }
+    return (int64_t)(intptr_t)duplicate;
+}
+
+/**
+ * @brief Reads one byte from `fd`
+ *
+ * @param fd The handle to read from
+ * @param buf The target buffer
+ * @return `0` or `-1` on error
+ */
+int32_t serial_read_one(int64_t fd, uint8_t* buf) {
+    HANDLE handle = (HANDLE)(intptr_t)fd;
+    while (1) {
+        // Try to read a single byte
+        DWORD read_ = 0;
+        if (!ReadFile(handle, buf, 1, &read_, NULL)) {
+            return -1;
+        }
+        if (read_ == 1) {
+            return 0;
+        }
+
+        // Wait for input
+        Sleep(SERIAL_POLL_INTERVAL_MS);
+    }
+}
+
+/**
+ * @brief Writes one byte to `fd`
+ *
+ * @param fd The handle to write to
+ * @param byte The byte to write
+ * @return `0` or `-1` on error
+ */
+int32_t serial_write_one(int64_t fd, const uint8_t* byte) {
+    // Write a single byte
+    DWORD written = 0;
+    if (!WriteFile((HANDLE)(intptr_t)fd, byte, 1, &written, NULL)) {
+        return -1;
+    }
+    if (written < 1) {
+        SetLastError(ERROR_HANDLE_EOF);
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Waits until all output written to `fd` has been transmitted
+ *
+ * @param fd The handle to drain
+ * @return `0` or `-1` on error
+ */
+int32_t serial_drain(int64_t fd) {
+    if (!FlushFileBuffers((HANDLE)(intptr_t)fd)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Checks whether `fd` is writable without transmitting anything
+ *
+ * @param fd The handle to probe
+ * @return `0` or `-1` on error
+ */
+int32_t serial_probe_write(int64_t fd) {
+    // Perform a zero-byte write which surfaces driver errors without transmitting anything
+    uint8_t byte = 0;
+    DWORD written = 0;
+    if (!WriteFile((HANDLE)(intptr_t)fd, &byte, 0, &written, NULL)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Gets the cumulative error counts of `fd` from the driver
+ *
+ * @param fd The handle to query
+ * @param counts The target buffer for the framing, parity, overrun and buffer overrun counts
+ * @return `-1` since the driver does not provide cumulative counts (`ERROR_NOT_SUPPORTED`)
+ */
+int32_t serial_get_icounts(int64_t fd, uint64_t* counts) {
+    (void)fd;
+    (void)counts;
+    SetLastError(ERROR_NOT_SUPPORTED);
+    return -1;
+}
+
+/**
+ * @brief Sets or clears the DTR line of `fd`
+ *
+ * @param fd The handle to configure
+ * @param on Whether to set or clear the line
+ * @return `0` or `-1` on error
+ */
+int32_t serial_set_dtr(int64_t fd, uint8_t on) {
+    if (!EscapeCommFunction((HANDLE)(intptr_t)fd, on ? SETDTR : CLRDTR)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Sets or clears the RTS line of `fd`
+ *
+ * @param fd The handle to configure
+ * @param on Whether to set or clear the line
+ * @return `0` or `-1` on error
+ */
+int32_t serial_set_rts(int64_t fd, uint8_t on) {
+    if (!EscapeCommFunction((HANDLE)(intptr_t)fd, on ? SETRTS : CLRRTS)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Transmits a break condition on `fd` after the pending output has been transmitted
+ *
+ * @param fd The handle to transmit the break on
+ * @param duration_ms The duration of the break in milliseconds
+ * @return `0` or `-1` on error
+ */
+int32_t serial_send_break(int64_t fd, uint64_t duration_ms) {
+    // Wait until the pending output has been transmitted so that the break does not truncate it
+    HANDLE handle = (HANDLE)(intptr_t)fd;
+    if (!FlushFileBuffers(handle)) {
+        return -1;
+    }
+
+    // Hold the break condition for the requested duration
+    if (!SetCommBreak(handle)) {
+        return -1;
+    }
+    Sleep((DWORD)duration_ms);
+    if (!ClearCommBreak(handle)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Appends `part` to `buf` as far as it fits and advances `pos` by the length of `part` in any case
+ *
+ * @param buf The target buffer
+ * @param len The size of the target buffer
+ * @param pos The current position which may exceed `len`
+ * @param part The string to append
+ */
+static void serial_list_append(uint8_t* buf, uint64_t len, uint64_t* pos, const char* part) {
+    for (; *part != 0; part++, (*pos)++) {
+        if (*pos < len) {
+            buf[*pos] = (uint8_t)*part;
+        }
+    }
+}
+
+/**
+ * @brief Lists the serial ports registered in the serial port map as `port\tdevice\n` lines
+ *
+ * @param buf The target buffer
+ * @param len The size of the target buffer
+ * @return The total length of the list, which exceeds `len` if the list has been truncated, or `-1` on error
+ */
+int64_t serial_list_ports(uint8_t* buf, uint64_t len) {
+    // Open the serial port map
+    // Note: the key only exists if there is at least one port
+    HKEY key;
+    LSTATUS status = RegOpenKeyExA(HKEY_LOCAL_MACHINE, "HARDWARE\\DEVICEMAP\\SERIALCOMM", 0, KEY_READ, &key);
+    if (status == ERROR_FILE_NOT_FOUND) {
+        return 0;
+    }
+    if (status != ERROR_SUCCESS) {
+        SetLastError((DWORD)status);
+        return -1;
+    }
+
+    // Append each port with its device name
+    uint64_t pos = 0;
+    for (DWORD index = 0;; index++) {
+        char device[256] = { 0 }, port[256] = { 0 };
+        DWORD device_len = sizeof(device), port_len = sizeof(port) - 1, type = 0;
+        status = RegEnumValueA(key, index, device, &device_len, NULL, &type, (LPBYTE)port, &port_len);
+        if (status == ERROR_NO_MORE_ITEMS) {
+            break;
+        }
+        if (status != ERROR_SUCCESS) {
+            RegCloseKey(key);
+            SetLastError((DWORD)status);
+            return -1;
+        }
+        if (type != REG_SZ) {
+            continue;
+        }
+
+        serial_list_append(buf, len, &pos, port);
+        serial_list_append(buf, len, &pos, "\t");
+        serial_list_append(buf, len, &pos, device);
+        serial_list_append(buf, len, &pos, "\n");
+    }
+    RegCloseKey(key);
+    return (int64_t)pos;
+}
+
+/**
+ * @brief Closes `fd`
+ *
+ * @param fd The handle to close
+ */
+void serial_close(int64_t fd) {
+    CloseHandle((HANDLE)(intptr_t)fd);
+}
@@ -1,106 +1,2145 @@
 //! A unified server
 
-use crate::{config::Config, error::Error, logger::Logger, serial::SerialDevice};
+#[cfg(feature = "sandbox")]
+use crate::sandbox;
+use crate::{
+    affinity,
+    allowlist::SourceAllowlist,
+    autobaud,
+    batch::BatchReceiver,
+    capture::Capture,
+    clock,
+    coalesce::WriteCoalescer,
+    config::{self, BytePattern, Config, Framing, Mode, Overflow, Reconnect, Scheduling, StopAction},
+    control::Control,
+    destination::Destination,
+    diag,
+    endpoint::MetricsEndpoint,
+    error::{Error, ErrorCategory, ErrorKind},
+    framing::{Deframer, Encoder},
+    header::Header,
+    histogram::{ChunkHistogram, LatencyHistogram},
+    jitter::JitterBuffer,
+    json,
+    logger::{self, Logging},
+    multicast,
+    pcap::PcapWriter,
+    pending::PendingBuffer,
+    queue::WriteQueue,
+    rate::RateCounter,
+    reconnect::SharedDevice,
+    routing::{Route, Router},
+    sched,
+    serial::{sysfs, SerialDevice, SerialErrorCounts, Terminator},
+    signal::{self, Signal},
+    stats::Stats,
+    translate::Table,
+    transport::{tcp::TcpTransport, Transport},
+    trigger::{StartTrigger, StopTrigger},
+    verify::Verifier,
+};
 use std::{
-    io::{self, Read, Write},
-    net::{ToSocketAddrs, UdpSocket},
-    thread,
+    fs,
+    io::{self, Write},
+    mem,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Condvar, Mutex, RwLock,
+    },
+    thread::{self, Scope},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
+use toml::{map::Map, Value};
+
+/// A data direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Data read from the serial device and forwarded to UDP (RX)
+    SerialToUdp,
+    /// Data received via UDP and written to the serial device (TX)
+    UdpToSerial,
+}
 
 /// The server
 pub struct Server {
     /// The server config
     config: Config,
-    /// The UDP socket
-    socket: UdpSocket,
+    /// The index of the bridge in the config file
+    bridge: usize,
+    /// The UDP socket if UDP is enabled
+    socket: Option<UdpSocket>,
+    /// The outgoing UDP socket
+    sender: UdpSocket,
+    /// The send destination
+    destination: Destination,
+    /// The content-based router for the serial frames
+    router: Option<Router>,
+    /// The source addresses that may send datagrams to the serial device, or `None` if all sources are allowed
+    allowlist: Option<SourceAllowlist>,
+    /// The serial output that has not been sent yet because the destination is not known yet
+    pending: Mutex<PendingBuffer>,
+    /// Signals that the pending buffer has been flushed
+    pending_flushed: Condvar,
     /// The serial device
-    serial: SerialDevice,
-    /// The logger
-    logger: Option<Logger>,
+    serial: SharedDevice,
+    /// The serial settings the device is opened with
+    ///
+    /// # Note
+    /// The settings are replaced on reload so that the device is reopened with the new settings.
+    serial_config: RwLock<config::Serial>,
+    /// Serializes the writes to the serial device so that concurrent inputs are not interleaved
+    write_lock: Mutex<()>,
+    /// The additional transports
+    transports: Vec<Box<dyn Transport>>,
+    /// The logging facility
+    ///
+    /// # Note
+    /// The logging facility is swapped atomically on reload so that logging changes never interrupt the forwarding.
+    logging: RwLock<Option<Arc<Logging>>>,
+    /// Whether to log the dropped data
+    log_drops: AtomicBool,
+    /// The translation table for data read from the serial device
+    translate_rx: Option<Table>,
+    /// The translation table for data written to the serial device
+    translate_tx: Option<Table>,
+    /// The serial frame encoder for datagrams
+    encoder: Option<Encoder>,
+    /// The datagram header
+    header: Option<Header>,
+    /// The jitter buffer
+    jitter: Option<JitterBuffer>,
+    /// The UDP->serial write coalescer
+    coalescer: Option<WriteCoalescer>,
+    /// The UDP->serial write queue
+    queue: Option<WriteQueue>,
+    /// The write verifier
+    verifier: Option<Verifier>,
+    /// The serial output capture
+    capture: Option<Capture>,
+    /// The pcap capture of the bridged UDP datagrams
+    pcap: Option<PcapWriter>,
+    /// The control socket
+    control: Option<Control>,
+    /// The metrics endpoint
+    endpoint: Option<MetricsEndpoint>,
+    /// The server statistics
+    stats: Stats,
+    /// The serial->UDP rate counter
+    rate_rx: RateCounter,
+    /// The UDP->serial rate counter
+    rate_tx: RateCounter,
+    /// The serial read chunk size histogram
+    histogram_rx: ChunkHistogram,
+    /// The serial write chunk size histogram
+    histogram_tx: ChunkHistogram,
+    /// The round-trip time histogram of verified writes
+    latency_verify: LatencyHistogram,
+    /// The histogram of the intervals between inbound UDP datagrams
+    latency_inter_packet: LatencyHistogram,
+    /// The histogram of the delays from reading the serial output to sending it via UDP
+    latency_serial_to_udp: LatencyHistogram,
+    /// The histogram of the delays from receiving input to writing it to the serial device
+    latency_udp_to_serial: LatencyHistogram,
+    /// Whether forwarding is paused
+    paused: AtomicBool,
+    /// Whether the serial->UDP runloop should forward its partial chunk and stop
+    draining: AtomicBool,
+    /// Whether the serial->UDP runloop has stopped after draining
+    drained: Mutex<bool>,
+    /// Signals that the serial->UDP runloop has stopped after draining
+    drained_changed: Condvar,
+    /// The time of the most recently sent datagram in milliseconds since the server start
+    last_sent_ms: AtomicU64,
+    /// The server start time
+    started: Instant,
 }
 impl Server {
-    /// Creates a new server
-    pub fn new(config: Config) -> Result<Self, Error> {
-        // Setup socket
-        let socket = UdpSocket::bind(&config.udp.listen)?;
-        socket.set_ttl(config.udp.ttl)?;
+    /// The interval to check whether log deduplication has been enabled by a reload
+    const DEDUP_IDLE_INTERVAL: Duration = Duration::from_secs(1);
+    /// The settings besides `[log]` that are applied on reload without reopening the serial device
+    const RELOAD_LIVE: &'static [&'static str] =
+        &["udp.send", "udp.ttl", "udp.multicast_loop", "serial.allowed_baudrates", "serial.break_duration_ms"];
+    /// The serial port settings that are applied on reload by reopening the serial device
+    const RELOAD_REOPEN: &'static [&'static str] = &[
+        "serial.baudrate",
+        "serial.data_bits",
+        "serial.parity",
+        "serial.stop_bits",
+        "serial.flow_control",
+        "serial.flush_mode",
+        "serial.read_timeout_ms",
+        "serial.write_timeout_ms",
+        "serial.chmod",
+        "serial.chown",
+        "serial.initial_dtr",
+        "serial.initial_rts",
+        "serial.probe_write",
+        "serial.latency_timer",
+        "serial.report_latency_timer",
+        "serial.warmup",
+    ];
+
+    /// Creates a new server for the bridge with the given index
+    pub fn new(mut config: Config, bridge: usize) -> Result<Self, Error> {
+        // Check that the timestamp clock is supported so that timestamps never silently fall back to zero
+        (clock::now(config.timestamp_clock))
+            .map_err(|e| eio!("Unsupported timestamp clock ({e})").in_category(ErrorCategory::Config))?;
+
+        // Setup socket if UDP is enabled
+        let udp_error = |e: io::Error| Error::from(e).in_category(ErrorCategory::Udp);
+        let socket = match config.mode {
+            Mode::Udp if config.udp_to_serial => Some(UdpSocket::bind(&config.udp.listen).map_err(udp_error)?),
+            Mode::Udp | Mode::Tcp => None,
+        };
+
+        // Setup outgoing socket and destination
+        let sender = UdpSocket::bind(config.udp.bind_address()).map_err(udp_error)?;
+        if config.mode == Mode::Udp {
+            Self::set_send_ttl(&sender, &config.udp).map_err(udp_error)?;
+        }
+        if let Some(socket) = socket.as_ref() {
+            info!("Listening for UDP datagrams on {}", socket.local_addr().map_err(udp_error)?);
+        }
+        debug!("Sending UDP datagrams from {}", sender.local_addr().map_err(udp_error)?);
+
+        // Select the multicast interface and join the multicast groups
+        if let Some(multicast) = config.udp.multicast.as_ref() {
+            let interface = multicast.interface.unwrap_or(Ipv4Addr::UNSPECIFIED);
+            if multicast.interface.is_some() && sender.local_addr().map_err(udp_error)?.is_ipv4() {
+                multicast::set_interface_v4(&sender, interface).map_err(udp_error)?;
+            }
+            for group in multicast.groups.iter() {
+                // Note: IPv6 groups are joined on the default interface since `interface` is an IPv4 address
+                let socket = socket.as_ref().expect("Multicast groups require a listen socket");
+                let joined = match group {
+                    IpAddr::V4(group) => socket.join_multicast_v4(group, &interface),
+                    IpAddr::V6(group) => socket.join_multicast_v6(group, 0),
+                };
+                joined.map_err(|e| {
+                    eio!("Failed to join multicast group {group} ({e})").in_category(ErrorCategory::Udp)
+                })?;
+            }
+        }
+        let resolve_interval = config.udp.resolve_interval_ms.filter(|ms| *ms > 0).map(Duration::from_millis);
+        let adopt_after = config.udp.adopt_after_ms.map(Duration::from_millis);
+        let destination =
+            Destination::new(config.udp.send.as_deref(), resolve_interval, config.udp.adopt_after_packets, adopt_after);
+        let router = config.routing.as_ref().map(|routing| Router::new(routing, resolve_interval));
+        let allowed_sources = config.udp.allowed_sources.as_deref().filter(|sources| !sources.is_empty());
+        let allowlist = (allowed_sources.map(SourceAllowlist::new).transpose())
+            .map_err(|e| e.in_category(ErrorCategory::Config))?;
+        let max_buffer_age = config.udp.max_buffer_age_ms.map(Duration::from_millis);
+        let pending = Mutex::new(PendingBuffer::new(config.udp.pending_buffer, max_buffer_age));
 
         // Setup spipe and logger
-        let serial = SerialDevice::new(&config.serial.device, config.serial.baudrate)?;
-        let logger = config.log.enabled.then(Logger::new);
-        Ok(Self { config, socket, serial, logger })
+        let serial = Self::setup_serial(&mut config.serial).map_err(|e| e.in_category(ErrorCategory::Serial))?;
+        info!("Opened serial device {} at {} baud", config.serial.device, config.serial.baudrate);
+        let started = Instant::now();
+        let logging = Logging::new(&config.log, &config.serial.device, started, config.timestamp_clock)?.map(Arc::new);
+        let log_drops = AtomicBool::new(config.log.log_drops);
+
+        // Setup latency histograms
+        let latency_verify = LatencyHistogram::new(&config.metrics.latency_buckets_ms);
+        let latency_inter_packet = LatencyHistogram::new(&config.metrics.latency_buckets_ms);
+        let latency_serial_to_udp = LatencyHistogram::new(&config.metrics.latency_buckets_ms);
+        let latency_udp_to_serial = LatencyHistogram::new(&config.metrics.latency_buckets_ms);
+
+        // Setup translation tables
+        let translate_rx = Table::new(&config.translate.serial_to_udp)?;
+        let translate_tx = Table::new(&config.translate.udp_to_serial)?;
+
+        // Setup serial frame encoder
+        let encoder = Encoder::new(&config.serial);
+
+        // Setup datagram header
+        let header = config.udp.header.as_ref().map(|header| Header::new(header, config.timestamp_clock));
+
+        // Setup jitter buffer
+        let jitter = (config.jitter_buffer.as_ref())
+            .map(|jitter| JitterBuffer::new(Duration::from_millis(jitter.delay_ms), config.serial.baudrate));
+
+        // Setup write coalescer
+        let coalescer = (config.serial.udp_to_serial_coalesce_ms).map(|window| {
+            WriteCoalescer::new(Duration::from_millis(window), config.serial.udp_to_serial_coalesce_max_bytes)
+        });
+
+        // Setup write queue
+        let queue = (config.serial.udp_to_serial_queue_depth)
+            .map(|depth| WriteQueue::new(depth, config.serial.udp_to_serial_queue_overflow));
+
+        // Setup write verifier
+        let verifier = config.verify_writes.as_ref().map(|verify| {
+            let response = verify.response.as_ref().map(|response| response.as_bytes().to_vec());
+            Verifier::new(response, Duration::from_millis(verify.timeout_ms), verify.retries)
+        });
+
+        // Setup serial output and pcap capture
+        let capture = (config.capture.as_ref())
+            .and_then(|capture| capture.path.as_deref().map(|path| Capture::new(path, capture)))
+            .transpose()?;
+        let pcap = (config.capture.as_ref())
+            .and_then(|capture| {
+                capture.pcap.as_deref().map(|path| PcapWriter::new(path, capture, config.timestamp_clock))
+            })
+            .transpose()?;
+
+        // Setup control socket
+        let control = config.control.as_ref().map(Control::new).transpose()?;
+
+        // Setup the metrics endpoint
+        let endpoint = config.metrics.listen.as_deref().map(MetricsEndpoint::new).transpose()?;
+
+        // Setup the additional transports
+        let mut transports: Vec<Box<dyn Transport>> = Vec::new();
+        if let Some(tcp) = config.tcp.as_ref() {
+            transports.push(Box::new(TcpTransport::new(tcp)?));
+        }
+        let serial_config = RwLock::new(config.serial.clone());
+        Ok(Self {
+            config,
+            bridge,
+            socket,
+            sender,
+            destination,
+            router,
+            allowlist,
+            pending,
+            pending_flushed: Condvar::new(),
+            serial: SharedDevice::new(serial),
+            serial_config,
+            write_lock: Mutex::default(),
+            transports,
+            logging: RwLock::new(logging),
+            log_drops,
+            translate_rx,
+            translate_tx,
+            encoder,
+            header,
+            jitter,
+            coalescer,
+            queue,
+            verifier,
+            capture,
+            pcap,
+            control,
+            endpoint,
+            stats: Stats::default(),
+            rate_rx: RateCounter::default(),
+            rate_tx: RateCounter::default(),
+            histogram_rx: ChunkHistogram::default(),
+            histogram_tx: ChunkHistogram::default(),
+            latency_verify,
+            latency_inter_packet,
+            latency_serial_to_udp,
+            latency_udp_to_serial,
+            paused: AtomicBool::new(false),
+            draining: AtomicBool::new(false),
+            drained: Mutex::new(false),
+            drained_changed: Condvar::new(),
+            last_sent_ms: AtomicU64::new(0),
+            started,
+        })
+    }
+
+    /// Opens and sets up the serial device on startup, including the baudrate detection
+    fn setup_serial(config: &mut config::Serial) -> Result<SerialDevice, Error> {
+        let serial = SerialDevice::new(config)?;
+        Self::probe_write(&serial, config)?;
+        Self::setup_latency_timer(config)?;
+        if let Some(autobaud) = config.autobaud.as_ref() {
+            config.baudrate = Self::detect_baudrate(&serial, autobaud, config.baudrate)?;
+        }
+        if let Some(warmup) = config.warmup.as_ref() {
+            Self::write_warmup(&serial, warmup)?;
+        }
+        Ok(serial)
+    }
+    /// Sets and/or reports the latency timer of the serial device if configured
+    fn setup_latency_timer(config: &config::Serial) -> Result<(), Error> {
+        // Set the latency timer
+        if let Some(milliseconds) = config.latency_timer {
+            if !sysfs::set_latency_timer(&config.device, milliseconds)? {
+                warn!("{} has no latency timer attribute; ignoring latency timer", config.device);
+                return Ok(());
+            }
+        }
+
+        // Report the effective latency timer
+        if config.latency_timer.is_some() || config.report_latency_timer {
+            match sysfs::latency_timer(&config.device)? {
+                Some(milliseconds) => info!("Latency timer of {}: {milliseconds}ms", config.device),
+                None => warn!("{} has no latency timer attribute", config.device),
+            }
+        }
+        Ok(())
+    }
+
+    /// Detects the baudrate and returns the chosen baudrate, falling back to the configured baudrate
+    fn detect_baudrate(serial: &SerialDevice, autobaud: &config::Autobaud, fallback: u64) -> Result<u64, Error> {
+        let baudrate = match autobaud::detect(serial, autobaud)? {
+            Some(baudrate) => {
+                info!("Autobaud: chose baudrate {baudrate}");
+                baudrate
+            }
+            None => {
+                warn!("autobaud found no plausible baudrate; falling back to {fallback}");
+                fallback
+            }
+        };
+
+        // Apply the chosen baudrate
+        serial.set_baudrate(baudrate)?;
+        Ok(baudrate)
+    }
+    /// Writes the warm-up pattern to the serial device and waits until it has been transmitted
+    fn write_warmup(serial: &SerialDevice, warmup: &config::Warmup) -> Result<(), Error> {
+        let (mut serial, started, mut repetitions) = (serial.try_clone()?, Instant::now(), 0);
+        loop {
+            // Check whether the warm-up is complete
+            let complete = match (warmup.count, warmup.duration_ms) {
+                (Some(count), _) => repetitions >= count,
+                (None, Some(duration_ms)) => started.elapsed() >= Duration::from_millis(duration_ms),
+                (None, None) => true,
+            };
+            if complete {
+                break;
+            }
+
+            // Write the pattern
+            serial.write_all(warmup.pattern.as_bytes())?;
+            repetitions += 1;
+        }
+
+        // Wait until the pattern has been transmitted
+        serial.flush()?;
+        info!("Warm-up complete ({repetitions} repetitions in {}ms)", started.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Reopens the serial device after an I/O error if reconnecting is enabled, and returns the reopened device and
+    /// its generation
+    fn reconnect_serial(&self, generation: u64, error: Error) -> Result<(SerialDevice, u64), Error> {
+        // Propagate the error if reconnecting is disabled
+        if !self.config.reconnect.enabled {
+            return Err(error.in_category(ErrorCategory::Serial));
+        }
+
+        // Reopen the device
+        let reopened = self.serial.reopen(generation, &error, &self.config.reconnect, || self.open_serial());
+        self.stats.reconnect_cycles.store(self.serial.cycles(), Ordering::Relaxed);
+        match reopened? {
+            Some(reopened) => Ok(reopened),
+            None => {
+                // Give up so that a supervisor can escalate
+                let max_cycles = self.config.reconnect.max_cycles;
+                error!("reached the maximum of {max_cycles} reconnect cycles; giving up and shutting down");
+                self.shutdown(Reconnect::EXIT_CYCLES_EXHAUSTED)
+            }
+        }
+    }
+    /// Closes and reopens the serial device on request of the operator
+    ///
+    /// # Note
+    /// The device is reopened with the same settings, including the latency timer and warm-up, and the runloops switch
+    /// to the reopened device before their next I/O.
+    pub fn reopen_serial(&self) -> Result<(), Error> {
+        self.check_reopen()?;
+        self.serial.reopen_now(&self.config.reconnect, || self.open_serial())?;
+        Ok(())
+    }
+    /// Refuses to close the device if the sandbox would prevent reopening it
+    fn check_reopen(&self) -> Result<(), Error> {
+        if self.config.sandbox.as_ref().is_some_and(|sandbox| !sandbox.allow_open) {
+            return Err(eio!("The syscall sandbox does not allow reopening the serial device"));
+        }
+        Ok(())
+    }
+    /// Checks that the serial device is writable if appropriate so that misconfigurations surface before the first
+    /// datagram
+    fn probe_write(serial: &SerialDevice, config: &config::Serial) -> Result<(), Error> {
+        if config.probe_write {
+            (serial.probe_write())
+                .map_err(|e| eio!("Serial device {} is not writable ({})", config.device, e.to_string().trim_end()))?;
+        }
+        Ok(())
+    }
+    /// Validates the CPU indices against the available CPUs and pins the calling thread to them
+    fn setup_affinity(cpus: &[usize]) -> Result<(), Error> {
+        let count = affinity::cpu_count().map_err(|e| eio!("Failed to get the amount of CPUs ({e})"))?;
+        if let Some(cpu) = cpus.iter().find(|&&cpu| cpu >= count) {
+            return Err(eio!(
+                "Invalid CPU index {cpu} in `cpu_affinity` (this system has CPUs 0 to {})",
+                count.saturating_sub(1)
+            ));
+        }
+        affinity::apply(cpus).map_err(|e| eio!("Failed to set the CPU affinity {cpus:?} ({e})"))?;
+        info!("Pinned the server to CPUs {cpus:?}");
+        Ok(())
+    }
+    /// Applies the real-time scheduling policy to the calling serial thread if appropriate
+    fn setup_scheduling(&self) -> Result<(), Error> {
+        let Some(scheduling) = self.config.scheduling.as_ref() else {
+            return Ok(());
+        };
+        sched::apply(scheduling).map_err(|e| Self::scheduling_error(scheduling, e))
+    }
+    /// Creates a descriptive error for a failure to apply the real-time scheduling policy
+    fn scheduling_error(scheduling: &Scheduling, e: io::Error) -> Error {
+        let priority = scheduling.priority;
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => eio!(
+                "Failed to apply the real-time scheduling with priority {priority} since the process lacks the \
+                privileges; grant `CAP_SYS_NICE` (e.g. via `setcap cap_sys_nice+ep`), raise `RLIMIT_RTPRIO` or remove \
+                `[scheduling]`"
+            ),
+            _ => eio!("Failed to apply the real-time scheduling with priority {priority} ({e})"),
+        }
+    }
+    /// Opens and sets up the serial device for a reopen
+    fn open_serial(&self) -> Result<SerialDevice, Error> {
+        let config = self.serial_config.read().expect("Serial config is poisoned");
+        let serial = SerialDevice::new(&config)?;
+        Self::probe_write(&serial, &config)?;
+        Self::setup_latency_timer(&config)?;
+        if let Some(warmup) = config.warmup.as_ref() {
+            Self::write_warmup(&serial, warmup)?;
+        }
+        Ok(serial)
     }
 
-    /// Starts the server runloop
-    pub fn runloop(self) -> Result<(), Error> {
+    /// Starts the runloops of the servers of all bridges
+    ///
+    /// # Note
+    /// The process-wide settings are the same for all bridges, so they are taken from the first server. If a bridge
+    /// fails, the process exits.
+    pub fn runloop(servers: Vec<Self>) -> Result<(), Error> {
+        let config = &servers.first().ok_or(eio!("No bridge configured"))?.config;
+
+        // Block the handled signals before spawning any threads so that they are only seen by the signal thread
+        signal::block()?;
+
+        // Pin the process to the configured CPUs before spawning any threads so that all threads inherit the affinity
+        if let Some(cpus) = config.cpu_affinity.as_ref() {
+            Self::setup_affinity(cpus)?;
+        }
+
+        // Check the real-time scheduling before spawning any threads so that missing privileges fail early
+        if let Some(scheduling) = config.scheduling.as_ref() {
+            sched::check(scheduling).map_err(|e| Self::scheduling_error(scheduling, e))?;
+        }
+
+        // Enter the syscall sandbox before spawning any threads so that it applies to the whole process
+        #[cfg(feature = "sandbox")]
+        if let Some(sandbox) = config.sandbox.as_ref() {
+            sandbox::apply(sandbox.allow_open)?;
+            info!("Entered the syscall sandbox");
+        }
         thread::scope(|scope| -> Result<(), Error> {
-            // Clone serial port and spawn threads
-            let (serial_in, serial_out) = (self.serial.try_clone()?, self.serial.try_clone()?);
-            let serial2udp = scope.spawn(|| self.runloop_serial2udp(serial_in));
-            let udp2serial = scope.spawn(|| self.runloop_udp2serial(serial_out));
+            // Spawn the bridges
+            let mut bridges = Vec::with_capacity(servers.len());
+            for server in &servers {
+                bridges.push(scope.spawn(|| server.runloop_bridge(scope)));
+            }
 
-            // Wait for threads and propagate results
-            serial2udp.join().expect("Serial->UDP thread has panicked")?;
-            udp2serial.join().expect("UDP->serial thread has panicked")?;
+            // Spawn the signal thread
+            scope.spawn(|| Self::runloop_signals(&servers));
+
+            // Wait for a single bridge and propagate its result
+            if servers.len() == 1 {
+                let bridge = bridges.pop().expect("Bridge thread is missing");
+                return bridge.join().expect("Bridge thread has panicked");
+            }
+
+            // Wait for all bridges and exit once one has failed
+            for (bridge, server) in bridges.into_iter().zip(&servers) {
+                scope.spawn(move || {
+                    if let Err(e) = bridge.join().expect("Bridge thread has panicked") {
+                        error!("Bridge {} ({}) has failed: {e}", server.bridge, server.config.serial.device);
+                        process::exit(e.category().exit_code());
+                    }
+                });
+            }
             Ok(())
         })
     }
+    /// The runloops of a bridge
+    fn runloop_bridge<'scope>(&'scope self, scope: &'scope Scope<'scope, '_>) -> Result<(), Error> {
+        // Spawn threads
+        let serial2udp = self.config.serial_to_udp.then(|| scope.spawn(|| self.runloop_serial2udp()));
+        let udp2serial = (self.socket.as_ref()).map(|socket| scope.spawn(|| self.runloop_udp2serial(socket)));
+        if let Some(control) = self.control.as_ref() {
+            scope.spawn(|| control.runloop(scope, self));
+        }
+        if let Some(endpoint) = self.endpoint.as_ref() {
+            scope.spawn(|| endpoint.runloop(self));
+        }
+        for transport in &self.transports {
+            scope.spawn(|| self.runloop_transport(scope, transport.as_ref()));
+        }
+
+        // Spawn the jitter buffer thread if appropriate
+        let jitter = (self.jitter.as_ref()).map(|jitter| scope.spawn(|| self.runloop_jitter(jitter)));
+
+        // Spawn the write coalescing thread if appropriate
+        let coalescer = (self.coalescer.as_ref()).map(|coalescer| scope.spawn(|| self.runloop_coalesce(coalescer)));
+
+        // Spawn the write queue thread if appropriate
+        let queue = (self.queue.as_ref()).map(|queue| scope.spawn(|| self.runloop_queue(queue)));
+        debug!(
+            "Bridge {} started (serial->UDP: {}, UDP->serial: {}, jitter buffer: {}, coalescer: {}, write queue: {})",
+            self.bridge,
+            serial2udp.is_some(),
+            udp2serial.is_some(),
+            jitter.is_some(),
+            coalescer.is_some(),
+            queue.is_some()
+        );
+
+        // Spawn the resolver threads of the default destination and the route destinations
+        // Note: the output that has been buffered until the default destination is known is flushed once it resolves
+        scope.spawn(|| self.destination.runloop(|| self.flush_resolved()));
+        for destination in self.router.iter().flat_map(Router::destinations) {
+            scope.spawn(|| destination.runloop(|| ()));
+        }
+
+        // Spawn the log deduplication thread
+        // Note: deduplication may be enabled by a reload, so the thread is always needed
+        scope.spawn(|| self.runloop_dedup());
+
+        // Spawn the rate logging thread if appropriate
+        if self.config.log.rate_interval_ms.is_some() {
+            scope.spawn(|| self.runloop_rate());
+        }
+
+        // Spawn the chunk histogram thread if appropriate
+        if self.config.metrics.chunk_histogram {
+            scope.spawn(|| self.runloop_histogram());
+        }
+
+        // Spawn the status file thread if appropriate
+        if let Some(status) = self.config.status.as_ref() {
+            scope.spawn(|| self.runloop_status_file(status));
+        }
+
+        // Spawn the heartbeat thread if appropriate
+        if let Some(heartbeat) = self.config.udp.heartbeat.as_ref() {
+            scope.spawn(|| self.runloop_heartbeat(heartbeat));
+        }
+
+        // Spawn the periodic flush thread if appropriate
+        if self.config.serial.flush_interval_ms.is_some() {
+            scope.spawn(|| self.runloop_flush());
+        }
+
+        // Spawn the error count thread if appropriate
+        if self.config.serial.error_counts_interval_ms.is_some() {
+            scope.spawn(|| self.runloop_error_counts());
+        }
+
+        // Wait for threads and propagate results
+        if let Some(serial2udp) = serial2udp {
+            serial2udp.join().expect("Serial->UDP thread has panicked")?;
+        }
+        if let Some(udp2serial) = udp2serial {
+            udp2serial.join().expect("UDP->serial thread has panicked")?;
+        }
+        if let Some(jitter) = jitter {
+            jitter.join().expect("Jitter buffer thread has panicked")?;
+        }
+        if let Some(coalescer) = coalescer {
+            coalescer.join().expect("Write coalescing thread has panicked")?;
+        }
+        if let Some(queue) = queue {
+            queue.join().expect("Write queue thread has panicked")?;
+        }
+        Ok(())
+    }
     /// The serial->UDP runloop
-    fn runloop_serial2udp(&self, mut serial: SerialDevice) -> Result<(), Error> {
-        // Unwrap and resolve the remote address
-        let address = (self.config.udp.send.as_ref())
-            .map(|address| address.to_socket_addrs())
-            .transpose()?
-            .and_then(|mut addresses| addresses.next());
-
-        // The `socket::send_to` implementation *if there is a remote address configured*
-        let socket_send_to = {
-            // Create the socket
-            let socket = UdpSocket::bind("0.0.0.0:0")?;
-            socket.set_ttl(self.config.udp.ttl)?;
-
-            // Create the closure
-            move |buf: &[u8]| -> io::Result<usize> {
-                // Send UDP packet if a multicast address is defined or perform a no-op
-                match address.as_ref() {
-                    Some(multicast) => socket.send_to(buf, multicast),
-                    None => Ok(buf.len()),
-                }
-            }
+    fn runloop_serial2udp(&self) -> Result<(), Error> {
+        self.setup_scheduling()?;
+        let (mut serial, mut generation) = self.serial.get()?;
+        let mut buf = vec![0; self.config.buffers.serial_buffer];
+        let coalesce = self.config.serial.coalesce_ms.filter(|coalesce_ms| *coalesce_ms > 0).map(Duration::from_millis);
+        let final_idle = coalesce.or(self.config.serial.final_idle_ms.map(Duration::from_millis));
+        let marker = self.config.serial.incomplete_marker.as_deref().unwrap_or_default().as_bytes();
+        let terminator = match self.config.serial.framing {
+            Framing::Line => Terminator::Delimiter(self.config.serial.delimiter.as_bytes()),
+            // Note: coalesced output is only split once the buffer is full or the line is idle
+            Framing::Raw if coalesce.is_some() => Terminator::Full,
+            Framing::Raw | Framing::LengthPrefixed => Terminator::Newline,
         };
+        let mut deframer = Deframer::new(&self.config.serial);
+        if let Some(config) = self.config.udp.header.as_ref() {
+            // Limit the chunk size so that the datagrams including the incomplete marker do not exceed the MTU
+            buf.truncate(config.mtu - config.size() - marker.len());
+        }
+        if let Some(read_hint_bytes) = self.config.serial.read_hint_bytes {
+            // Size the reads to the expected frame size; note: larger bursts are simply split across multiple reads
+            buf.truncate(read_hint_bytes);
+        }
+
+        // Suppress the output until the start trigger has been seen if appropriate
+        let mut start_trigger = self.config.serial.start_trigger.as_ref().map(StartTrigger::new);
+        let mut stop_trigger = self.config.serial.stop_trigger.as_ref().map(StopTrigger::new);
+
+        // Reserve the maximum capacity of the marker and header buffers up front so that they are never reallocated
+        let (mut marked_buf, mut datagram_buf) = self.datagram_buffers(buf.len(), marker);
 
-        // Send the packets
-        let mut buf = vec![0; 400];
         loop {
-            // Receive serial chunk
-            let bytes_read = serial.read(&mut buf)?;
+            // Stop once the partial chunk has been forwarded on shutdown
+            if self.draining.load(Ordering::Relaxed) {
+                *self.drained.lock().expect("Drain state is poisoned") = true;
+                self.drained_changed.notify_all();
+                return Ok(());
+            }
+
+            // Switch to the current device if it has been reopened on request
+            let previous_generation = generation;
+            self.serial.refresh(&mut serial, &mut generation)?;
+            if let Some(deframer) = deframer.as_mut().filter(|_| generation != previous_generation) {
+                deframer.reset(|reason, data| self.log_drop(reason, data));
+            }
+
+            // Receive serial chunk or frame
+            let read = match deframer.as_mut() {
+                Some(deframer) => (deframer.read_frame(
+                    &mut serial,
+                    &mut buf,
+                    &self.draining,
+                    |raw| self.observe_raw(raw),
+                    |reason, data| self.log_drop(reason, data),
+                ))
+                .map(|bytes_read| (bytes_read, true)),
+                None => (serial.read_chunk(&mut buf, terminator, final_idle, &self.draining))
+                    .inspect(|(bytes_read, _)| self.observe_raw(&buf[..*bytes_read])),
+            };
+            let (bytes_read, complete) = match read {
+                // Note: coalesced output is complete once the line is idle
+                Ok((bytes_read, complete)) => (bytes_read, complete || coalesce.is_some()),
+                Err(e) => match Error::from(e) {
+                    // Note: the partial chunk is lost if the read is interrupted
+                    e if e.kind() == ErrorKind::Transient => continue,
+                    e => {
+                        (serial, generation) = self.reconnect_serial(generation, e)?;
+                        if let Some(deframer) = deframer.as_mut() {
+                            deframer.reset(|reason, data| self.log_drop(reason, data));
+                        }
+                        continue;
+                    }
+                },
+            };
+            let read_at = Instant::now();
             if bytes_read > 0 {
-                // Send the message to the multicast address if a multicast
-                socket_send_to(&buf[..bytes_read])?;
-                self.log(&buf[..bytes_read]);
+                self.histogram_rx.record(bytes_read);
+
+                // Discard the chunk if forwarding is paused
+                if self.paused.load(Ordering::Relaxed) {
+                    self.log_drop("paused", &buf[..bytes_read]);
+                    continue;
+                }
+
+                // Discard the output before the start trigger
+                let chunk = match start_trigger.as_mut().filter(|start_trigger| !start_trigger.seen()) {
+                    Some(start_trigger) => match start_trigger
+                        .scan(&buf[..bytes_read], |discarded| self.log_drop("before start trigger", discarded))
+                    {
+                        Some(chunk) => {
+                            info!("Start trigger seen; forwarding the serial output");
+                            chunk
+                        }
+                        None => continue,
+                    },
+                    None => &mut buf[..bytes_read],
+                };
+
+                // Discard the output after the stop trigger
+                let stop_len = stop_trigger.as_mut().and_then(|stop_trigger| stop_trigger.scan(chunk));
+                let chunk = match stop_len {
+                    Some(stop_len) => {
+                        self.log_drop("after stop trigger", &chunk[stop_len..]);
+                        &mut chunk[..stop_len]
+                    }
+                    None => chunk,
+                };
+
+                // Translate the chunk if necessary
+                if let Some(translate) = self.translate_rx.as_ref() {
+                    translate.apply(chunk);
+                }
+
+                // Mark partial chunks if appropriate
+                let chunk = match complete || marker.is_empty() {
+                    true => &*chunk,
+                    false => Self::mark_incomplete(chunk, marker, &mut marked_buf),
+                };
+
+                // Prepend the header and send the message to the destination
+                let datagram = match self.header.as_ref() {
+                    Some(header) => header.encode(chunk, &mut datagram_buf),
+                    None => chunk,
+                };
+                let result = match self.router.as_ref().map(|router| router.route(chunk)) {
+                    Some(Route::Rule(destination)) => self.send_routed(datagram, destination, read_at),
+                    Some(Route::Drop) => {
+                        self.log_drop("no matching route", chunk);
+                        Ok(())
+                    }
+                    Some(Route::Default) | None => self.send(datagram, read_at),
+                };
+                match result {
+                    Err(e) if self.transports.is_empty() => return Err(e),
+                    Err(e) => warn!("failed to send via UDP ({})", e.to_string().trim_end()),
+                    Ok(_) => (),
+                }
+
+                // Fan the message out to the additional transports
+                for transport in &self.transports {
+                    if let Err(e) = transport.send(chunk) {
+                        warn!("failed to send via {} ({})", transport.name(), e.to_string().trim_end());
+                    }
+                }
+                self.monitor(Direction::SerialToUdp, chunk);
+
+                // Perform the stop action if appropriate
+                if stop_len.is_some() {
+                    self.stop();
+                }
             }
         }
     }
+    /// Passes raw serial output to the write verifier and the capture
+    fn observe_raw(&self, raw: &[u8]) {
+        if raw.is_empty() {
+            return;
+        }
+
+        // Pass the raw output to the write verifier
+        if let Some(verifier) = self.verifier.as_ref() {
+            verifier.observe(raw);
+        }
+
+        // Capture the raw output
+        if let Some(capture) = self.capture.as_ref() {
+            capture.record(raw);
+        }
+    }
+    /// Performs the configured stop action after the stop trigger has been seen
+    fn stop(&self) {
+        match self.config.serial.stop_trigger.as_ref().map(|stop_trigger| stop_trigger.action) {
+            Some(StopAction::Pause) => {
+                info!("Stop trigger seen; pausing the forwarding");
+                self.set_paused(true);
+            }
+            Some(StopAction::Shutdown) => {
+                // Note: the serial->UDP runloop has already forwarded everything up to the trigger and cannot wait for
+                // itself to drain
+                info!("Stop trigger seen; shutting down");
+                *self.drained.lock().expect("Drain state is poisoned") = true;
+                self.shutdown(0)
+            }
+            None => (),
+        }
+    }
     /// The UDP->serial runloop
-    fn runloop_udp2serial(&self, mut serial: SerialDevice) -> Result<(), Error> {
-        let mut buf = vec![0; 4000];
+    fn runloop_udp2serial(&self, socket: &UdpSocket) -> Result<(), Error> {
+        self.setup_scheduling()?;
+        let (mut serial, mut generation) = self.serial.get()?;
+        let udp_buffer = self.config.buffers.udp_buffer;
+        let (mut frame_buf, mut last_arrival) = (self.frame_buffer(udp_buffer), None);
+
+        // Receive batches of datagrams if appropriate
+        // Note: the receive buffers have one spare byte so that truncated datagrams can be detected by their length
+        if self.config.udp.recv_batch > 1 {
+            let mut batch = BatchReceiver::new(self.config.udp.recv_batch, udp_buffer + 1);
+            loop {
+                // Receive the batch
+                let received = match batch.recv(socket) {
+                    Ok(received) => received,
+                    Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                        warn!("batch receiving is not supported; falling back to single datagrams");
+                        break;
+                    }
+                    Err(e) => match Error::from(e) {
+                        e if e.kind() == ErrorKind::Transient => continue,
+                        e => return Err(e.in_category(ErrorCategory::Udp)),
+                    },
+                };
+
+                // Process the datagrams in order
+                self.stats.udp_recv_batch_size.store(received as u64, Ordering::Relaxed);
+                self.observe_arrival(&mut last_arrival, received);
+                for slot in 0..received {
+                    let (datagram, sender) = batch.get_mut(slot);
+                    self.process_datagram(datagram, sender, &mut serial, &mut generation, &mut frame_buf)?;
+                }
+            }
+        }
+
+        // Receive single datagrams
+        let mut buf = vec![0; udp_buffer + 1];
         loop {
-            // Receive UDP packet
-            let bytes_read = self.socket.recv(&mut buf)?;
-            if bytes_read > 0 {
-                // Write the message to the serial device
-                serial.write_all(&buf[..bytes_read])?;
-                self.log(&buf[..bytes_read]);
+            let (bytes_read, sender) = match socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(e) => match Error::from(e) {
+                    e if e.kind() == ErrorKind::Transient => continue,
+                    e => return Err(e.in_category(ErrorCategory::Udp)),
+                },
+            };
+            self.observe_arrival(&mut last_arrival, 1);
+            self.process_datagram(&mut buf[..bytes_read], sender, &mut serial, &mut generation, &mut frame_buf)?;
+        }
+    }
+    /// Records the intervals between inbound datagrams for a batch of `received` datagrams that arrived at once
+    fn observe_arrival(&self, last_arrival: &mut Option<Instant>, received: usize) {
+        let now = Instant::now();
+        if let Some(last_arrival) = last_arrival.replace(now) {
+            self.latency_inter_packet.observe(now - last_arrival);
+        }
+        for _ in 1..received {
+            self.latency_inter_packet.observe(Duration::ZERO);
+        }
+    }
+    /// Processes a received datagram and writes its payload to the serial device or the jitter buffer
+    fn process_datagram(
+        &self,
+        datagram: &mut [u8],
+        sender: SocketAddr,
+        serial: &mut SerialDevice,
+        generation: &mut u64,
+        frame_buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        // Pass the datagram to the pcap capture if appropriate
+        if let (Some(pcap), Some(socket)) = (self.pcap.as_ref(), self.socket.as_ref()) {
+            pcap.record(sender, socket.local_addr()?, datagram);
+        }
+
+        // Drop datagrams from sources that are not allowed before they can be adopted
+        if self.allowlist.as_ref().is_some_and(|allowlist| !allowlist.allows(sender.ip())) {
+            self.stats.udp_rejected_datagrams.fetch_add(1, Ordering::Relaxed);
+            self.log_drop("source not allowed", datagram);
+            return Ok(());
+        }
+
+        // Drop truncated and oversized datagrams instead of forwarding partial or link-monopolizing data
+        let udp_buffer = self.config.buffers.udp_buffer;
+        if datagram.len() > udp_buffer {
+            warn!("dropping truncated datagram from {sender} (exceeds the buffer of {udp_buffer} bytes)");
+            self.stats.udp_truncated_datagrams.fetch_add(1, Ordering::Relaxed);
+            self.log_drop("truncated datagram", &datagram[..udp_buffer]);
+            return Ok(());
+        }
+        if self.config.udp.max_packet_size.is_some_and(|max_packet_size| datagram.len() > max_packet_size) {
+            self.stats.udp_oversized_datagrams.fetch_add(1, Ordering::Relaxed);
+            self.log_drop("oversized datagram", datagram);
+            return Ok(());
+        }
+
+        // Adopt the sender if appropriate
+        if self.destination.adopt(sender) {
+            // Flush the output that has been buffered until now
+            self.flush_pending()?;
+        }
+
+        if self.paused.load(Ordering::Relaxed) {
+            self.log_drop("paused", datagram);
+            return Ok(());
+        }
+
+        // Strip the header if appropriate
+        let payload = match self.header.as_ref().map(|header| header.decode(datagram)) {
+            None => 0..datagram.len(),
+            Some(Ok(payload)) => datagram.len() - payload.len()..datagram.len(),
+            Some(Err(e)) => {
+                warn!("dropping datagram from {sender} ({})", e.to_string().trim_end());
+                self.log_drop("invalid header", datagram);
+                return Ok(());
+            }
+        };
+
+        // Transmit a break instead of writing the break datagram
+        let break_datagram = self.config.udp.break_datagram.as_ref();
+        if break_datagram.is_some_and(|pattern| datagram[payload.clone()] == *pattern.as_bytes()) {
+            if let Err(e) = self.send_break() {
+                warn!("failed to send a break ({})", e.to_string().trim_end());
             }
+            return Ok(());
         }
+
+        // Skip empty payloads
+        // Note: an empty datagram is a legitimate packet, e.g. a keep-alive that has already adopted the sender above,
+        // and not an end of stream, so it is only counted and logged
+        if payload.is_empty() {
+            self.stats.udp_empty_datagrams.fetch_add(1, Ordering::Relaxed);
+            if let Some(logging) = self.logging() {
+                logging.logger.note(&format!("empty datagram from {sender}"));
+            }
+            return Ok(());
+        }
+        self.forward_to_serial(&mut datagram[payload], serial, generation, frame_buf)
+    }
+    /// Transmits a break condition of the configured duration once the pending serial output has been transmitted
+    pub fn send_break(&self) -> Result<(), Error> {
+        let (serial, _) = self.serial.get()?;
+        let _write_lock = self.write_lock.lock().expect("Serial write lock is poisoned");
+        let duration = self.serial_config.read().expect("Serial config is poisoned").break_duration_ms;
+        serial.send_break(Duration::from_millis(duration))?;
+        self.stats.serial_breaks.fetch_add(1, Ordering::Relaxed);
+        Ok(())
     }
+    /// Translates a message and writes it to the serial device or passes it to the jitter buffer
+    fn forward_to_serial(
+        &self,
+        message: &mut [u8],
+        serial: &mut SerialDevice,
+        generation: &mut u64,
+        frame_buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        // Translate the message if necessary
+        let arrival = Instant::now();
+        if let Some(translate) = self.translate_tx.as_ref() {
+            translate.apply(message);
+        }
+
+        // Write the message to the serial device or pass it to the jitter buffer, the write coalescer or the write
+        // queue
+        match (self.jitter.as_ref(), self.coalescer.as_ref(), self.queue.as_ref()) {
+            (Some(jitter), _, _) => {
+                jitter.push(message);
+                self.stats.jitter_datagrams.fetch_add(1, Ordering::Relaxed);
+                self.stats.jitter_bytes.fetch_add(message.len() as u64, Ordering::Relaxed);
+            }
+            (None, Some(coalescer), _) => {
+                let frame = self.encoder.as_ref().map(|encoder| encoder.encode(message, frame_buf));
+                match frame {
+                    Some(Some(frame)) => coalescer.push(frame),
+                    Some(None) => self.log_drop("too long for the length prefix", message),
+                    None => coalescer.push(message),
+                }
+            }
+            (None, None, Some(queue)) => {
+                self.stats.udp_to_serial_queued.fetch_add(1, Ordering::Relaxed);
+                queue.push(message, |dropped| {
+                    self.stats.udp_to_serial_queued.fetch_sub(1, Ordering::Relaxed);
+                    self.stats.udp_to_serial_queue_dropped.fetch_add(1, Ordering::Relaxed);
+                    self.log_drop("write queue full", dropped);
+                });
+            }
+            (None, None, None) => {
+                self.write_serial_reconnecting(serial, generation, message, frame_buf)?;
+                self.latency_udp_to_serial.observe(arrival.elapsed());
+            }
+        }
+        Ok(())
+    }
+    /// The runloop of an additional transport which keeps the other transports running if the transport fails
+    ///
+    /// # Note
+    /// In TCP mode, the TCP transport is the only transport, so the server exits if it fails.
+    fn runloop_transport<'scope>(&'scope self, scope: &'scope Scope<'scope, '_>, transport: &'scope dyn Transport) {
+        if let Err(e) = transport.runloop(scope, self) {
+            let name = transport.name();
+            if self.config.mode == Mode::Tcp {
+                error!("{name} has failed ({})", e.to_string().trim_end());
+                process::exit(e.category().exit_code());
+            }
+            warn!("{name} has failed ({}); continuing without it", e.to_string().trim_end());
+        }
+    }
+    /// Creates a frame buffer for [`Server::write_input`] that holds the frames of messages up to `max_len` bytes
+    /// without reallocating
+    pub fn frame_buffer(&self, max_len: usize) -> Vec<u8> {
+        self.encoder.as_ref().map(|encoder| encoder.buffer(max_len)).unwrap_or_default()
+    }
+    /// Writes the input of an additional transport to the serial device, using `frame_buf` as scratch buffer
+    ///
+    /// # Note
+    /// Serial errors are fatal like for UDP input, so the process exits if the device cannot be written or reopened.
+    pub fn write_input(&self, message: &mut [u8], frame_buf: &mut Vec<u8>) {
+        // Skip empty messages
+        // Note: like an empty datagram, an empty message has nothing to write and is not dropped by a policy
+        if message.is_empty() {
+            return;
+        }
+
+        // Discard the message if forwarding is paused or disabled
+        if self.paused.load(Ordering::Relaxed) {
+            self.log_drop("paused", message);
+            return;
+        }
+        if !self.config.udp_to_serial {
+            self.log_drop("UDP->serial disabled", message);
+            return;
+        }
+
+        // Forward the message
+        let result = self.serial.get().and_then(|(mut serial, mut generation)| {
+            self.forward_to_serial(message, &mut serial, &mut generation, frame_buf)
+        });
+        if let Err(e) = result {
+            error!("{}", e.to_string().trim_end());
+            process::exit(e.category().exit_code());
+        }
+    }
+    /// The jitter buffer runloop which writes the buffered datagrams at a rate matched to the baudrate
+    fn runloop_jitter(&self, jitter: &JitterBuffer) -> Result<(), Error> {
+        self.setup_scheduling()?;
+        let (mut serial, mut generation) = self.serial.get()?;
+        let mut frame_buf = self.frame_buffer(self.config.buffers.udp_buffer.max(TcpTransport::BUF_SIZE));
+        loop {
+            // Get the next due datagram and drop it if it has exceeded the maximum delay
+            let (arrival, datagram) = jitter.pop();
+            self.stats.jitter_datagrams.fetch_sub(1, Ordering::Relaxed);
+            self.stats.jitter_bytes.fetch_sub(datagram.len() as u64, Ordering::Relaxed);
+            if self.is_stale(arrival, &datagram) {
+                continue;
+            }
+
+            // Write the datagram and wait until it has been transmitted at the configured baudrate
+            let started = Instant::now();
+            self.write_serial_reconnecting(&mut serial, &mut generation, &datagram, &mut frame_buf)?;
+            self.latency_udp_to_serial.observe(arrival.elapsed());
+            let remaining = jitter.transmit_time(datagram.len()).saturating_sub(started.elapsed());
+            thread::sleep(remaining);
+        }
+    }
+    /// The write coalescing runloop which writes the accumulated frames with a single write and flush
+    fn runloop_coalesce(&self, coalescer: &WriteCoalescer) -> Result<(), Error> {
+        self.setup_scheduling()?;
+        let (mut serial, mut generation) = self.serial.get()?;
+        let mut batch = Vec::new();
+        loop {
+            // Get the accumulated frames and drop them if the oldest one has exceeded the maximum delay
+            let oldest = coalescer.pop(&mut batch);
+            if self.is_stale(oldest, &batch) {
+                continue;
+            }
+
+            // Write the frames and retry them with the reopened device after an I/O error if reconnecting is enabled
+            self.serial.refresh(&mut serial, &mut generation)?;
+            while let Err(e) = self.write_batch(&mut serial, &batch) {
+                (serial, generation) = self.reconnect_serial(generation, e)?;
+            }
+            self.latency_udp_to_serial.observe(oldest.elapsed());
+        }
+    }
+    /// The write queue runloop which writes the queued datagrams in order
+    fn runloop_queue(&self, queue: &WriteQueue) -> Result<(), Error> {
+        self.setup_scheduling()?;
+        let (mut serial, mut generation) = self.serial.get()?;
+        let mut frame_buf = self.frame_buffer(self.config.buffers.udp_buffer.max(TcpTransport::BUF_SIZE));
+        loop {
+            // Get the next datagram and drop it if it has exceeded the maximum delay
+            let (arrival, datagram) = queue.pop();
+            self.stats.udp_to_serial_queued.fetch_sub(1, Ordering::Relaxed);
+            if self.is_stale(arrival, &datagram) {
+                continue;
+            }
+
+            // Write the datagram
+            self.write_serial_reconnecting(&mut serial, &mut generation, &datagram, &mut frame_buf)?;
+            self.latency_udp_to_serial.observe(arrival.elapsed());
+        }
+    }
+    /// Checks whether buffered input that has arrived at `arrival` has exceeded the maximum UDP->serial delay and
+    /// drops it if appropriate
+    fn is_stale(&self, arrival: Instant, data: &[u8]) -> bool {
+        let max_delay = self.config.serial.udp_to_serial_max_delay_ms.map(Duration::from_millis);
+        if max_delay.is_some_and(|max_delay| arrival.elapsed() > max_delay) {
+            self.log_drop("maximum delay exceeded", data);
+            self.stats.udp_to_serial_expired_writes.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+    /// The log deduplication runloop which periodically reports the expired repetition counts
+    fn runloop_dedup(&self) {
+        loop {
+            // Report the expired repetitions of the current deduplicator
+            // Note: if deduplication is disabled, check again later in case it is enabled by a reload
+            let logging = self.logging();
+            match logging.as_deref() {
+                Some(Logging { logger, dedup: Some(dedup) }) => {
+                    thread::sleep(dedup.timeout());
+                    dedup.expire(logger);
+                }
+                _ => thread::sleep(Self::DEDUP_IDLE_INTERVAL),
+            }
+        }
+    }
+    /// The rate logging runloop which periodically logs the data rates over the last interval
+    fn runloop_rate(&self) {
+        let interval_ms = self.config.log.rate_interval_ms.unwrap_or_default();
+        let (interval, mut window_start) = (Duration::from_millis(interval_ms), Instant::now());
+        loop {
+            // Wait for the window to elapse
+            thread::sleep(interval);
+            let elapsed = window_start.elapsed();
+            window_start = Instant::now();
+
+            // Log the rates
+            let (rx, tx) = (self.rate_rx.take(elapsed), self.rate_tx.take(elapsed));
+            info!("Rate: RX {rx}, TX {tx}");
+        }
+    }
+    /// The status file runloop which periodically rewrites the status file
+    fn runloop_status_file(&self, status: &config::Status) {
+        let (interval, mut failing) = (Duration::from_millis(status.interval_ms), false);
+        loop {
+            // Write the status file and warn once per failure streak
+            match self.write_status_file(&status.file) {
+                Ok(_) => failing = false,
+                Err(e) if !failing => {
+                    warn!("failed to write the status file {} ({})", status.file, e.to_string().trim_end());
+                    failing = true;
+                }
+                Err(_) => (),
+            }
+            thread::sleep(interval);
+        }
+    }
+    /// Writes the status document to a temporary file and renames it so that readers never see a partial document
+    fn write_status_file(&self, path: &str) -> Result<(), Error> {
+        let temp_path = format!("{path}.tmp");
+        fs::write(&temp_path, self.status_json()? + "\n")?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+    /// The chunk histogram runloop which periodically logs the chunk sizes over the last interval
+    fn runloop_histogram(&self) {
+        let interval = Duration::from_millis(self.config.metrics.interval_ms);
+        loop {
+            // Wait for the window to elapse
+            thread::sleep(interval);
+
+            // Log the histograms if there was any traffic
+            let (rx, tx) = (self.histogram_rx.take(), self.histogram_tx.take());
+            if !rx.is_empty() || !tx.is_empty() {
+                info!("Chunk sizes: RX {rx}, TX {tx}");
+            }
+        }
+    }
+    /// The periodic flush runloop which bounds the time the output may sit in the kernel's output buffer
+    fn runloop_flush(&self) {
+        let interval_ms = self.config.serial.flush_interval_ms.unwrap_or_default();
+        let (interval, mut failing) = (Duration::from_millis(interval_ms), false);
+        loop {
+            thread::sleep(interval);
+
+            // Flush the device and warn once per failure streak
+            // Note: the device is not available while it is being reopened, so the flush is retried after the interval
+            let Some(mut serial) = self.serial.try_get() else {
+                continue;
+            };
+            match serial.flush() {
+                Err(e) if !failing => {
+                    warn!("failed to flush the serial device ({e})");
+                    failing = true;
+                }
+                Err(_) => (),
+                Ok(_) => failing = false,
+            }
+        }
+    }
+    /// The heartbeat runloop which sends the heartbeat payload whenever no datagram has been sent for the interval
+    ///
+    /// # Note
+    /// The heartbeat is sent as-is without the datagram header, and is skipped while forwarding is paused or the send
+    /// destination is not known yet.
+    fn runloop_heartbeat(&self, heartbeat: &config::Heartbeat) {
+        let (interval, mut failing) = (Duration::from_millis(heartbeat.interval_ms), false);
+        loop {
+            // Wait until the interval has elapsed since the most recently sent datagram
+            let last_sent = Duration::from_millis(self.last_sent_ms.load(Ordering::Relaxed));
+            let idle = self.started.elapsed().saturating_sub(last_sent);
+            if idle < interval {
+                thread::sleep(interval - idle);
+                continue;
+            }
+
+            // Send the heartbeat and warn once per failure streak
+            let destination = self.destination.get().filter(|_| !self.paused.load(Ordering::Relaxed));
+            let Some(address) = destination else {
+                thread::sleep(interval);
+                continue;
+            };
+            let payload = heartbeat.payload.as_bytes();
+            match self.send_to(payload, address) {
+                Err(e) if !failing => {
+                    warn!("failed to send the heartbeat ({})", e.to_string().trim_end());
+                    failing = true;
+                    thread::sleep(interval);
+                }
+                Err(_) => thread::sleep(interval),
+                Ok(_) => {
+                    failing = false;
+                    self.stats.udp_heartbeats.fetch_add(1, Ordering::Relaxed);
+                    if let Some(logging) = self.logging() {
+                        logging.logger.note(&format!("heartbeat: {} bytes", payload.len()));
+                    }
+                }
+            }
+        }
+    }
+    /// The error count runloop which periodically polls the serial driver's error counts
+    fn runloop_error_counts(&self) {
+        let interval_ms = self.config.serial.error_counts_interval_ms.unwrap_or_default();
+        let (interval, mut previous) = (Duration::from_millis(interval_ms), None);
+        loop {
+            // Get the error counts
+            let counts = match self.serial.get().and_then(|(serial, _)| self.update_error_counts(&serial)) {
+                Ok(counts) => counts,
+                Err(e) => {
+                    warn!("failed to get the serial error counts ({}); disabling", e.to_string().trim_end());
+                    return;
+                }
+            };
+
+            // Log the error counts if they have changed
+            if previous != Some(counts) {
+                info!("Serial error counts: {counts}");
+                previous = Some(counts);
+            }
+            thread::sleep(interval);
+        }
+    }
+    /// Gets the serial driver's error counts and updates the statistics
+    fn update_error_counts(&self, serial: &SerialDevice) -> Result<SerialErrorCounts, Error> {
+        let counts = serial.error_counts()?;
+        self.stats.serial_frame_errors.store(counts.frame, Ordering::Relaxed);
+        self.stats.serial_parity_errors.store(counts.parity, Ordering::Relaxed);
+        self.stats.serial_overruns.store(counts.overrun, Ordering::Relaxed);
+        self.stats.serial_buffer_overruns.store(counts.buffer_overrun, Ordering::Relaxed);
+        Ok(counts)
+    }
+
+    /// Writes a datagram via [`Self::write_serial`] and retries it with the reopened device after an I/O error if
+    /// reconnecting is enabled
+    fn write_serial_reconnecting(
+        &self,
+        serial: &mut SerialDevice,
+        generation: &mut u64,
+        datagram: &[u8],
+        frame_buf: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        // Switch to the current device if it has been reopened on request
+        self.serial.refresh(serial, generation)?;
+        while let Err(e) = self.write_serial(serial, datagram, frame_buf) {
+            (*serial, *generation) = self.reconnect_serial(*generation, e)?;
+        }
+        Ok(())
+    }
+    /// Encodes a datagram, writes it to the serial device and verifies it if appropriate
+    fn write_serial(&self, serial: &mut SerialDevice, datagram: &[u8], frame_buf: &mut Vec<u8>) -> Result<(), Error> {
+        // Encode the message if appropriate
+        let frame = match self.encoder.as_ref().map(|encoder| encoder.encode(datagram, frame_buf)) {
+            Some(Some(frame)) => frame,
+            Some(None) => {
+                self.log_drop("too long for the length prefix", datagram);
+                return Ok(());
+            }
+            None => datagram,
+        };
+
+        // Write the message to the serial device and verify it if appropriate
+        let _write_lock = self.write_lock.lock().expect("Serial write lock is poisoned");
+        match self.verifier.as_ref() {
+            Some(verifier) => {
+                if let Some(rtt) = verifier.write(frame, |data| serial.write_all(data))? {
+                    self.latency_verify.observe(rtt);
+                }
+            }
+            None => serial.write_all(frame)?,
+        }
+        self.histogram_tx.record(frame.len());
+        self.monitor(Direction::UdpToSerial, frame);
+        Ok(())
+    }
+
+    /// Writes already encoded frames to the serial device and flushes it
+    fn write_batch(&self, serial: &mut SerialDevice, batch: &[u8]) -> Result<(), Error> {
+        let _write_lock = self.write_lock.lock().expect("Serial write lock is poisoned");
+        serial.write_all(batch)?;
+        serial.flush()?;
+        self.histogram_tx.record(batch.len());
+        self.monitor(Direction::UdpToSerial, batch);
+        Ok(())
+    }
+
+    /// The signal runloop which reopens the serial devices on request and shuts the servers down on a termination
+    /// signal
+    fn runloop_signals(servers: &[Self]) -> Result<(), Error> {
+        loop {
+            // Wait for the next signal
+            match signal::wait()? {
+                Signal::Reopen => {
+                    for server in servers {
+                        if let Err(e) = server.reopen_serial() {
+                            let device = &server.config.serial.device;
+                            warn!("failed to reopen {device} ({})", e.to_string().trim_end());
+                        }
+                    }
+                }
+                Signal::Reload => {
+                    for server in servers {
+                        if let Err(e) = server.reload() {
+                            warn!("failed to reload the config ({})", e.to_string().trim_end());
+                        }
+                    }
+                }
+                Signal::Terminate(signal) => {
+                    info!("Received signal {signal}; shutting down");
+                    Self::shutdown_all(servers, 0)
+                }
+            }
+        }
+    }
+    /// Shuts the servers of all bridges down gracefully in parallel and exits with the given code
+    fn shutdown_all(servers: &[Self], code: i32) -> ! {
+        thread::scope(|scope| {
+            for server in servers {
+                scope.spawn(|| server.shutdown_bridge());
+            }
+        });
+        process::exit(code)
+    }
+    /// Shuts the server down gracefully and exits with the given code
+    ///
+    /// # Note
+    /// The other bridges are not shut down gracefully since the server does not know them.
+    fn shutdown(&self, code: i32) -> ! {
+        self.shutdown_bridge();
+        process::exit(code)
+    }
+    /// Writes the shutdown commands and forwards the partial serial chunk without exiting
+    fn shutdown_bridge(&self) {
+        // Note: the device is not available while it is being reopened
+        let serial = self.serial.try_get();
+
+        // Report the final error counts if appropriate
+        if let Some(serial) = serial.as_ref().filter(|_| self.config.serial.error_counts_interval_ms.is_some()) {
+            if let Ok(counts) = self.update_error_counts(serial) {
+                info!("Serial error counts: {counts}");
+            }
+        }
+
+        // Write the shutdown commands if any and flush the pending serial output
+        match serial {
+            Some(serial) => {
+                // Note: the writer thread is detached so that a blocked device cannot delay the shutdown beyond the
+                // grace period
+                let commands = self.config.serial.shutdown_commands.clone();
+                let what = match commands.is_empty() {
+                    true => "the pending serial output",
+                    false => "the shutdown commands",
+                };
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || sender.send(Self::write_commands(serial, &commands)));
+
+                // Wait for the commands and the pending output to be transmitted
+                let grace = Duration::from_millis(self.config.serial.shutdown_grace_ms);
+                match receiver.recv_timeout(grace) {
+                    Ok(Ok(_)) => (),
+                    Ok(Err(e)) => warn!("failed to transmit {what} ({e})"),
+                    Err(_) => warn!("failed to transmit {what} within the grace period"),
+                }
+            }
+            None if !self.config.serial.shutdown_commands.is_empty() => {
+                warn!("the serial device is not available; skipping the shutdown commands")
+            }
+            None => (),
+        }
+
+        // Forward the partial serial chunk if the device is available
+        // Note: the device is never available if the serial->UDP runloop shuts the server down itself after failed
+        // reconnect attempts, so it cannot wait for itself
+        if self.serial.try_get().is_some() && self.config.serial_to_udp {
+            self.drain_serial();
+        }
+    }
+    /// Stops the serial->UDP runloop once it has forwarded its partial chunk, waiting at most `shutdown_grace_ms`
+    fn drain_serial(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+        let grace = Duration::from_millis(self.config.serial.shutdown_grace_ms);
+        let drained = self.drained.lock().expect("Drain state is poisoned");
+        let (drained, _) = (self.drained_changed.wait_timeout_while(drained, grace, |drained| !*drained))
+            .expect("Drain state is poisoned");
+        if !*drained {
+            warn!("the partial serial chunk has not been forwarded within the grace period");
+        }
+    }
+    /// Writes the commands to the serial device and waits until they and any pending output have been transmitted
+    fn write_commands(mut serial: SerialDevice, commands: &[BytePattern]) -> Result<(), Error> {
+        for command in commands {
+            serial.write_all(command.as_bytes())?;
+        }
+        serial.flush()?;
+        Ok(())
+    }
+
+    /// Sends a chunk that has been read at `read_at` to the destination, or buffers it if the destination is not known
+    /// yet
+    fn send(&self, chunk: &[u8], read_at: Instant) -> Result<(), Error> {
+        // Note: hold the lock during sending to preserve the order with concurrent flushes
+        let mut pending = self.pending.lock().expect("Pending buffer is poisoned");
+        loop {
+            // Drop the stale chunks first so that they are neither sent nor occupy space
+            self.expire_pending(&mut pending);
+            match self.destination.get() {
+                Some(address) => {
+                    // Flush the pending chunks first to preserve the order
+                    while let Some((buffered, pending_chunk)) = pending.pop() {
+                        self.send_to(&pending_chunk, address)?;
+                        self.latency_serial_to_udp.observe(buffered.elapsed());
+                    }
+                    self.send_to(chunk, address)?;
+                    self.latency_serial_to_udp.observe(read_at.elapsed());
+                }
+                None if self.destination.is_none() => (),
+                None => {
+                    // Buffer the chunk according to the overflow strategy
+                    let dropped = match self.config.serial.overflow {
+                        Overflow::DropOld => {
+                            pending.push(chunk, |dropped| self.log_drop("pending buffer full", dropped))
+                        }
+                        Overflow::DropNew if pending.try_push(chunk) => 0,
+                        Overflow::DropNew => {
+                            self.log_drop("pending buffer full", chunk);
+                            1
+                        }
+                        Overflow::Block if pending.try_push(chunk) => 0,
+                        Overflow::Block => {
+                            // Wait for a flush and retry; note: the timeout allows pending resolutions to be retried
+                            let timeout = Duration::from_millis(100);
+                            let (pending_, _) = (self.pending_flushed.wait_timeout(pending, timeout))
+                                .expect("Pending buffer is poisoned");
+                            pending = pending_;
+                            continue;
+                        }
+                    };
+                    self.stats.pending_dropped_chunks.fetch_add(dropped as u64, Ordering::Relaxed);
+                }
+            }
+            return Ok(());
+        }
+    }
+    /// Flushes the buffered output if the destination is known
+    pub fn flush_pending(&self) -> Result<(), Error> {
+        let mut pending = self.pending.lock().expect("Pending buffer is poisoned");
+        self.expire_pending(&mut pending);
+        if let Some(address) = self.destination.get() {
+            while let Some((buffered, pending_chunk)) = pending.pop() {
+                self.send_to(&pending_chunk, address)?;
+                self.latency_serial_to_udp.observe(buffered.elapsed());
+            }
+            self.pending_flushed.notify_all();
+        }
+        Ok(())
+    }
+    /// Flushes the output that has been buffered until the destination has been resolved
+    fn flush_resolved(&self) {
+        if let Err(e) = self.flush_pending() {
+            warn!("failed to send the buffered output ({})", e.to_string().trim_end());
+        }
+    }
+    /// Sends a routed datagram to the destination of its rule, or drops it if the destination cannot be resolved
+    ///
+    /// # Note
+    /// Unlike the default destination, the routed output is not buffered until the destination is known.
+    fn send_routed(&self, datagram: &[u8], destination: &Destination, read_at: Instant) -> Result<(), Error> {
+        match destination.get() {
+            Some(address) => {
+                self.send_to(datagram, address)?;
+                self.latency_serial_to_udp.observe(read_at.elapsed());
+                Ok(())
+            }
+            None => {
+                self.log_drop("route destination unresolved", datagram);
+                Ok(())
+            }
+        }
+    }
+    /// Creates the buffers for [`Server::mark_incomplete`] and the datagram header that hold chunks up to `max_len`
+    /// bytes without reallocating
+    fn datagram_buffers(&self, max_len: usize, marker: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let marked_buf = Vec::with_capacity(max_len + marker.len());
+        let datagram_buf =
+            (self.header.as_ref()).map(|header| header.buffer(marked_buf.capacity())).unwrap_or_default();
+        (marked_buf, datagram_buf)
+    }
+    /// Appends the incomplete marker to a partial chunk, using `buf` as scratch buffer
+    fn mark_incomplete<'a>(chunk: &[u8], marker: &[u8], buf: &'a mut Vec<u8>) -> &'a [u8] {
+        buf.clear();
+        buf.extend_from_slice(chunk);
+        buf.extend_from_slice(marker);
+        buf
+    }
+    /// Applies the TTL and the multicast loopback to the outgoing socket
+    ///
+    /// # Note
+    /// Multicast datagrams ignore the unicast TTL, so the multicast setters are used if the send address is a multicast
+    /// address. For IPv6, the TTL is applied as hop limit.
+    fn set_send_ttl(sender: &UdpSocket, udp: &config::Udp) -> io::Result<()> {
+        match (sender.local_addr()?.is_ipv6(), udp.sends_multicast()) {
+            (false, false) => sender.set_ttl(udp.ttl),
+            (false, true) => {
+                sender.set_multicast_ttl_v4(udp.ttl)?;
+                sender.set_multicast_loop_v4(udp.multicast_loop)
+            }
+            (true, false) => multicast::set_hops_v6(sender, udp.ttl, false),
+            (true, true) => {
+                multicast::set_hops_v6(sender, udp.ttl, true)?;
+                sender.set_multicast_loop_v6(udp.multicast_loop)
+            }
+        }
+    }
+    /// Sends a datagram via the outgoing socket and passes it to the pcap capture if appropriate
+    fn send_to(&self, datagram: &[u8], address: SocketAddr) -> Result<(), Error> {
+        (self.sender.send_to(datagram, address)).map_err(|e| Error::from(e).in_category(ErrorCategory::Udp))?;
+        self.last_sent_ms.store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+        if let Some(pcap) = self.pcap.as_ref() {
+            pcap.record(self.sender.local_addr()?, address, datagram);
+        }
+        Ok(())
+    }
+    /// Drops the buffered chunks that exceed the maximum buffer age and counts them
+    fn expire_pending(&self, pending: &mut PendingBuffer) {
+        let expired = pending.expire(|expired| self.log_drop("maximum buffer age exceeded", expired));
+        self.stats.pending_expired_chunks.fetch_add(expired as u64, Ordering::Relaxed);
+    }
+
+    /// Renders the server status as `name value` lines
+    pub fn status(&self) -> String {
+        let mut status = String::new();
+        for (name, value) in self.status_values() {
+            match value {
+                Value::String(value) => status.push_str(&format!("{name} {value}\n")),
+                value => status.push_str(&format!("{name} {value}\n")),
+            }
+        }
+        status
+    }
+    /// Renders the redacted config, the server status, the statistics and the queue depths as JSON document
+    pub fn status_json(&self) -> Result<String, Error> {
+        // Collect the status, the statistics and the queue depths
+        let status = self.status_values().into_iter().map(|(name, value)| (name.to_string(), value)).collect();
+        let stats = (self.stats.values().into_iter())
+            .map(|(name, value)| (name.to_string(), Value::Integer(value as i64)))
+            .collect();
+        let queues = {
+            let pending = self.pending.lock().expect("Pending buffer is poisoned");
+            let queues = [
+                ("pending_chunks", pending.len()),
+                ("pending_bytes", pending.size()),
+                ("jitter_datagrams", self.stats.jitter_datagrams.load(Ordering::Relaxed) as usize),
+                ("jitter_bytes", self.stats.jitter_bytes.load(Ordering::Relaxed) as usize),
+            ];
+            queues.into_iter().map(|(name, value)| (name.to_string(), Value::Integer(value as i64))).collect()
+        };
+
+        // Assemble the document
+        let document = Map::from_iter([
+            ("status".to_string(), Value::Table(status)),
+            ("stats".to_string(), Value::Table(stats)),
+            ("queues".to_string(), Value::Table(queues)),
+            ("config".to_string(), self.config.to_redacted_value()?),
+        ]);
+        Ok(json::to_json(&Value::Table(document)))
+    }
+    /// The server statistics as JSON document
+    pub fn stats_json(&self) -> String {
+        let stats = (self.stats.values().into_iter())
+            .map(|(name, value)| (name.to_string(), Value::Integer(value as i64)))
+            .collect();
+        json::to_json(&Value::Table(stats))
+    }
+    /// The server status as `(name, value)` pairs
+    fn status_values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("device", Value::String(self.config.serial.device.clone())),
+            ("baudrate", Value::Integer(self.serial_config.read().expect("Serial config is poisoned").baudrate as i64)),
+            ("listen", Value::String(self.socket.as_ref().map_or("none", |_| &self.config.udp.listen).to_string())),
+            ("destination", Value::String(self.destination.to_string())),
+            ("paused", Value::Boolean(self.paused.load(Ordering::Relaxed))),
+            ("uptime_s", Value::Integer(self.started.elapsed().as_secs() as i64)),
+        ]
+    }
+    /// The server statistics
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+    /// The latency histograms in the Prometheus text format
+    pub fn metrics(&self) -> String {
+        let histograms = [
+            ("serial_server_verify_rtt_seconds", "The round-trip time of verified serial writes", &self.latency_verify),
+            (
+                "serial_server_udp_inter_packet_seconds",
+                "The interval between inbound UDP datagrams",
+                &self.latency_inter_packet,
+            ),
+            (
+                "serial_server_serial_to_udp_delay_seconds",
+                "The delay from reading the serial output to sending it via UDP",
+                &self.latency_serial_to_udp,
+            ),
+            (
+                "serial_server_udp_to_serial_delay_seconds",
+                "The delay from receiving input to writing it to the serial device",
+                &self.latency_udp_to_serial,
+            ),
+        ];
+
+        let mut metrics = String::new();
+        for (name, help, histogram) in histograms {
+            histogram.write_prometheus(name, help, &mut metrics).expect("Failed to format histogram");
+        }
+        metrics
+    }
+    /// Pauses or resumes the forwarding in both directions
+    ///
+    /// # Note
+    /// Data that is received while forwarding is paused is discarded.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+    /// Sets the send address, `auto` or `none`
+    ///
+    /// # Note
+    /// The address is validated like `udp.send` on startup; if it is invalid, the previous destination is kept.
+    pub fn set_send(&self, send: &str) -> Result<(), Error> {
+        match send {
+            "none" => self.destination.set(None),
+            Destination::AUTO => self.destination.set(Some(send)),
+            send => {
+                // Reject addresses that cannot be sent to from the outgoing socket
+                let address = Config::validate_address("udp.send", send)?;
+                let local = self.sender.local_addr()?;
+                if address.is_ipv6() != local.is_ipv6() {
+                    return Err(eio!(
+                        "`udp.send` address {send} cannot be reached from the outgoing socket {local} since the \
+                        address families differ; use an address of the same family"
+                    ));
+                }
+                self.destination.set(Some(send));
+            }
+        }
+        Ok(())
+    }
+    /// Reloads the config and applies the settings that can be changed at runtime
+    ///
+    /// # Note
+    /// The send address, the logging configuration and the break duration are changed without interrupting the
+    /// forwarding. If the settings of the serial port have changed, the device is reopened with the new settings like
+    /// on `reopen`. All other changes require a restart and are reported as ignored.
+    pub fn reload(&self) -> Result<(), Error> {
+        let mut config = (Config::load()?.into_iter().nth(self.bridge))
+            .ok_or(eio!("Bridge {} has been removed (bridges cannot be added or removed at runtime)", self.bridge))?;
+        let mut applied = self.config.clone();
+        applied.serial = self.serial_config.read().expect("Serial config is poisoned").clone();
+        if config.serial.autobaud.is_some() {
+            // Keep the detected baudrate since autobaud only runs on startup
+            config.serial.baudrate = applied.serial.baudrate;
+        }
+
+        // Report the changes that require a restart and check whether the serial port settings have changed
+        let changed = self.config.changed_settings(&config)?;
+        let ignored: Vec<_> =
+            changed.iter().map(String::as_str).filter(|setting| !Self::is_reloadable(setting)).collect();
+        if !ignored.is_empty() {
+            let ignored = ignored.join(", ");
+            warn!("ignoring the changed settings {ignored} on reload (restart the server to apply them)");
+        }
+        let reopen =
+            applied.changed_settings(&config)?.iter().any(|setting| Self::RELOAD_REOPEN.contains(&setting.as_str()));
+        if reopen {
+            self.check_reopen()?;
+        }
+
+        // Note: the logging facility is created first so that failing to open the log file leaves everything unchanged
+        let logging = Logging::new(&config.log, &self.config.serial.device, self.started, self.config.timestamp_clock)?
+            .map(Arc::new);
+        if self.config.mode == Mode::Udp {
+            Self::set_send_ttl(&self.sender, &config.udp)
+                .map_err(|e| Error::from(e).in_category(ErrorCategory::Udp))?;
+        }
+        self.destination.set(config.udp.send.as_deref());
+
+        // Swap the logging facility and report the repetitions suppressed by the previous one
+        let previous = mem::replace(&mut *self.logging.write().expect("Logging is poisoned"), logging);
+        if let Some(Logging { logger, dedup: Some(dedup) }) = previous.as_deref() {
+            dedup.report_all(logger);
+        }
+        self.log_drops.store(config.log.log_drops, Ordering::Relaxed);
+        diag::set_level(config.log.level);
+
+        // Swap the serial settings and reopen the device with them if appropriate
+        *self.serial_config.write().expect("Serial config is poisoned") = config.serial;
+        if reopen {
+            self.serial.reopen_now(&self.config.reconnect, || self.open_serial())?;
+        }
+        Ok(())
+    }
+    /// Whether a changed setting is applied on reload
+    fn is_reloadable(setting: &str) -> bool {
+        match setting.split_once('.') {
+            Some(("log", key)) => key != "rate_interval_ms",
+            _ => Self::RELOAD_LIVE.contains(&setting) || Self::RELOAD_REOPEN.contains(&setting),
+        }
+    }
+    /// Logs the data that has been dropped by a policy together with the reason if appropriate
+    ///
+    /// # Note
+    /// All policies that drop data report it here so that drops are logged consistently.
+    pub fn log_drop(&self, reason: &str, data: &[u8]) {
+        self.stats.dropped_chunks.fetch_add(1, Ordering::Relaxed);
+        self.stats.dropped_bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+        if self.log_drops.load(Ordering::Relaxed) && !data.is_empty() {
+            info!("Dropped {} bytes ({reason}): {}", data.len(), logger::escape_line(data));
+        }
+    }
+    /// The current logging facility
+    fn logging(&self) -> Option<Arc<Logging>> {
+        self.logging.read().expect("Logging is poisoned").clone()
+    }
+
+    /// Logs the data if there is a logger available and passes it to the tail subscribers
+    fn monitor(&self, direction: Direction, data: &[u8]) {
+        // Record the activity
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        match direction {
+            Direction::SerialToUdp => {
+                self.stats.serial_rx_bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+                self.stats.last_rx_unix_ms.store(now, Ordering::Relaxed);
+                self.rate_rx.record(data.len());
+            }
+            Direction::UdpToSerial => {
+                self.stats.serial_tx_bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+                self.stats.last_tx_unix_ms.store(now, Ordering::Relaxed);
+                self.rate_tx.record(data.len());
+            }
+        }
+
+        // Log the data if logging is enabled
+        if let Some(logging) = self.logging() {
+            logging.log(direction, data);
+        }
+
+        // Pass the data to the tail subscribers if there is a control socket
+        if let Some(control) = self.control.as_ref() {
+            control.tail().push(direction, data);
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::serial::tests::Pty;
+    use std::io::Read;
+
+    /// Creates a server for the PTY with the given `[udp]` config lines
+    ///
+    /// # Note
+    /// The runloops never return, so the server is leaked and the runloop threads are detached.
+    fn server(pty: &Pty, udp: &str) -> &'static Server {
+        let config = format!("[serial]\ndevice = \"{}\"\n[udp]\n{udp}", pty.path);
+        let config: Config = toml::from_str(&config).expect("Invalid config in test");
+        config.validate().expect("Invalid config in test");
+        Box::leak(Box::new(Server::new(config, 0).expect("Failed to create server")))
+    }
+
+    /// Starts the UDP->serial runloop of a server for the PTY with the given additional `[udp]` config lines and
+    /// returns the server and its listen address
+    fn start(pty: &Pty, udp: &str) -> (&'static Server, SocketAddr) {
+        let server = server(pty, &format!("listen = \"127.0.0.1:0\"\nttl = 64\n{udp}"));
+        let socket = server.socket.as_ref().expect("Missing UDP socket");
+        let address = socket.local_addr().expect("Failed to get the listen address");
+        thread::spawn(move || server.runloop_udp2serial(socket));
+        (server, address)
+    }
+
+    /// Starts the serial->UDP runloop of a server for the PTY with the given additional `[udp]` config lines
+    fn start_sender(pty: &Pty, udp: &str) -> &'static Server {
+        let server = server(pty, &format!("listen = \"127.0.0.1:0\"\n{udp}"));
+        thread::spawn(move || server.runloop_serial2udp());
+        server
+    }
+
+    /// Sends the datagrams in order from the given local address
+    fn send(from: &str, to: SocketAddr, datagrams: &[&[u8]]) {
+        let socket = UdpSocket::bind(from).expect("Failed to bind UDP socket");
+        for datagram in datagrams {
+            socket.send_to(datagram, to).expect("Failed to send datagram");
+        }
+    }
+
+    /// Reads exactly `len` bytes of the serial output, or fails if they do not arrive in time
+    fn read_serial(pty: &Pty, len: usize) -> Vec<u8> {
+        let mut primary = pty.primary.try_clone().expect("Failed to clone PTY");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = vec![0; len];
+            sender.send(primary.read_exact(&mut buf).map(|_| buf))
+        });
+        let output = receiver.recv_timeout(Duration::from_secs(5)).expect("Timed out waiting for the serial output");
+        output.expect("Failed to read from PTY")
+    }
+
+    /// Receives a datagram, or returns `None` if none arrives within the timeout
+    fn recv(socket: &UdpSocket, timeout: Duration) -> Option<Vec<u8>> {
+        socket.set_read_timeout(Some(timeout)).expect("Failed to set read timeout");
+        let mut buf = vec![0; 2048];
+        match socket.recv(&mut buf) {
+            Ok(len) => Some(buf[..len].to_vec()),
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => None,
+            Err(e) => panic!("Failed to receive datagram ({e})"),
+        }
+    }
+
+    /// Joins the IPv4 multicast group on the interface and returns the member socket, or `None` if the host does not
+    /// route multicast datagrams on the interface
+    ///
+    /// # Note
+    /// The member is checked with a probe datagram, so a skipped test means that multicast itself is unavailable.
+    fn join(group: Ipv4Addr, interface: Ipv4Addr) -> Option<UdpSocket> {
+        let member = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).expect("Failed to bind UDP socket");
+        let address = SocketAddr::from((group, member.local_addr().expect("Failed to get the member address").port()));
+        let probe = UdpSocket::bind((interface, 0)).expect("Failed to bind UDP socket");
+        let probed = (member.join_multicast_v4(&group, &interface))
+            .and_then(|_| multicast::set_interface_v4(&probe, interface))
+            .and_then(|_| probe.send_to(b"probe", address))
+            .map(|_| recv(&member, Duration::from_secs(1)));
+        match probed {
+            Ok(Some(datagram)) => {
+                assert_eq!(datagram, b"probe");
+                Some(member)
+            }
+            Ok(None) => {
+                eprintln!("Skipping the test since multicast datagrams are not delivered on {interface}");
+                None
+            }
+            Err(e) => {
+                eprintln!("Skipping the test since multicast is not available on {interface} ({e})");
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn only_allowed_sources_reach_the_serial_device() {
+        let pty = Pty::new();
+        let (server, address) = start(&pty, "allowed_sources = [\"127.0.0.1/32\"]");
+
+        // Send from a disallowed source first, so that the allowed bytes are the first serial output
+        // Note: the whole `127.0.0.0/8` network is routed to the loopback interface
+        send("127.0.0.2:0", address, &[b"disallowed\n"]);
+        send("127.0.0.1:0", address, &[b"allowed\n"]);
+        assert_eq!(read_serial(&pty, 8), b"allowed\n");
+        assert_eq!(server.stats.udp_rejected_datagrams.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn oversized_datagrams_are_dropped() {
+        let pty = Pty::new();
+        let (server, address) = start(&pty, "max_packet_size = 16");
+
+        // Send an oversized datagram first, so that the following datagram is the first serial output
+        send("127.0.0.1:0", address, &[&[b'x'; 17], b"exactly 16 bytes"]);
+        assert_eq!(read_serial(&pty, 16), b"exactly 16 bytes");
+        assert_eq!(server.stats.udp_oversized_datagrams.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn large_datagrams_are_forwarded_intact() {
+        let pty = Pty::new();
+        let (server, address) = start(&pty, "");
+
+        // Send a 10 KB datagram with a pattern that reveals missing or reordered bytes
+        let datagram: Vec<u8> = (0..10 * 1024).map(|index| (index * 7 % 251) as u8).collect();
+        send("127.0.0.1:0", address, &[&datagram]);
+        assert_eq!(read_serial(&pty, datagram.len()), datagram);
+        assert_eq!(server.stats.udp_truncated_datagrams.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn empty_datagrams_are_counted_but_not_written() {
+        let pty = Pty::new();
+        let (server, address) = start(&pty, "");
+
+        // Send an empty datagram first, so that the following datagram is the first serial output
+        send("127.0.0.1:0", address, &[b"", b"after\n"]);
+        assert_eq!(read_serial(&pty, 6), b"after\n");
+        assert_eq!(server.stats.udp_empty_datagrams.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn break_datagrams_send_a_break_instead_of_being_written() {
+        let pty = Pty::new();
+        let (server, address) = start(&pty, "break_datagram = \"BREAK\"");
+
+        // Send the break datagram first, so that the following datagram is the first serial output
+        send("127.0.0.1:0", address, &[b"BREAK", b"after\n"]);
+        assert_eq!(read_serial(&pty, 6), b"after\n");
+        assert_eq!(server.stats.serial_breaks.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn empty_transport_input_is_not_a_drop() {
+        let pty = Pty::new();
+        let (server, _) = start(&pty, "");
+
+        // Write an empty message first, so that the following message is the first serial output
+        let mut frame_buf = server.frame_buffer(16);
+        server.write_input(&mut [], &mut frame_buf);
+        server.write_input(&mut b"after\n".to_vec(), &mut frame_buf);
+        assert_eq!(read_serial(&pty, 6), b"after\n");
+        assert_eq!(server.stats.dropped_chunks.load(Ordering::Relaxed), 0);
+
+        // Check that a message is still dropped while forwarding is paused
+        server.paused.store(true, Ordering::Relaxed);
+        server.write_input(&mut b"paused\n".to_vec(), &mut frame_buf);
+        assert_eq!(server.stats.dropped_chunks.load(Ordering::Relaxed), 1);
+    }
+
+    /// Gets the local address of the default route, or `None` if there is no default route via a non-loopback interface
+    fn default_interface() -> Option<Ipv4Addr> {
+        // Note: connecting a UDP socket only selects the route and the source address without sending anything
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).expect("Failed to bind UDP socket");
+        socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+        match socket.local_addr().expect("Failed to get the local address").ip() {
+            IpAddr::V4(address) if !address.is_loopback() => Some(address),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn serial_output_reaches_a_multicast_group_member() {
+        let group = Ipv4Addr::new(239, 255, 80, 1);
+        let Some(member) = join(group, Ipv4Addr::LOCALHOST) else {
+            return;
+        };
+
+        // Send the serial output to the group via the loopback interface
+        let port = member.local_addr().expect("Failed to get the member address").port();
+        let pty = Pty::new();
+        start_sender(&pty, &format!("send = \"{group}:{port}\"\nttl = 1\n[udp.multicast]\ninterface = \"127.0.0.1\""));
+        (&pty.primary).write_all(b"multicast\n").expect("Failed to write to PTY");
+        assert_eq!(recv(&member, Duration::from_secs(5)).as_deref(), Some(&b"multicast\n"[..]));
+    }
+
+    #[test]
+    fn datagrams_to_a_joined_multicast_group_reach_the_serial_device() {
+        let group = Ipv4Addr::new(239, 255, 80, 2);
+        if join(group, Ipv4Addr::LOCALHOST).is_none() {
+            return;
+        }
+
+        // Join the group via the config and send a datagram to the group via the loopback interface
+        let pty = Pty::new();
+        let udp = format!(
+            "listen = \"0.0.0.0:0\"\nttl = 64\n[udp.multicast]\ninterface = \"127.0.0.1\"\ngroups = [\"{group}\"]"
+        );
+        let server = server(&pty, &udp);
+        let socket = server.socket.as_ref().expect("Missing UDP socket");
+        let port = socket.local_addr().expect("Failed to get the listen address").port();
+        thread::spawn(move || server.runloop_udp2serial(socket));
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).expect("Failed to bind UDP socket");
+        multicast::set_interface_v4(&sender, Ipv4Addr::LOCALHOST).expect("Failed to select the loopback interface");
+        sender.send_to(b"multicast\n", (group, port)).expect("Failed to send datagram");
+        assert_eq!(read_serial(&pty, 10), b"multicast\n");
+    }
+
+    #[test]
+    fn multicast_loop_controls_the_local_delivery() {
+        // Note: the loopback interface delivers its own multicast datagrams regardless of the multicast loopback, so
+        // this needs a real interface
+        let Some(interface) = default_interface() else {
+            eprintln!("Skipping the test since there is no default route for multicast datagrams");
+            return;
+        };
+        let group = Ipv4Addr::new(239, 255, 81, 1);
+        let Some(member) = join(group, interface) else {
+            return;
+        };
+
+        // Send from a server without multicast loopback first, so that its datagram cannot be mistaken for the
+        // looped back datagram
+        let port = member.local_addr().expect("Failed to get the member address").port();
+        for (multicast_loop, output) in [(false, &b"not looped\n"[..]), (true, &b"looped\n"[..])] {
+            let pty = Pty::new();
+            let udp = format!("send = \"{group}:{port}\"\nmulticast_loop = {multicast_loop}");
+            start_sender(&pty, &format!("{udp}\n[udp.multicast]\ninterface = \"{interface}\""));
+            (&pty.primary).write_all(output).expect("Failed to write to PTY");
+            match multicast_loop {
+                true => assert_eq!(recv(&member, Duration::from_secs(5)).as_deref(), Some(output)),
+                false => assert_eq!(recv(&member, Duration::from_millis(500)), None, "Datagram has been looped back"),
+            }
+        }
+    }
+
+    #[test]
+    fn multicast_send_addresses_use_the_multicast_ttl() {
+        // Note: the TTL differs from both defaults, so that a wrong setter is observable
+        let defaults = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).expect("Failed to bind UDP socket");
+        let default_ttl = defaults.ttl().expect("Failed to get the TTL");
+        let default_multicast_ttl = defaults.multicast_ttl_v4().expect("Failed to get the multicast TTL");
+        assert!(default_ttl != 7 && default_multicast_ttl != 7, "The test TTL is a default TTL");
+
+        // Check that a multicast send address sets the multicast TTL and keeps the unicast TTL
+        let multicast = server(&Pty::new(), "listen = \"127.0.0.1:0\"\nsend = \"239.255.81.2:6666\"\nttl = 7");
+        assert_eq!(multicast.sender.multicast_ttl_v4().expect("Failed to get the multicast TTL"), 7);
+        assert_eq!(multicast.sender.ttl().expect("Failed to get the TTL"), default_ttl);
+
+        // Check that a unicast send address sets the unicast TTL and keeps the multicast TTL
+        let unicast = server(&Pty::new(), "listen = \"127.0.0.1:0\"\nsend = \"127.0.0.1:6666\"\nttl = 7");
+        assert_eq!(unicast.sender.ttl().expect("Failed to get the TTL"), 7);
+        assert_eq!(unicast.sender.multicast_ttl_v4().expect("Failed to get the multicast TTL"), default_multicast_ttl);
+    }
+
+    #[test]
+    fn datagrams_round_trip_over_ipv6() {
+        let peer = UdpSocket::bind("[::1]:0").expect("Failed to bind IPv6 UDP socket");
+        let peer_address = peer.local_addr().expect("Failed to get the peer address");
+        let pty = Pty::new();
+        let server = server(&pty, &format!("listen = \"[::1]:0\"\nsend = \"{peer_address}\"\nttl = 64"));
+        assert!(server.sender.local_addr().expect("Failed to get the sender address").is_ipv6());
+
+        // Start both runloops
+        let socket = server.socket.as_ref().expect("Missing UDP socket");
+        let address = socket.local_addr().expect("Failed to get the listen address");
+        thread::spawn(move || server.runloop_udp2serial(socket));
+        thread::spawn(move || server.runloop_serial2udp());
+
+        // Send a datagram to the serial device and the serial device's reply back to the peer
+        peer.send_to(b"request\n", address).expect("Failed to send datagram");
+        assert_eq!(read_serial(&pty, 8), b"request\n");
+        (&pty.primary).write_all(b"reply\n").expect("Failed to write to PTY");
+        assert_eq!(recv(&peer, Duration::from_secs(5)).as_deref(), Some(&b"reply\n"[..]));
+    }
+
+    #[test]
+    fn datagram_buffers_are_not_reallocated() {
+        let pty = Pty::new();
+        let udp = "listen = \"127.0.0.1:0\"\nttl = 64\n[udp.header]\nfields = [{ field = \"sequence\", size = 4 }]";
+        let server = server(&pty, udp);
+        let header = server.header.as_ref().expect("Missing header");
+        let (marker, max_len) = (&b"[...]"[..], 1024);
+        let (mut marked_buf, mut datagram_buf) = server.datagram_buffers(max_len, marker);
+        let marked_alloc = (marked_buf.as_ptr(), marked_buf.capacity());
+        let datagram_alloc = (datagram_buf.as_ptr(), datagram_buf.capacity());
 
-    /// Logs the data if there is a logger available
-    fn log(&self, data: &[u8]) {
-        // Unwrap the logger if available
-        if let Some(logger) = self.logger {
-            // Log the data
-            logger.log(data);
+        // Mark and frame chunks of growing and shrinking sizes up to the maximum
+        for len in (0..=max_len).step_by(7).chain([max_len, 0, max_len]) {
+            let marked = Server::mark_incomplete(&vec![b'x'; len], marker, &mut marked_buf);
+            let datagram = header.encode(marked, &mut datagram_buf);
+            assert_eq!(datagram.len(), 4 + len + marker.len());
         }
+        assert_eq!((marked_buf.as_ptr(), marked_buf.capacity()), marked_alloc, "Marker buffer has been reallocated");
+        assert_eq!(
+            (datagram_buf.as_ptr(), datagram_buf.capacity()),
+            datagram_alloc,
+            "Datagram buffer has been reallocated"
+        );
     }
 }
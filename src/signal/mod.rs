@@ -0,0 +1,56 @@
+//! Provides OS-specific signal handling
+
+use std::io;
+
+extern "C" {
+    // int32_t signal_block(void)
+    fn signal_block() -> i32;
+
+    // int32_t signal_wait(void)
+    fn signal_wait() -> i32;
+
+    // int32_t signal_reopen(void)
+    fn signal_reopen() -> i32;
+
+    // int32_t signal_reload(void)
+    fn signal_reload() -> i32;
+}
+
+/// A handled signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// A termination signal (`SIGINT`, `SIGTERM`) with the given signal number
+    Terminate(i32),
+    /// A request to reopen the serial device (`SIGUSR2`)
+    Reopen,
+    /// A request to reload the config (`SIGHUP`)
+    Reload,
+}
+
+/// Blocks the handled signals (`SIGINT`, `SIGTERM`, `SIGUSR2`, `SIGHUP`) for the calling thread and all threads spawned
+/// by it afterwards
+///
+/// # Note
+/// This must be called before spawning any threads so that the signals are only delivered via [`wait`].
+pub fn block() -> io::Result<()> {
+    let result = unsafe { signal_block() };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Waits until one of the handled signals is pending and returns the signal
+pub fn wait() -> io::Result<Signal> {
+    let signal = unsafe { signal_wait() };
+    if signal < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Classify the signal
+    match signal {
+        signal if signal == unsafe { signal_reopen() } => Ok(Signal::Reopen),
+        signal if signal == unsafe { signal_reload() } => Ok(Signal::Reload),
+        signal => Ok(Signal::Terminate(signal)),
+    }
+}
@@ -0,0 +1,72 @@
+#include <stdint.h>
+#include <errno.h>
+#include <signal.h>
+#include <pthread.h>
+
+/**
+ * @brief Creates the set of handled signals
+ * 
+ * @param set The signal set to initialize
+ */
+static void signal_set(sigset_t* set) {
+    sigemptyset(set);
+    sigaddset(set, SIGINT);
+    sigaddset(set, SIGTERM);
+    sigaddset(set, SIGUSR2);
+    sigaddset(set, SIGHUP);
+}
+
+/**
+ * @brief Gets the signal number that requests a reopen of the serial device
+ * 
+ * @return The signal number
+ */
+int32_t signal_reopen(void) {
+    return SIGUSR2;
+}
+
+/**
+ * @brief Gets the signal number that requests a reload of the config
+ * 
+ * @return The signal number
+ */
+int32_t signal_reload(void) {
+    return SIGHUP;
+}
+
+/**
+ * @brief Blocks the handled signals for the calling thread and all threads spawned by it afterwards
+ * 
+ * @return `0` or `-1` on error
+ */
+int32_t signal_block(void) {
+    // Block the signals
+    sigset_t set;
+    signal_set(&set);
+
+    int result = pthread_sigmask(SIG_BLOCK, &set, NULL);
+    if (result != 0) {
+        errno = result;
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Waits until one of the handled signals is pending
+ * 
+ * @return The signal number or `-1` on error
+ */
+int32_t signal_wait(void) {
+    // Wait for the signals
+    sigset_t set;
+    signal_set(&set);
+
+    int signal = 0;
+    int result = sigwait(&set, &signal);
+    if (result != 0) {
+        errno = result;
+        return -1;
+    }
+    return signal;
+}
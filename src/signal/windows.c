@@ -0,0 +1,77 @@
+#include <stdint.h>
+#include <signal.h>
+#include <windows.h>
+
+/// The event that is set once a console control event has been received
+static HANDLE signal_event = NULL;
+/// The signal number of the last console control event
+static volatile LONG signal_pending = 0;
+
+/**
+ * @brief Records console control events as termination signals
+ *
+ * @param type The console control event type
+ * @return `TRUE` if the event has been handled
+ */
+static BOOL WINAPI signal_handler(DWORD type) {
+    switch (type) {
+        case CTRL_C_EVENT:
+        case CTRL_BREAK_EVENT:
+            InterlockedExchange(&signal_pending, SIGINT);
+            break;
+        case CTRL_CLOSE_EVENT:
+        case CTRL_SHUTDOWN_EVENT:
+            InterlockedExchange(&signal_pending, SIGTERM);
+            break;
+        default:
+            return FALSE;
+    }
+    SetEvent(signal_event);
+    return TRUE;
+}
+
+/**
+ * @brief Gets the signal number that requests a reopen of the serial device
+ *
+ * @return `-1` since there is no such signal on this platform
+ */
+int32_t signal_reopen(void) {
+    return -1;
+}
+
+/**
+ * @brief Gets the signal number that requests a reload of the config
+ *
+ * @return `-1` since there is no such signal on this platform
+ */
+int32_t signal_reload(void) {
+    return -1;
+}
+
+/**
+ * @brief Installs the console control handler which records the termination requests
+ *
+ * @return `0` or `-1` on error
+ */
+int32_t signal_block(void) {
+    signal_event = CreateEventA(NULL, FALSE, FALSE, NULL);
+    if (signal_event == NULL) {
+        return -1;
+    }
+    if (!SetConsoleCtrlHandler(signal_handler, TRUE)) {
+        return -1;
+    }
+    return 0;
+}
+
+/**
+ * @brief Waits until a console control event has been received
+ *
+ * @return The signal number (`SIGINT` or `SIGTERM`) or `-1` on error
+ */
+int32_t signal_wait(void) {
+    if (WaitForSingleObject(signal_event, INFINITE) != WAIT_OBJECT_0) {
+        return -1;
+    }
+    return (int32_t)InterlockedExchange(&signal_pending, 0);
+}
@@ -0,0 +1,101 @@
+//! Implements the server statistics
+
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The server statistics
+#[derive(Debug, Default)]
+pub struct Stats {
+    /// The amount of datagrams currently held in the jitter buffer
+    pub jitter_datagrams: AtomicU64,
+    /// The amount of bytes currently held in the jitter buffer
+    pub jitter_bytes: AtomicU64,
+    /// The amount of framing errors reported by the serial driver
+    pub serial_frame_errors: AtomicU64,
+    /// The amount of parity errors reported by the serial driver
+    pub serial_parity_errors: AtomicU64,
+    /// The amount of hardware overruns reported by the serial driver
+    pub serial_overruns: AtomicU64,
+    /// The amount of buffer overruns reported by the serial driver
+    pub serial_buffer_overruns: AtomicU64,
+    /// The amount of serial output chunks dropped because the pending buffer was full
+    pub pending_dropped_chunks: AtomicU64,
+    /// The amount of serial output chunks dropped because they exceeded the maximum buffer age
+    pub pending_expired_chunks: AtomicU64,
+    /// The amount of buffered UDP->serial writes dropped because they exceeded the maximum delay
+    pub udp_to_serial_expired_writes: AtomicU64,
+    /// The total amount of bytes read from the serial device
+    pub serial_rx_bytes: AtomicU64,
+    /// The total amount of bytes written to the serial device
+    pub serial_tx_bytes: AtomicU64,
+    /// The total amount of chunks and datagrams dropped by a policy
+    pub dropped_chunks: AtomicU64,
+    /// The total amount of bytes dropped by a policy
+    pub dropped_bytes: AtomicU64,
+    /// The amount of reconnect cycles of the serial device
+    pub reconnect_cycles: AtomicU64,
+    /// The amount of break conditions transmitted on request
+    pub serial_breaks: AtomicU64,
+    /// The amount of datagrams dropped because their source is not allowed
+    pub udp_rejected_datagrams: AtomicU64,
+    /// The amount of datagrams dropped because they exceeded the UDP receive buffer
+    pub udp_truncated_datagrams: AtomicU64,
+    /// The amount of datagrams dropped because they exceeded the maximum packet size
+    pub udp_oversized_datagrams: AtomicU64,
+    /// The amount of datagrams in the UDP->serial queue
+    pub udp_to_serial_queued: AtomicU64,
+    /// The amount of datagrams dropped because the UDP->serial queue was full
+    pub udp_to_serial_queue_dropped: AtomicU64,
+    /// The amount of received datagrams without payload
+    pub udp_empty_datagrams: AtomicU64,
+    /// The amount of heartbeat datagrams sent while the serial device was idle
+    pub udp_heartbeats: AtomicU64,
+    /// The amount of datagrams received with the most recent batch receive
+    pub udp_recv_batch_size: AtomicU64,
+    /// The time of the most recent serial->UDP chunk in milliseconds since the Unix epoch, or `0` if there was none
+    pub last_rx_unix_ms: AtomicU64,
+    /// The time of the most recent UDP->serial chunk in milliseconds since the Unix epoch, or `0` if there was none
+    pub last_tx_unix_ms: AtomicU64,
+}
+impl Stats {
+    /// A snapshot of all statistics as `(name, value)` pairs
+    pub fn values(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("jitter_datagrams", self.jitter_datagrams.load(Ordering::Relaxed)),
+            ("jitter_bytes", self.jitter_bytes.load(Ordering::Relaxed)),
+            ("serial_frame_errors", self.serial_frame_errors.load(Ordering::Relaxed)),
+            ("serial_parity_errors", self.serial_parity_errors.load(Ordering::Relaxed)),
+            ("serial_overruns", self.serial_overruns.load(Ordering::Relaxed)),
+            ("serial_buffer_overruns", self.serial_buffer_overruns.load(Ordering::Relaxed)),
+            ("pending_dropped_chunks", self.pending_dropped_chunks.load(Ordering::Relaxed)),
+            ("pending_expired_chunks", self.pending_expired_chunks.load(Ordering::Relaxed)),
+            ("udp_to_serial_expired_writes", self.udp_to_serial_expired_writes.load(Ordering::Relaxed)),
+            ("serial_rx_bytes", self.serial_rx_bytes.load(Ordering::Relaxed)),
+            ("serial_tx_bytes", self.serial_tx_bytes.load(Ordering::Relaxed)),
+            ("dropped_chunks", self.dropped_chunks.load(Ordering::Relaxed)),
+            ("dropped_bytes", self.dropped_bytes.load(Ordering::Relaxed)),
+            ("reconnect_cycles", self.reconnect_cycles.load(Ordering::Relaxed)),
+            ("serial_breaks", self.serial_breaks.load(Ordering::Relaxed)),
+            ("udp_rejected_datagrams", self.udp_rejected_datagrams.load(Ordering::Relaxed)),
+            ("udp_truncated_datagrams", self.udp_truncated_datagrams.load(Ordering::Relaxed)),
+            ("udp_oversized_datagrams", self.udp_oversized_datagrams.load(Ordering::Relaxed)),
+            ("udp_to_serial_queued", self.udp_to_serial_queued.load(Ordering::Relaxed)),
+            ("udp_to_serial_queue_dropped", self.udp_to_serial_queue_dropped.load(Ordering::Relaxed)),
+            ("udp_empty_datagrams", self.udp_empty_datagrams.load(Ordering::Relaxed)),
+            ("udp_heartbeats", self.udp_heartbeats.load(Ordering::Relaxed)),
+            ("udp_recv_batch_size", self.udp_recv_batch_size.load(Ordering::Relaxed)),
+            ("last_rx_unix_ms", self.last_rx_unix_ms.load(Ordering::Relaxed)),
+            ("last_tx_unix_ms", self.last_tx_unix_ms.load(Ordering::Relaxed)),
+        ]
+    }
+}
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (name, value) in self.values() {
+            writeln!(f, "{name} {value}")?;
+        }
+        Ok(())
+    }
+}
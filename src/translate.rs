@@ -0,0 +1,55 @@
+//! Implements a byte-to-byte translation table
+
+use crate::error::Error;
+use std::collections::BTreeMap;
+
+/// A 256-entry byte translation table
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// The lookup table
+    table: [u8; 256],
+}
+impl Table {
+    /// Creates a new translation table from a map of input bytes to output bytes
+    ///
+    /// # Note
+    /// The input bytes are specified as decimal (`13`) or hexadecimal (`0x0d`) strings since TOML keys are always
+    /// strings. Returns `None` if the table would be the identity.
+    pub fn new(translations: &BTreeMap<String, u8>) -> Result<Option<Self>, Error> {
+        // Create the identity table
+        let mut table = [0; 256];
+        for (index, entry) in table.iter_mut().enumerate() {
+            *entry = index as u8;
+        }
+
+        // Apply the translations
+        let mut is_identity = true;
+        for (input, &output) in translations {
+            let input = Self::parse_byte(input)?;
+            table[input as usize] = output;
+            is_identity &= input == output;
+        }
+
+        // Skip translation entirely if the table is the identity
+        match is_identity {
+            true => Ok(None),
+            false => Ok(Some(Self { table })),
+        }
+    }
+
+    /// Translates the data in place
+    pub fn apply(&self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte = self.table[*byte as usize];
+        }
+    }
+
+    /// Parses a decimal or `0x`-prefixed hexadecimal byte
+    fn parse_byte(byte: &str) -> Result<u8, Error> {
+        let parsed = match byte.strip_prefix("0x").or_else(|| byte.strip_prefix("0X")) {
+            Some(hex) => u8::from_str_radix(hex, 16),
+            None => byte.parse(),
+        };
+        parsed.map_err(|_| eio!("Invalid byte in translation table: {byte}"))
+    }
+}
@@ -0,0 +1,22 @@
+//! Implements the transports that carry the serial data in addition to UDP
+
+pub mod tcp;
+
+use crate::{error::Error, server::Server};
+use std::{fmt::Debug, thread::Scope};
+
+/// A transport that carries the serial data
+///
+/// # Failure handling
+/// A transport handles failing peers itself (e.g. by dropping disconnected clients); if the transport as a whole fails,
+/// the server continues without it.
+pub trait Transport: Debug + Sync {
+    /// A short description of the transport for log messages
+    fn name(&self) -> String;
+
+    /// Sends a chunk of serial output to all peers of the transport
+    fn send(&self, chunk: &[u8]) -> Result<(), Error>;
+
+    /// Serves the transport and passes the peers' input to [`Server::write_input`]
+    fn runloop<'scope>(&'scope self, scope: &'scope Scope<'scope, '_>, server: &'scope Server) -> Result<(), Error>;
+}
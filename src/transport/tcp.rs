@@ -0,0 +1,249 @@
+//! Implements a TCP transport
+
+use crate::{
+    config::{self, TcpLimit, TcpWritePolicy},
+    error::Error,
+    server::Server,
+    transport::Transport,
+};
+#[cfg(unix)]
+use std::os::fd::AsRawFd;
+#[cfg(windows)]
+use std::os::windows::io::AsRawSocket;
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{Condvar, Mutex},
+    thread::{self, Scope},
+    time::Duration,
+};
+
+extern "C" {
+    // int32_t transport_set_backlog(int64_t fd, uint32_t backlog)
+    fn transport_set_backlog(fd: i64, backlog: u32) -> i32;
+}
+
+/// A TCP transport where each client receives the serial output and may write to the serial device
+///
+/// # Connect mode
+/// If the transport connects to a remote address instead of listening, the remote peer is the only client; the
+/// transport reconnects if the connection fails or is closed.
+#[derive(Debug)]
+pub struct TcpTransport {
+    /// The TCP listener if the transport listens for clients
+    listener: Option<TcpListener>,
+    /// The remote address if the transport connects to a remote peer
+    connect: Option<String>,
+    /// The maximum amount of concurrently connected clients
+    max_clients: usize,
+    /// How new clients are handled if the client limit is reached
+    on_limit: TcpLimit,
+    /// Which clients may write to the serial device
+    write_policy: TcpWritePolicy,
+    /// The connected clients and their addresses
+    clients: Mutex<Vec<(SocketAddr, TcpStream)>>,
+    /// Signals that a client has been removed from the client list
+    clients_removed: Condvar,
+    /// The client that may write exclusively to the serial device
+    writer: Mutex<Option<SocketAddr>>,
+}
+impl TcpTransport {
+    /// The maximum time to wait for a client to accept the serial output before it is disconnected
+    const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+    /// The delay between two connection attempts in connect mode
+    const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+    /// The size of the receive buffer per client
+    pub const BUF_SIZE: usize = 4000;
+
+    /// Creates a new TCP transport
+    pub fn new(config: &config::Tcp) -> Result<Self, Error> {
+        let listener = match config.listen.as_deref() {
+            Some(listen) => Some(TcpListener::bind(listen)?),
+            None => None,
+        };
+        if let Some(listener) = &listener {
+            Self::set_backlog(listener, config.backlog)?;
+        }
+        Ok(Self {
+            listener,
+            connect: config.connect.clone(),
+            max_clients: config.max_clients,
+            on_limit: config.on_limit,
+            write_policy: config.write_policy,
+            clients: Mutex::default(),
+            clients_removed: Condvar::new(),
+            writer: Mutex::default(),
+        })
+    }
+    /// Changes the listen backlog of the listener
+    fn set_backlog(listener: &TcpListener, backlog: u32) -> Result<(), Error> {
+        #[cfg(unix)]
+        let fd = listener.as_raw_fd() as i64;
+        #[cfg(windows)]
+        let fd = listener.as_raw_socket() as i64;
+
+        let result = unsafe { transport_set_backlog(fd, backlog) };
+        if result < 0 {
+            let errno = io::Error::last_os_error();
+            return Err(errno.into());
+        }
+        Ok(())
+    }
+
+    /// Registers a connected client so that it receives the serial output
+    fn register(&self, stream: TcpStream) -> Result<TcpStream, Error> {
+        // Note: the write timeout ensures that a stalled client cannot block the serial output for other peers
+        stream.set_write_timeout(Some(Self::WRITE_TIMEOUT))?;
+        Ok(stream.try_clone()?)
+    }
+
+    /// Accepts clients and serves each client in a separate thread
+    fn runloop_listen<'scope>(
+        &'scope self,
+        listener: &'scope TcpListener,
+        scope: &'scope Scope<'scope, '_>,
+        server: &'scope Server,
+    ) -> Result<(), Error> {
+        loop {
+            // Wait for a free slot before accepting so that new connections wait in the listen backlog if appropriate
+            if self.on_limit == TcpLimit::Queue {
+                let clients = self.clients.lock().expect("TCP client list is poisoned");
+                let _clients = (self.clients_removed.wait_while(clients, |clients| clients.len() >= self.max_clients))
+                    .expect("TCP client list is poisoned");
+            }
+
+            // Ignore failed connection attempts
+            let Ok((stream, address)) = listener.accept() else {
+                continue;
+            };
+
+            // Register the client or refuse it if the limit is reached
+            let reader = self.register(stream.try_clone()?)?;
+            let mut clients = self.clients.lock().expect("TCP client list is poisoned");
+            if clients.len() >= self.max_clients {
+                warn!("refusing TCP client {address} (limit of {} clients reached)", self.max_clients);
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+            clients.push((address, stream));
+            drop(clients);
+
+            // Serve the client
+            scope.spawn(move || self.serve(server, address, reader));
+        }
+    }
+    /// Connects to the remote peer and serves it, reconnecting if the connection fails or is closed
+    fn runloop_connect(&self, remote: &str, server: &Server) -> Result<(), Error> {
+        let mut failing = false;
+        loop {
+            // Connect to the remote peer and warn once per failure streak
+            let stream = match TcpStream::connect(remote) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    if !failing {
+                        warn!("failed to connect to TCP peer {remote} ({e}); retrying");
+                        failing = true;
+                    }
+                    thread::sleep(Self::RECONNECT_DELAY);
+                    continue;
+                }
+            };
+            failing = false;
+
+            // Register and serve the peer
+            let address = stream.peer_addr()?;
+            let reader = self.register(stream.try_clone()?)?;
+            self.clients.lock().expect("TCP client list is poisoned").push((address, stream));
+            info!("Connected to TCP peer {address}");
+            self.serve(server, address, reader);
+
+            // Reconnect after a delay
+            warn!("TCP peer {address} has disconnected; reconnecting");
+            thread::sleep(Self::RECONNECT_DELAY);
+        }
+    }
+
+    /// Passes the input of a client to the server until the client disconnects
+    fn serve(&self, server: &Server, address: SocketAddr, mut stream: TcpStream) {
+        let (mut buf, mut frame_buf, mut rejected) =
+            (vec![0; Self::BUF_SIZE], server.frame_buffer(Self::BUF_SIZE), false);
+        loop {
+            let bytes_read = match stream.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(bytes_read) => bytes_read,
+            };
+
+            // Drop the input if another client is the exclusive writer
+            if !self.may_write(address) {
+                if !rejected {
+                    warn!("dropping the input of TCP client {address} since another client is the writer");
+                    rejected = true;
+                }
+                server.log_drop("another TCP client is the writer", &buf[..bytes_read]);
+                continue;
+            }
+            server.write_input(&mut buf[..bytes_read], &mut frame_buf);
+        }
+
+        // Release the client
+        let _ = stream.shutdown(Shutdown::Both);
+        self.remove(address);
+    }
+    /// Checks whether the client may write to the serial device and claims the exclusive writer role if appropriate
+    fn may_write(&self, address: SocketAddr) -> bool {
+        if self.write_policy == TcpWritePolicy::Shared {
+            return true;
+        }
+
+        // Claim the writer role if it is vacant
+        let mut writer = self.writer.lock().expect("TCP writer is poisoned");
+        *writer.get_or_insert(address) == address
+    }
+    /// Removes a client from the client list and releases its writer role
+    fn remove(&self, address: SocketAddr) {
+        let mut writer = self.writer.lock().expect("TCP writer is poisoned");
+        if *writer == Some(address) {
+            *writer = None;
+        }
+
+        // Remove the client
+        let mut clients = self.clients.lock().expect("TCP client list is poisoned");
+        clients.retain(|(client, _)| *client != address);
+        self.clients_removed.notify_all();
+    }
+}
+impl Transport for TcpTransport {
+    fn name(&self) -> String {
+        if let Some(remote) = &self.connect {
+            return format!("TCP client {remote}");
+        }
+        let address = self.listener.as_ref().and_then(|listener| listener.local_addr().ok());
+        match address {
+            Some(address) => format!("TCP {address}"),
+            None => "TCP (unknown)".to_string(),
+        }
+    }
+
+    fn send(&self, chunk: &[u8]) -> Result<(), Error> {
+        // Send the chunk to all clients and drop unresponsive or disconnected clients
+        let mut clients = self.clients.lock().expect("TCP client list is poisoned");
+        clients.retain_mut(|(address, client)| match client.write_all(chunk) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("disconnecting TCP client {address} ({e})");
+                let _ = client.shutdown(Shutdown::Both);
+                false
+            }
+        });
+        self.clients_removed.notify_all();
+        Ok(())
+    }
+
+    fn runloop<'scope>(&'scope self, scope: &'scope Scope<'scope, '_>, server: &'scope Server) -> Result<(), Error> {
+        match (&self.listener, &self.connect) {
+            (Some(listener), _) => self.runloop_listen(listener, scope, server),
+            (None, Some(remote)) => self.runloop_connect(remote, server),
+            (None, None) => unreachable!("TCP transport has neither a listener nor a remote address"),
+        }
+    }
+}
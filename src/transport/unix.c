@@ -0,0 +1,16 @@
+#include <stdint.h>
+#include <sys/socket.h>
+
+/**
+ * @brief Changes the listen backlog of an already listening socket
+ * 
+ * @param fd The socket file descriptor
+ * @param backlog The maximum amount of pending connections
+ * @return `0` or `-1` on error
+ */
+int32_t transport_set_backlog(int64_t fd, uint32_t backlog) {
+    if (listen((int)fd, (int)backlog) != 0) {
+        return -1;
+    }
+    return 0;
+}
@@ -0,0 +1,17 @@
+#include <stdint.h>
+#include <winsock2.h>
+
+/**
+ * @brief Changes the listen backlog of an already listening socket
+ *
+ * @param fd The socket handle
+ * @param backlog The maximum amount of pending connections
+ * @return `0` or `-1` on error
+ */
+int32_t transport_set_backlog(int64_t fd, uint32_t backlog) {
+    if (listen((SOCKET)fd, (int)backlog) != 0) {
+        SetLastError((DWORD)WSAGetLastError());
+        return -1;
+    }
+    return 0;
+}
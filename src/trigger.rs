@@ -0,0 +1,96 @@
+//! Implements the start trigger which suppresses the serial output until a pattern has been seen
+
+use crate::config;
+
+/// Scans the serial output for the start trigger
+#[derive(Debug)]
+pub struct StartTrigger {
+    /// The trigger pattern
+    pattern: Vec<u8>,
+    /// Whether the trigger itself is forwarded
+    include: bool,
+    /// Whether the trigger has been seen
+    seen: bool,
+    /// The scan buffer with the tail of the previous chunks followed by the current chunk
+    buf: Vec<u8>,
+}
+impl StartTrigger {
+    /// Creates a new start trigger
+    pub fn new(config: &config::StartTrigger) -> Self {
+        let pattern = config.pattern.as_bytes().to_vec();
+        Self { pattern, include: config.include_trigger, seen: false, buf: Vec::new() }
+    }
+
+    /// Whether the trigger has been seen and the output is forwarded
+    pub const fn seen(&self) -> bool {
+        self.seen
+    }
+
+    /// Scans the chunk for the trigger and returns the output to forward once the trigger has been seen; the
+    /// discarded output is passed to `on_discard`
+    ///
+    /// # Note
+    /// The trigger may span multiple chunks; if the trigger is included, the returned output starts with the complete
+    /// trigger and may thus be up to `pattern.len() - 1` bytes longer than the chunk.
+    pub fn scan<F>(&mut self, chunk: &[u8], on_discard: F) -> Option<&mut [u8]>
+    where
+        F: FnOnce(&[u8]),
+    {
+        self.buf.extend_from_slice(chunk);
+
+        // Search the trigger
+        let Some(position) = self.buf.windows(self.pattern.len()).position(|window| window == self.pattern) else {
+            // Keep the tail that may be the start of a trigger that spans into the next chunk
+            let tail = self.buf.len().saturating_sub(self.pattern.len() - 1);
+            on_discard(&self.buf[..tail]);
+            self.buf.drain(..tail);
+            return None;
+        };
+
+        // Forward the output after the trigger
+        self.seen = true;
+        let start = match self.include {
+            true => position,
+            false => position + self.pattern.len(),
+        };
+        on_discard(&self.buf[..start]);
+        Some(&mut self.buf[start..])
+    }
+}
+
+/// Scans the serial output for the stop trigger
+#[derive(Debug)]
+pub struct StopTrigger {
+    /// The trigger pattern
+    pattern: Vec<u8>,
+    /// The scan buffer with the tail of the previous chunks followed by the current chunk
+    buf: Vec<u8>,
+}
+impl StopTrigger {
+    /// Creates a new stop trigger
+    pub fn new(config: &config::StopTrigger) -> Self {
+        Self { pattern: config.pattern.as_bytes().to_vec(), buf: Vec::new() }
+    }
+
+    /// Scans the chunk for the trigger and returns the length of the chunk up to and including the trigger if it has
+    /// been seen
+    ///
+    /// # Note
+    /// The trigger may span multiple chunks; the scanner is reset after a match so that it can be seen again.
+    pub fn scan(&mut self, chunk: &[u8]) -> Option<usize> {
+        let tail_len = self.buf.len();
+        self.buf.extend_from_slice(chunk);
+
+        // Search the trigger
+        let Some(position) = self.buf.windows(self.pattern.len()).position(|window| window == self.pattern) else {
+            // Keep the tail that may be the start of a trigger that spans into the next chunk
+            let tail = self.buf.len().saturating_sub(self.pattern.len() - 1);
+            self.buf.drain(..tail);
+            return None;
+        };
+
+        // Note: the trigger always ends within the chunk since the tail is shorter than the trigger
+        self.buf.clear();
+        Some(position + self.pattern.len() - tail_len)
+    }
+}
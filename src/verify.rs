@@ -0,0 +1,121 @@
+//! Implements a post-write verification via the serial device's output
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// A pending expectation
+#[derive(Debug, Default)]
+struct Expectation {
+    /// The expected pattern, or `None` if nothing is expected
+    pattern: Option<Vec<u8>>,
+    /// The most recent output that may contain the beginning of the pattern
+    window: Vec<u8>,
+    /// Whether the pattern has been seen
+    matched: bool,
+}
+
+/// Verifies writes by waiting for an expected response in the serial device's output
+#[derive(Debug)]
+pub struct Verifier {
+    /// The expected response, or `None` to expect an echo of the written data
+    response: Option<Vec<u8>>,
+    /// The time to wait for the response per attempt
+    timeout: Duration,
+    /// The amount of retries
+    retries: u64,
+    /// The pending expectation
+    expectation: Mutex<Expectation>,
+    /// Signals a match
+    matched: Condvar,
+    /// The total amount of failed verifications
+    failures: AtomicU64,
+}
+impl Verifier {
+    /// Creates a new verifier
+    pub fn new(response: Option<Vec<u8>>, timeout: Duration, retries: u64) -> Self {
+        Self {
+            response,
+            timeout,
+            retries,
+            expectation: Mutex::default(),
+            matched: Condvar::new(),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    /// Writes the data via `write` and waits for the expected response, retrying if necessary
+    ///
+    /// Returns the round-trip time from the write of the successful attempt to the response, or `None` if the write
+    /// could not be verified.
+    pub fn write<F, E>(&self, data: &[u8], mut write: F) -> Result<Option<Duration>, E>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>,
+    {
+        // An empty pattern is always matched
+        let pattern = self.response.as_deref().unwrap_or(data);
+        if pattern.is_empty() {
+            let started = Instant::now();
+            write(data)?;
+            return Ok(Some(started.elapsed()));
+        }
+
+        // Write the data and wait for the response
+        for _ in 0..=self.retries {
+            // Arm the expectation before writing so that a fast response is not missed
+            self.expect(pattern);
+            let started = Instant::now();
+            write(data)?;
+            if self.wait() {
+                return Ok(Some(started.elapsed()));
+            }
+        }
+
+        // Record the failure
+        let failures = self.failures.fetch_add(1, Ordering::SeqCst) + 1;
+        warn!("write verification failed after {} attempts ({failures} failures total)", self.retries + 1);
+        Ok(None)
+    }
+
+    /// Observes the serial device's output
+    pub fn observe(&self, data: &[u8]) {
+        let mut expectation = self.expectation.lock().expect("Verifier is poisoned");
+        let Expectation { pattern: Some(pattern), window, .. } = &mut *expectation else {
+            return;
+        };
+
+        // Search the pattern, also across chunk boundaries
+        window.extend_from_slice(data);
+        if window.windows(pattern.len()).any(|candidate| candidate == pattern.as_slice()) {
+            *expectation = Expectation { pattern: None, window: Vec::new(), matched: true };
+            self.matched.notify_all();
+            return;
+        }
+
+        // Keep only the bytes that may contain the beginning of the pattern
+        let excess = window.len().saturating_sub(pattern.len() - 1);
+        window.drain(..excess);
+    }
+
+    /// Arms the expectation
+    fn expect(&self, pattern: &[u8]) {
+        let mut expectation = self.expectation.lock().expect("Verifier is poisoned");
+        *expectation = Expectation { pattern: Some(pattern.to_vec()), window: Vec::new(), matched: false };
+    }
+    /// Waits for the expectation to be matched and disarms it
+    fn wait(&self) -> bool {
+        let expectation = self.expectation.lock().expect("Verifier is poisoned");
+        let (mut expectation, _) =
+            (self.matched.wait_timeout_while(expectation, self.timeout, |expectation| !expectation.matched))
+                .expect("Verifier is poisoned");
+
+        // Disarm the expectation
+        let matched = expectation.matched;
+        *expectation = Expectation::default();
+        matched
+    }
+}
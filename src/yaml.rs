@@ -0,0 +1,551 @@
+//! Implements a parser for the YAML subset used by config files
+//!
+//! # Supported subset
+//! - a single document, optionally started by `---` and ended by `...`
+//! - block mappings with plain or quoted keys and block sequences, indented by spaces
+//! - flow sequences like `[1, 2]` and flow mappings like `{ a: 1 }`, which may span multiple lines
+//! - single-line plain, single-quoted and double-quoted scalars, resolved by the YAML 1.2 core schema (null, booleans,
+//!   decimal, hex and octal integers, floats and strings)
+//! - comments
+//!
+//! Everything else is rejected with an error instead of being misparsed, in particular anchors, aliases, tags,
+//! directives, complex keys (`? `), block scalars (`|`, `>`), multi-line scalars, multiple documents, plain scalars
+//! that start with a reserved indicator and plain scalars that contain `: `.
+
+use crate::error::Error;
+use std::{iter::Peekable, str::Chars};
+use toml::{value::Table, Value};
+
+/// Parses a YAML document into a TOML value
+///
+/// # Note
+/// Since TOML has no null, null mapping values are omitted so that they take their default, and null sequence items
+/// are rejected.
+pub fn from_yaml(yaml: &str) -> Result<Value, Error> {
+    let mut parser = Parser { lines: Line::split(yaml)?, pos: 0 };
+    let value = match parser.lines.first() {
+        Some(line) => parser.block(line.indent)?,
+        None => None,
+    };
+    if let Some(line) = parser.lines.get(parser.pos) {
+        return Err(line.error("unexpected line; check the indentation"));
+    }
+    value.ok_or_else(|| eio!("Invalid YAML (expected a document instead of null)"))
+}
+
+/// A non-empty line with its comment removed
+#[derive(Debug)]
+struct Line {
+    /// The line number
+    number: usize,
+    /// The amount of leading spaces
+    indent: usize,
+    /// The content after the indentation
+    content: String,
+}
+impl Line {
+    /// Splits a document into its non-empty lines
+    fn split(yaml: &str) -> Result<Vec<Self>, Error> {
+        let mut lines = Vec::new();
+        for (index, line) in yaml.lines().enumerate() {
+            let number = index + 1;
+            let content = line.trim_start_matches(' ');
+            let indent = line.len() - content.len();
+            let content = Self::strip_comment(content).trim_end();
+            match content {
+                "" => continue,
+                "---" if lines.is_empty() => continue,
+                "---" => return Err(eio!("Invalid YAML at line {number} (multiple documents are not supported)")),
+                "..." => break,
+                _ if content.starts_with('%') => {
+                    return Err(eio!("Invalid YAML at line {number} (directives are not supported)"))
+                }
+                _ if content == "?" || content.starts_with("? ") => {
+                    return Err(eio!("Invalid YAML at line {number} (complex mapping keys are not supported)"))
+                }
+                _ if content.starts_with('\t') => {
+                    return Err(eio!("Invalid YAML at line {number} (tabs cannot be used for indentation)"))
+                }
+                _ => lines.push(Self { number, indent, content: content.to_string() }),
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Removes a trailing comment outside of quotes
+    fn strip_comment(content: &str) -> &str {
+        let (mut quote, mut previous) = (None, ' ');
+        for (offset, char) in content.char_indices() {
+            match (quote, char) {
+                (None, '#') if previous.is_whitespace() => return &content[..offset],
+                (None, '"' | '\'') => quote = Some(char),
+                (Some('"'), '\\') if previous == '\\' => {
+                    // Treat an escaped backslash as plain character
+                    previous = ' ';
+                    continue;
+                }
+                (Some('"'), '"') if previous == '\\' => (),
+                (Some(open), _) if open == char => quote = None,
+                _ => (),
+            }
+            previous = char;
+        }
+        content
+    }
+
+    /// Whether the line is a sequence item
+    fn is_item(&self) -> bool {
+        self.content == "-" || self.content.starts_with("- ")
+    }
+
+    /// Creates an error for the line
+    fn error(&self, message: &str) -> Error {
+        eio!("Invalid YAML at line {} ({message})", self.number)
+    }
+}
+
+/// A parser for indentation-based block collections
+struct Parser {
+    /// The lines of the document
+    lines: Vec<Line>,
+    /// The index of the next line
+    pos: usize,
+}
+impl Parser {
+    /// Parses the block node that starts at the current line, or returns `None` for null
+    fn block(&mut self, indent: usize) -> Result<Option<Value>, Error> {
+        let line = &self.lines[self.pos];
+        if line.is_item() {
+            return self.sequence(indent).map(Some);
+        }
+        if Self::split_key(line)?.is_some() {
+            return self.mapping(indent).map(Some);
+        }
+
+        // Parse a scalar or flow collection on a line of its own
+        let (number, content) = (line.number, line.content.clone());
+        self.pos += 1;
+        self.inline(number, content)
+    }
+
+    /// Parses a block sequence whose items are indented by `indent`
+    fn sequence(&mut self, indent: usize) -> Result<Value, Error> {
+        let mut items = Vec::new();
+        while let Some(line) = self.lines.get(self.pos).filter(|line| line.indent == indent && line.is_item()) {
+            let number = line.number;
+            let rest = line.content[1..].trim_start_matches(' ');
+            let item = match rest.is_empty() {
+                true => {
+                    // Parse the item from the more indented lines below
+                    self.pos += 1;
+                    match self.lines.get(self.pos) {
+                        Some(next) if next.indent > indent => self.block(next.indent)?,
+                        _ => None,
+                    }
+                }
+                false => {
+                    // Parse the remainder of the line as if it was a line of its own, so that a mapping can continue on
+                    // the next lines
+                    let indent = indent + line.content.len() - rest.len();
+                    self.lines[self.pos] = Line { number, indent, content: rest.to_string() };
+                    self.block(indent)?
+                }
+            };
+            let item =
+                item.ok_or_else(|| eio!("Invalid YAML at line {number} (null sequence items are not supported)"))?;
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// Parses a block mapping whose keys are indented by `indent`
+    fn mapping(&mut self, indent: usize) -> Result<Value, Error> {
+        let mut table = Table::new();
+        while let Some(line) = self.lines.get(self.pos).filter(|line| line.indent == indent) {
+            let entry = match line.is_item() {
+                true => None,
+                false => Self::split_key(line)?,
+            };
+            let (key, rest) = entry.ok_or_else(|| line.error("expected a `key: value` entry"))?;
+            let number = line.number;
+            if table.contains_key(&key) {
+                return Err(line.error(&format!("duplicate key `{key}`")));
+            }
+
+            // Parse the value from the remainder of the line or from the lines below
+            self.pos += 1;
+            let value = match rest.is_empty() {
+                true => match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => self.block(next.indent)?,
+                    Some(next) if next.indent == indent && next.is_item() => self.sequence(indent).map(Some)?,
+                    _ => None,
+                },
+                false => self.inline(number, rest)?,
+            };
+            if let Some(value) = value {
+                table.insert(key, value);
+            }
+        }
+        Ok(Value::Table(table))
+    }
+
+    /// Splits a `key: value` entry into its key and the remainder of the line, or returns `None` if the line is no
+    /// mapping entry
+    fn split_key(line: &Line) -> Result<Option<(String, String)>, Error> {
+        // Parse a quoted key
+        let content = line.content.as_str();
+        if content.starts_with(['"', '\'']) {
+            let mut chars = content.chars().peekable();
+            let key = Flow::quoted(&mut chars).map_err(|e| line.error(e))?;
+            let rest: String = chars.collect();
+            return match rest.trim_start_matches(' ').strip_prefix(':') {
+                Some(value) if value.is_empty() || value.starts_with(' ') => {
+                    Ok(Some((key, value.trim_start().to_string())))
+                }
+                _ => Ok(None),
+            };
+        }
+
+        // Parse a plain key
+        if content.starts_with(['[', '{']) {
+            return Ok(None);
+        }
+        let separator = (content.match_indices(':'))
+            .find(|(offset, _)| content[offset + 1..].is_empty() || content[offset + 1..].starts_with(' '));
+        let entry = separator.map(|(offset, _)| {
+            let (key, value) = (&content[..offset], &content[offset + 1..]);
+            (key.trim_end().to_string(), value.trim_start().to_string())
+        });
+        if let Some((key, _)) = entry.as_ref() {
+            if key.starts_with(['&', '*', '!']) {
+                return Err(line.error("anchors, aliases and tags are not supported"));
+            }
+            if let Err(e) = Flow::check_plain(key) {
+                return Err(line.error(e));
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Parses a scalar or flow collection, joining the following lines if the flow collection spans multiple lines
+    fn inline(&mut self, number: usize, mut text: String) -> Result<Option<Value>, Error> {
+        while Flow::is_open(&text) {
+            let Some(next) = self.lines.get(self.pos) else {
+                break;
+            };
+            text.push(' ');
+            text.push_str(&next.content);
+            self.pos += 1;
+        }
+
+        // Parse the value
+        let mut chars = text.chars().peekable();
+        let value = Flow::value(&mut chars, false).map_err(|e| eio!("Invalid YAML at line {number} ({e})"))?;
+        match chars.next() {
+            Some(char) => Err(eio!("Invalid YAML at line {number} (unexpected character `{char}`)")),
+            None => Ok(value),
+        }
+    }
+}
+
+/// A parser for scalars and flow collections
+struct Flow;
+impl Flow {
+    /// Whether the text starts a flow collection that is not closed yet
+    fn is_open(text: &str) -> bool {
+        let (mut depth, mut quote, mut previous) = (0usize, None, ' ');
+        for char in text.chars() {
+            match (quote, char) {
+                (None, '[' | '{') => depth += 1,
+                (None, ']' | '}') => depth = depth.saturating_sub(1),
+                (None, '"' | '\'') => quote = Some(char),
+                (Some('"'), '"') if previous == '\\' => (),
+                (Some(open), _) if open == char => quote = None,
+                _ => (),
+            }
+            previous = match (previous, char) {
+                ('\\', '\\') => ' ',
+                _ => char,
+            };
+        }
+        text.starts_with(['[', '{']) && depth > 0
+    }
+
+    /// Parses a value, or returns `None` for null
+    fn value(chars: &mut Peekable<Chars>, nested: bool) -> Result<Option<Value>, &'static str> {
+        Self::skip_whitespace(chars);
+        let value = match chars.peek() {
+            Some('[') => Some(Self::sequence(chars)?),
+            Some('{') => Some(Self::mapping(chars)?),
+            Some('"' | '\'') => Some(Value::String(Self::quoted(chars)?)),
+            Some('|' | '>') => return Err("block scalars are not supported"),
+            Some('&' | '*') => return Err("anchors and aliases are not supported"),
+            Some('!') => return Err("tags are not supported"),
+            _ => Self::plain(chars, nested)?,
+        };
+        Self::skip_whitespace(chars);
+        Ok(value)
+    }
+
+    /// Parses a flow sequence like `[1, 2]`
+    fn sequence(chars: &mut Peekable<Chars>) -> Result<Value, &'static str> {
+        let mut items = Vec::new();
+        chars.next();
+        loop {
+            Self::skip_whitespace(chars);
+            if chars.peek().is_none() {
+                return Err("unterminated flow collection");
+            }
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Value::Array(items));
+            }
+            let item = Self::value(chars, true)?.ok_or("null sequence items are not supported")?;
+            items.push(item);
+            match chars.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err("expected `,` or `]`"),
+            }
+        }
+    }
+
+    /// Parses a flow mapping like `{ a: 1, b: 2 }`
+    fn mapping(chars: &mut Peekable<Chars>) -> Result<Value, &'static str> {
+        let mut table = Table::new();
+        chars.next();
+        loop {
+            // Parse the key
+            Self::skip_whitespace(chars);
+            if chars.peek().is_none() {
+                return Err("unterminated flow collection");
+            }
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(Value::Table(table));
+            }
+            let key = match chars.peek() {
+                Some('"' | '\'') => {
+                    let key = Self::quoted(chars)?;
+                    Self::skip_whitespace(chars);
+                    chars.next_if_eq(&':').ok_or("expected `:` after a flow mapping key")?;
+                    key
+                }
+                _ => match Self::plain_text(chars, true) {
+                    (key, true) => key.trim_end().to_string(),
+                    (_, false) => return Err("expected `:` after a flow mapping key"),
+                },
+            };
+            if table.contains_key(&key) {
+                return Err("duplicate key in flow mapping");
+            }
+
+            // Parse the value
+            if let Some(value) = Self::value(chars, true)? {
+                table.insert(key, value);
+            }
+            match chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return Err("expected `,` or `}`"),
+            }
+        }
+    }
+
+    /// Parses a single-quoted or double-quoted string
+    fn quoted(chars: &mut Peekable<Chars>) -> Result<String, &'static str> {
+        let mut string = String::new();
+        let quote = chars.next();
+        loop {
+            match (quote, chars.next()) {
+                (Some('\''), Some('\'')) if chars.next_if_eq(&'\'').is_some() => string.push('\''),
+                (Some('"'), Some('\\')) => string.push(Self::escape(chars)?),
+                (quote, Some(char)) if quote == Some(char) => return Ok(string),
+                (_, Some(char)) => string.push(char),
+                (_, None) => return Err("unterminated string"),
+            }
+        }
+    }
+
+    /// Parses the escape sequence after a backslash in a double-quoted string
+    fn escape(chars: &mut Peekable<Chars>) -> Result<char, &'static str> {
+        let escaped = match chars.next() {
+            Some('0') => '\0',
+            Some('a') => '\u{7}',
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('v') => '\u{b}',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('e') => '\u{1b}',
+            Some(' ') => ' ',
+            Some('"') => '"',
+            Some('/') => '/',
+            Some('\\') => '\\',
+            Some(prefix @ ('x' | 'u' | 'U')) => {
+                let len = match prefix {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let mut code = 0;
+                for _ in 0..len {
+                    let digit = chars.next().and_then(|char| char.to_digit(16)).ok_or("invalid hex escape")?;
+                    code = code << 4 | digit;
+                }
+                char::from_u32(code).ok_or("invalid unicode escape")?
+            }
+            _ => return Err("invalid escape sequence"),
+        };
+        Ok(escaped)
+    }
+
+    /// Parses and resolves a plain scalar
+    fn plain(chars: &mut Peekable<Chars>, nested: bool) -> Result<Option<Value>, &'static str> {
+        let (text, false) = Self::plain_text(chars, nested) else {
+            return Err("implicit mappings in flow sequences are not supported");
+        };
+        let text = text.trim_end();
+        if !nested {
+            Self::check_plain(text)?;
+        }
+        let (sign, digits) = match text.strip_prefix(['-', '+']) {
+            Some(digits) => (&text[..1], digits),
+            None => ("", text),
+        };
+        let value = match text {
+            "" | "~" | "null" | "Null" | "NULL" => return Ok(None),
+            "true" | "True" | "TRUE" => Value::Boolean(true),
+            "false" | "False" | "FALSE" => Value::Boolean(false),
+            ".nan" | ".NaN" | ".NAN" => Value::Float(f64::NAN),
+            _ if matches!(digits, ".inf" | ".Inf" | ".INF") => match sign {
+                "-" => Value::Float(f64::NEG_INFINITY),
+                _ => Value::Float(f64::INFINITY),
+            },
+            _ if text.starts_with("0x") => Self::radix(text, &text[2..], 16),
+            _ if text.starts_with("0o") => Self::radix(text, &text[2..], 8),
+            _ if !digits.starts_with(|char: char| char.is_ascii_digit() || char == '.') => {
+                Value::String(text.to_string())
+            }
+            _ => match (text.parse().map(Value::Integer), text.parse().map(Value::Float)) {
+                (Ok(integer), _) => integer,
+                (_, Ok(float)) => float,
+                _ => Value::String(text.to_string()),
+            },
+        };
+        Ok(Some(value))
+    }
+
+    /// Checks that the text is a valid plain scalar outside of a flow collection
+    ///
+    /// # Note
+    /// YAML rejects these scalars, so they are rejected instead of being read as strings.
+    fn check_plain(text: &str) -> Result<(), &'static str> {
+        if text.starts_with([']', '}', ',', '%', '@', '`']) || text == "-" || text.starts_with("- ") {
+            return Err("plain scalars cannot start with a reserved indicator; quote the value");
+        }
+        if text.contains(": ") || text.ends_with(':') {
+            return Err("plain scalars cannot contain `: `; quote the value or check the indentation");
+        }
+        Ok(())
+    }
+
+    /// Parses a hex or octal integer, or falls back to a string
+    fn radix(text: &str, digits: &str, radix: u32) -> Value {
+        match i64::from_str_radix(digits, radix) {
+            Ok(integer) => Value::Integer(integer),
+            Err(_) => Value::String(text.to_string()),
+        }
+    }
+
+    /// Collects the text of a plain scalar, which ends at flow indicators if the scalar is nested in a flow collection,
+    /// and returns whether it was terminated by the `:` of a flow mapping key
+    fn plain_text(chars: &mut Peekable<Chars>, nested: bool) -> (String, bool) {
+        let mut text = String::new();
+        while let Some(char) = chars.next_if(|char| !nested || !matches!(char, ',' | ']' | '}')) {
+            if nested && char == ':' && matches!(chars.peek(), None | Some(' ' | ',' | ']' | '}')) {
+                return (text, true);
+            }
+            text.push(char);
+        }
+        (text, false)
+    }
+
+    /// Skips spaces
+    fn skip_whitespace(chars: &mut Peekable<Chars>) {
+        while chars.next_if(|char| *char == ' ').is_some() {
+            // Consume the whitespace
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the YAML document and asserts that it equals the TOML document
+    fn assert_parses(yaml: &str, toml: &str) {
+        let expected: Value = toml::from_str(toml).expect("Invalid TOML in test");
+        assert_eq!(from_yaml(yaml).expect("Failed to parse YAML"), expected);
+    }
+
+    /// Asserts that the YAML document is rejected with an error that contains `message`
+    fn assert_rejects(yaml: &str, message: &str) {
+        let error = from_yaml(yaml).expect_err("Invalid YAML has been accepted").to_string();
+        assert!(error.contains(message), "Unexpected error: {error}");
+    }
+
+    #[test]
+    fn block_collections() {
+        let yaml =
+            "---\nserial:\n  path: /dev/ttyUSB0 # comment\n  baudrate: 115200\nroutes:\n- name: a\n  ports: [1, 2]\n\
+            - name: b\n  flags:\n    - x\n    - y\n...\nignored: true\n";
+        let toml = "serial = { path = '/dev/ttyUSB0', baudrate = 115200 }\n\
+            routes = [{ name = 'a', ports = [1, 2] }, { name = 'b', flags = ['x', 'y'] }]";
+        assert_parses(yaml, toml);
+    }
+
+    #[test]
+    fn flow_collections() {
+        let yaml = "a: { b: [1, 'two', \"three\"], c: {} }\nd: [\n  1,\n  2,\n]\n";
+        assert_parses(yaml, "a = { b = [1, 'two', 'three'], c = {} }\nd = [1, 2]");
+    }
+
+    #[test]
+    fn scalars() {
+        let yaml = "bool: true\nint: -42\nhex: 0x1F\noctal: 0o17\nfloat: 1.5e3\ninf: .inf\nstring: hello world\n\
+            number_string: '42'\nnull_value: ~\nsingle: 'it''s'\ndouble: \"tab\\tquote\\\" unicode\\u00e9\"\n";
+        let toml = "bool = true\nint = -42\nhex = 31\noctal = 15\nfloat = 1500.0\ninf = inf\nstring = 'hello world'\n\
+            number_string = '42'\nsingle = \"it's\"\ndouble = \"tab\\tquote\\\" unicode\\u00e9\"";
+        assert_parses(yaml, toml);
+    }
+
+    #[test]
+    fn rejects_unsupported_constructs() {
+        assert_rejects("a: &anchor 1\n", "anchors and aliases are not supported");
+        assert_rejects("a: *alias\n", "anchors and aliases are not supported");
+        assert_rejects("&anchor a: 1\n", "anchors, aliases and tags are not supported");
+        assert_rejects("a: !!str 1\n", "tags are not supported");
+        assert_rejects("a: |\n  text\n", "block scalars are not supported");
+        assert_rejects("%YAML 1.2\n---\na: 1\n", "directives are not supported");
+        assert_rejects("? a\n: 1\n", "complex mapping keys are not supported");
+        assert_rejects("a: 1\n---\nb: 2\n", "multiple documents are not supported");
+        assert_rejects("a: 'unterminated\n  string'\n", "");
+    }
+
+    #[test]
+    fn rejects_invalid_plain_scalars() {
+        assert_rejects("a: b: c\n", "plain scalars cannot contain `: `");
+        assert_rejects("a: b:\n", "plain scalars cannot contain `: `");
+        assert_rejects("a: - b\n", "reserved indicator");
+        assert_rejects("a: @b\n", "reserved indicator");
+        assert_rejects("a: multi-line\n  plain scalar\n", "check the indentation");
+    }
+
+    #[test]
+    fn rejects_invalid_structure() {
+        assert_rejects("a: 1\na: 2\n", "");
+        assert_rejects("a:\n  b: 1\n   c: 2\n", "check the indentation");
+        assert_rejects("- 1\n- ~\n", "");
+        assert_rejects("~\n", "expected a document instead of null");
+    }
+}